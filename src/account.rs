@@ -0,0 +1,395 @@
+use crate::{money::round_dollars, strategy::AllocationStrategy};
+use anyhow::{ensure, Context, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{fmt, fs, str::FromStr};
+
+/// AccountKind is the tax treatment of an account, which decides where assets are placed.  The
+/// account types used before account kinds existed are accepted as aliases.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountKind {
+    #[serde(alias = "brokerage")]
+    Taxable,
+    #[serde(alias = "traditional_ira")]
+    TaxDeferred,
+    #[serde(alias = "roth_ira")]
+    TaxFree,
+    Hsa,
+    Education,
+}
+
+impl AccountKind {
+    /// is_tax_free returns whether withdrawals from the account are not taxed, such as a Roth IRA
+    /// or an HSA, which makes it the best place for the assets with the largest growth
+    pub fn is_tax_free(&self) -> bool {
+        matches!(self, AccountKind::TaxFree | AccountKind::Hsa)
+    }
+}
+
+impl fmt::Display for AccountKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountKind::Taxable => write!(f, "Taxable"),
+            AccountKind::TaxDeferred => write!(f, "Tax-deferred"),
+            AccountKind::TaxFree => write!(f, "Tax-free"),
+            AccountKind::Hsa => write!(f, "HSA"),
+            AccountKind::Education => write!(f, "Education"),
+        }
+    }
+}
+
+/// CashReserve is the cash kept within the settlement fund of an account, such as an emergency
+/// fund or a buffer for a pending distribution.  The reserve is a dollar amount or a percentage of
+/// the value of the account and is carved out before the allocation is applied.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "CashReserveValue")]
+pub enum CashReserve {
+    Dollars(Decimal),
+    Percent(Decimal),
+}
+
+// CashReserveValue allows a cash reserve to be written as a number of dollars or a string such as
+// "10%" within a TOML file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CashReserveValue {
+    Dollars(Decimal),
+    Text(String),
+}
+
+impl CashReserve {
+    /// amount returns the dollars held in reserve within an account of the value
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::account::CashReserve;
+    ///
+    /// let dollars = "$5000".parse::<CashReserve>().unwrap();
+    /// assert_eq!(dollars.amount(dec!(20000)), dec!(5000));
+    ///
+    /// let percent = "2.5%".parse::<CashReserve>().unwrap();
+    /// assert_eq!(percent.amount(dec!(20000)), dec!(500));
+    ///
+    /// assert!("-100".parse::<CashReserve>().is_err());
+    /// assert!("150%".parse::<CashReserve>().is_err());
+    /// ```
+    pub fn amount(&self, account_value: Decimal) -> Decimal {
+        match self {
+            CashReserve::Dollars(dollars) => *dollars,
+            CashReserve::Percent(percent) => {
+                round_dollars(account_value * percent / Decimal::ONE_HUNDRED)
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self {
+            CashReserve::Dollars(dollars) => ensure!(
+                !dollars.is_sign_negative(),
+                "Cash reserve cannot be negative: ${}",
+                dollars
+            ),
+            CashReserve::Percent(percent) => ensure!(
+                !percent.is_sign_negative() && *percent <= Decimal::ONE_HUNDRED,
+                "Cash reserve needs to be between 0% and 100%: {}%",
+                percent
+            ),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CashReserve {
+    type Err = anyhow::Error;
+
+    fn from_str(cash_reserve: &str) -> Result<Self> {
+        let trimmed = cash_reserve.trim();
+        let reserve = match trimmed.strip_suffix('%') {
+            Some(percent) => CashReserve::Percent(
+                percent
+                    .trim()
+                    .parse::<Decimal>()
+                    .with_context(|| format!("Invalid cash reserve: {}", cash_reserve))?,
+            ),
+            None => CashReserve::Dollars(
+                trimmed
+                    .trim_start_matches('$')
+                    .replace(',', "")
+                    .parse::<Decimal>()
+                    .with_context(|| format!("Invalid cash reserve: {}", cash_reserve))?,
+            ),
+        };
+        reserve.validate()?;
+        Ok(reserve)
+    }
+}
+
+impl TryFrom<CashReserveValue> for CashReserve {
+    type Error = anyhow::Error;
+
+    fn try_from(value: CashReserveValue) -> Result<Self> {
+        match value {
+            CashReserveValue::Dollars(dollars) => {
+                let reserve = CashReserve::Dollars(dollars);
+                reserve.validate()?;
+                Ok(reserve)
+            }
+            CashReserveValue::Text(text) => text.parse(),
+        }
+    }
+}
+
+/// Owner is a member of the household who owns accounts.  The birth year is used to calculate the
+/// minimum distribution from the owner's tax-deferred accounts.  The allocation of the owner's
+/// share of the retirement accounts follows their strategy, which uses their birth year or
+/// retirement year.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Owner {
+    pub name: String,
+    #[serde(default)]
+    pub birth_year: Option<u32>,
+    #[serde(default)]
+    pub retirement_year: Option<i32>,
+    #[serde(default)]
+    pub strategy: Option<AllocationStrategy>,
+}
+
+/// Account is a Vanguard account along with its kind, the owner of the account, an optional name
+/// used when printing, and an optional glide path and strategy which replace those of the owner
+/// and household.  An account which is balanced on its own, such as a brokerage or education
+/// account, may have the goal year it is saved for, which it follows its glide path to.  The cash
+/// reserve is kept within the settlement fund of the account.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Account {
+    pub number: u32,
+    pub kind: AccountKind,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub glide_path: Option<String>,
+    #[serde(default)]
+    pub strategy: Option<AllocationStrategy>,
+    #[serde(default)]
+    pub goal_year: Option<i32>,
+    #[serde(default)]
+    pub cash_reserve: Option<CashReserve>,
+    #[serde(default)]
+    pub whole_shares: Option<bool>,
+}
+
+impl Account {
+    /// new creates an account with the name of the account and without an owner
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::account::{Account, AccountKind};
+    ///
+    /// let roth = Account::new(22222222, AccountKind::TaxFree, Some("Roth IRA"));
+    /// assert_eq!(roth.to_string(), "Roth IRA (22222222)");
+    ///
+    /// let hsa = Account::new(44444444, AccountKind::Hsa, None);
+    /// assert_eq!(hsa.to_string(), "HSA (44444444)");
+    /// ```
+    pub fn new(number: u32, kind: AccountKind, name: Option<&str>) -> Self {
+        Account {
+            number,
+            kind,
+            name: name.map(|name| name.to_string()),
+            owner: None,
+            glide_path: None,
+            strategy: None,
+            goal_year: None,
+            cash_reserve: None,
+            whole_shares: None,
+        }
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.name, &self.owner) {
+            (Some(name), _) => write!(f, "{} ({})", name, self.number),
+            (None, Some(owner)) => write!(f, "{} {} ({})", owner, self.kind, self.number),
+            (None, None) => write!(f, "{} ({})", self.kind, self.number),
+        }
+    }
+}
+
+/// Accounts holds all Vanguard accounts which are rebalanced, in the order they are printed,
+/// along with the owners of the household.  The retirement accounts of every owner are balanced
+/// together as one household.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Accounts {
+    #[serde(default, rename = "owner")]
+    owners: Vec<Owner>,
+    #[serde(default, rename = "account")]
+    accounts: Vec<Account>,
+}
+
+impl Accounts {
+    /// new creates an empty Accounts struct
+    pub fn new() -> Self {
+        Accounts {
+            owners: Vec::new(),
+            accounts: Vec::new(),
+        }
+    }
+
+    /// from_toml parses the owners and accounts from the contents of a TOML file
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     account::{AccountKind, Accounts, CashReserve},
+    ///     strategy::AllocationStrategy,
+    /// };
+    ///
+    /// let accounts = Accounts::from_toml(
+    ///     r#"
+    ///     [[owner]]
+    ///     name = "Sam"
+    ///     birth_year = 1958
+    ///
+    ///     [[owner]]
+    ///     name = "Alex"
+    ///     retirement_year = 2040
+    ///     strategy = "bond_tent"
+    ///
+    ///     [[account]]
+    ///     number = 22222222
+    ///     kind = "tax_free"
+    ///     owner = "Sam"
+    ///
+    ///     [[account]]
+    ///     number = 55555555
+    ///     kind = "tax_deferred"
+    ///     name = "Alex SEP-IRA"
+    ///     owner = "Alex"
+    ///     cash_reserve = "5%"
+    ///
+    ///     [[account]]
+    ///     number = 66666666
+    ///     kind = "education"
+    ///     goal_year = 2031
+    ///     "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(accounts.accounts().len(), 3);
+    /// assert_eq!(accounts.accounts()[0].to_string(), "Sam Tax-free (22222222)");
+    /// assert_eq!(accounts.of_kind(AccountKind::TaxDeferred)[0].number, 55555555);
+    /// assert_eq!(accounts.owner("Sam").unwrap().birth_year, Some(1958));
+    /// assert_eq!(accounts.of_kind(AccountKind::Education)[0].goal_year, Some(2031));
+    /// assert_eq!(
+    ///     accounts.account(55555555).unwrap().cash_reserve,
+    ///     Some(CashReserve::Percent(dec!(5)))
+    /// );
+    /// assert_eq!(accounts.owner("Alex").unwrap().strategy, Some(AllocationStrategy::BondTent));
+    /// assert!(Accounts::from_toml("[[account]]\nnumber = 1\nkind = \"pension\"").is_err());
+    /// assert!(Accounts::from_toml("[[account]]\nnumber = 1\nkind = \"hsa\"\nowner = \"Kim\"").is_err());
+    /// assert!(Accounts::from_toml("[[owner]]\nname = \"Kim\"\nstrategy = \"all_in\"").is_err());
+    /// ```
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let parsed: Accounts = toml::from_str(contents).context("Unable to parse accounts")?;
+        let mut accounts = Accounts::new();
+        for owner in parsed.owners {
+            accounts.add_owner(owner)?;
+        }
+        for account in parsed.accounts {
+            accounts.add_account(account)?;
+        }
+        Ok(accounts)
+    }
+
+    /// from_file reads an accounts TOML file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Accounts file not found: {}", path))?;
+        Self::from_toml(&contents).with_context(|| format!("Invalid accounts: {}", path))
+    }
+
+    /// add_owner adds a member of the household
+    ///
+    /// # Errors
+    ///
+    /// Errors when an owner with the same name has already been added
+    pub fn add_owner(&mut self, owner: Owner) -> Result<()> {
+        ensure!(
+            self.owner(&owner.name).is_none(),
+            "Owner {} is listed more than once",
+            owner.name
+        );
+        self.owners.push(owner);
+        Ok(())
+    }
+
+    /// owners returns all members of the household in the order they were added
+    pub fn owners(&self) -> &[Owner] {
+        &self.owners
+    }
+
+    /// owner returns the member of the household with the name
+    pub fn owner(&self, name: &str) -> Option<&Owner> {
+        self.owners.iter().find(|owner| owner.name == name)
+    }
+
+    /// add_account adds an account to the end of the accounts
+    ///
+    /// # Errors
+    ///
+    /// Errors when the account number has already been added or the owner of the account has not
+    /// been added
+    pub fn add_account(&mut self, account: Account) -> Result<()> {
+        ensure!(
+            self.account(account.number).is_none(),
+            "Account {} is listed more than once",
+            account.number
+        );
+        if let Some(owner) = &account.owner {
+            ensure!(
+                self.owner(owner).is_some(),
+                "Account {} is owned by {}, who is not listed as an owner",
+                account.number,
+                owner
+            );
+        }
+        self.accounts.push(account);
+        Ok(())
+    }
+
+    /// accounts returns all accounts in the order they were added
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    /// account returns the account with the account number
+    pub fn account(&self, number: u32) -> Option<&Account> {
+        self.accounts
+            .iter()
+            .find(|account| account.number == number)
+    }
+
+    /// of_kind returns all accounts of the account kind
+    pub fn of_kind(&self, kind: AccountKind) -> Vec<&Account> {
+        self.accounts
+            .iter()
+            .filter(|account| account.kind == kind)
+            .collect()
+    }
+
+    /// is_empty returns whether no accounts have been added
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+}
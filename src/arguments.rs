@@ -1,47 +1,77 @@
+use crate::{
+    account::{Account, AccountKind, Accounts, CashReserve, Owner},
+    asset::AllocationWeights,
+    costs::TradeCosts,
+    glide::{GlidePath, GlidePaths, DEFAULT_GLIDE_PATH},
+    harvest::HarvestPairs,
+    location::LocationAssumptions,
+    lots::{LotSelection, TaxRates},
+    money::DEFAULT_SHARE_DECIMALS,
+    policy::{Band, BandMode, RebalancePolicy, TaxPolicy},
+    strategy::{AllocationStrategy, Investor, DEFAULT_STRATEGY},
+};
+use anyhow::{anyhow, Result};
 use clap::{crate_version, App, Arg};
+use rust_decimal::Decimal;
 use time::OffsetDateTime;
 
 /// Args struct holds all CLI argument values passed
 ///
 /// # Panic
 ///
-/// Panics if percent stock and bond do not add up to 100
+/// Panics if percent stock, bond and inflation protected do not add up to 100
 #[derive(Clone)]
 pub struct Args {
     pub csv_path: String, // Path of the downloaded vanguard transactions file
     pub retirement_year_option: Option<i32>,
-    pub percent_stock_brokerage: f32, // Percent of stocks for brokerage account
-    pub percent_bond_brokerage: f32,  // Percent of bonds for brokerage account
-    pub percent_stock_retirement_option: Option<f32>, // Percent of stock for retirement account
-    pub percent_bond_retirement_option: Option<f32>, // Percent bond for retirement account
-    pub brokerage_cash_add: f32,      // Amount of cash added to brokerage account
-    pub brokerage_us_stock_add: f32,
-    pub brokerage_us_bond_add: f32,
-    pub brokerage_int_stock_add: f32,
-    pub brokerage_int_bond_add: f32,
-    pub traditional_cash_add: f32,
-    pub traditional_us_stock_add: f32,
-    pub traditional_us_bond_add: f32,
-    pub traditional_int_stock_add: f32,
-    pub traditional_int_bond_add: f32,
-    pub roth_cash_add: f32,
-    pub roth_us_stock_add: f32,
-    pub roth_us_bond_add: f32,
-    pub roth_int_stock_add: f32,
-    pub roth_int_bond_add: f32,
+    pub percent_stock_brokerage: Decimal, // Percent of stocks for brokerage account
+    pub percent_bond_brokerage: Decimal,  // Percent of bonds for brokerage account
+    pub percent_inflation_brokerage: Decimal, // Percent of inflation protected for brokerage account
+    pub brokerage_goal_year_option: Option<i32>, // Goal year the brokerage account is saved for
+    pub brokerage_glide_path: String, // Glide path of the brokerage account to its goal year
+    pub percent_stock_retirement_option: Option<Decimal>, // Percent of stock for retirement account
+    pub percent_bond_retirement_option: Option<Decimal>, // Percent bond for retirement account
+    pub cash_adds: Vec<(u32, Decimal)>, // Amount of cash added to each account number
+    pub cash_reserves: Vec<(u32, CashReserve)>, // Cash kept within each account number
+    pub withdrawals: Vec<(u32, Decimal)>, // Amount raised by selling within each account number
+    pub withdraw_by_lots: bool, // Whether or not withdrawals first sell lots without short term gains
+    pub accounts_path_option: Option<String>, // Path of the accounts TOML file
     pub brok_acct_option: Option<u32>, // Vanguard brokerage account number
     pub trad_acct_option: Option<u32>, // Vanguard traditional IRA account number
     pub roth_acct_option: Option<u32>, // Vanguard roth IRA account number
-    pub output: bool,                  // Whether or not to output calculations to a txt file
-    pub age_option: Option<u32>,        // age
-    pub distribution_year: u32,        // age
+    pub output: bool,           // Whether or not to output calculations to a txt file
+    pub age_option: Option<u32>, // age
+    pub birth_year_option: Option<u32>, // Birth year of the accounts without an owner
+    pub distribution_year: u32, // age
     pub distribution_table_path: String,
     pub use_brokerage_retirement: bool,
+    pub fund_universe_path_option: Option<String>, // Path of the fund universe TOML file
+    pub share_decimals: u32, // Number of decimals that share quantities are rounded to
+    pub whole_shares: bool,  // Whether or not ETF orders are rounded to whole shares
+    pub keep_money_market_option: Option<String>, // Money market kept as a holding
+    pub lots_path_option: Option<String>, // Path of the vanguard unrealized gains file
+    pub outside_holdings_path_option: Option<String>, // Path of the outside holdings TOML file
+    pub weights_path_option: Option<String>, // Path of the allocation weights TOML file
+    pub glide_path: String,  // Glide path of the household's retirement accounts
+    pub glide_paths_path_option: Option<String>, // Path of the glide paths TOML file
+    pub strategy: AllocationStrategy, // Allocation strategy of the household's retirement accounts
+    pub compare_strategies: bool, // Whether or not to print the allocation of each strategy
+    pub policy: RebalancePolicy, // Tolerance bands positions drift within before they are traded
+    pub tax_rates: TaxRates, // Short term and ordinary income, and long term tax rates
+    pub tax_policy_option: Option<TaxPolicy>, // Lot selection and tax limit of the taxable accounts
+    pub harvest: bool,       // Whether or not to scan the taxable accounts for losses to harvest
+    pub harvest_threshold: Decimal, // Smallest loss of a fund which is harvested
+    pub harvest_pairs_path_option: Option<String>, // Path of the harvest substitute pairs TOML file
+    pub asset_location_path_option: Option<String>, // Path of the asset location assumptions TOML file
+    pub cost_aware: bool, // Whether or not to drop the trades which cost more than they are worth
+    pub trade_costs_path_option: Option<String>, // Path of the trade costs TOML file
+    pub execution_plan: bool, // Whether or not to order the trades of each account by when they can be made
 }
 
 impl Args {
     pub fn new() -> Self {
         let this_year = OffsetDateTime::now_utc().year().to_string();
+        let default_share_decimals = DEFAULT_SHARE_DECIMALS.to_string();
         let args = App::new("Vanguard Stock Adjustment")
             .version(crate_version!())
             .author("Rory Coffey <coffeyrt@gmail.com")
@@ -57,6 +87,36 @@ impl Args {
                     .takes_value(true)
                     .help("Path of the minimum distribution csv from the IRS"),
             )
+            .arg(
+                Arg::with_name("unrealized-gains")
+                    .long("unrealized-gains")
+                    .takes_value(true)
+                    .help("Path of the unrealized gains CSV download from Vanguard with the tax lots of the taxable accounts"),
+            )
+            .arg(
+                Arg::with_name("outside-holdings")
+                    .long("outside-holdings")
+                    .takes_value(true)
+                    .help("Path of a TOML file listing holdings outside of Vanguard.  Used for calculating stock/bond ratios."),
+            )
+            .arg(
+                Arg::with_name("allocation-weights")
+                    .long("allocation-weights")
+                    .takes_value(true)
+                    .help("Path of a TOML file with the weights of each sub class and fund within the stock, bond and inflation protected allocations"),
+            )
+            .arg(
+                Arg::with_name("fund-universe")
+                    .long("fund-universe")
+                    .takes_value(true)
+                    .help("Path of a fund universe TOML file which replaces the built-in fund lineup"),
+            )
+            .arg(
+                Arg::with_name("keep-money-market")
+                    .long("keep-money-market")
+                    .takes_value(true)
+                    .help("Money market fund kept as a holding at its current value rather than pooled as investable cash"),
+            )
             .arg(
                 Arg::with_name("retirement-year")
                     .long("retirement-year")
@@ -65,148 +125,252 @@ impl Args {
                     .help("Retirment year in the format of YYYY"),
             )
             .arg(
-                Arg::with_name("percent-bond-brokerage")
-                    .long("bond-percent-brokerage")
+                Arg::with_name("glide-path")
+                    .long("glide-path")
                     .takes_value(true)
-                    .default_value("40")
-                    .help("Percentage to allocate in bonds in the brokerage account"),
+                    .default_value(DEFAULT_GLIDE_PATH)
+                    .help("Glide path followed to the retirement year: current, conservative, rising_equity or one within the glide paths file"),
             )
             .arg(
-                Arg::with_name("percent-stock-brokerage")
-                    .long("stock-percent-brokerage")
+                Arg::with_name("glide-paths")
+                    .long("glide-paths")
                     .takes_value(true)
-                    .default_value("60")
-                    .help("Percentage to allocate in stocks in the brokerage account"),
+                    .help("Path of a TOML file with glide path tables of years to retirement to stock/bond/inflation protected percentages"),
             )
             .arg(
-                Arg::with_name("percent-bond-retirement")
-                    .long("bond-percent-retirement")
+                Arg::with_name("strategy")
+                    .long("strategy")
                     .takes_value(true)
-                    .help("Percentage to allocate in bonds in the retirement account"),
+                    .default_value(DEFAULT_STRATEGY)
+                    .help("Allocation strategy of the retirement accounts: glide_path, age_in_bonds, <base>_minus_age such as 110_minus_age, bond_tent or fixed:<stock>/<bond>[/<inflation>]"),
             )
             .arg(
-                Arg::with_name("percent-stock-retirement")
-                    .long("stock-percent-retirement")
+                Arg::with_name("compare-strategies")
+                    .long("compare-strategies")
+                    .takes_value(false)
+                    .help("Print the allocation each strategy gives every owner of the household"),
+            )
+            .arg(
+                Arg::with_name("asset-class-bands")
+                    .long("asset-class-bands")
                     .takes_value(true)
-                    .help("Percentage to allocate in stocks in the retirement account"),
+                    .help("Tolerance band of each asset class as <absolute percent>/<relative percent>, such as 5/25.  Funds are only traded once their asset class drifts outside of the band"),
             )
             .arg(
-                Arg::with_name("add-cash-brokerage")
-                    .long("adjust-cash-brokerage")
-                    .short("B")
-                    .allow_hyphen_values(true)
+                Arg::with_name("fund-bands")
+                    .long("fund-bands")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Amount of cash added to or withdraw from the brokerage account"),
+                    .help("Tolerance band of each fund as <absolute percent>/<relative percent>, such as 5/25.  Funds are only traded once they drift outside of the band"),
             )
             .arg(
-                Arg::with_name("add-us-stock-brokerage")
-                    .long("add-us-stock-brokerage")
+                Arg::with_name("band-mode")
+                    .long("band-mode")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Value of brokerage US stock held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .default_value("target")
+                    .possible_values(&["target", "edge"])
+                    .help("Whether positions outside of their band are traded back to their target or to the edge of the band"),
             )
             .arg(
-                Arg::with_name("add-us-bond-brokerage")
-                    .long("add-us-bond-brokerage")
+                Arg::with_name("lot-selection")
+                    .long("lot-selection")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Value of brokerage US bond held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .possible_values(&["hifo", "loss_first", "long_term_first"])
+                    .help("Order the tax lots of the taxable accounts are sold in, which also estimates the gains and tax of the sales.  Needs the unrealized gains file"),
             )
             .arg(
-                Arg::with_name("add-int-stock-brokerage")
-                    .long("add-int-stock-brokerage")
+                Arg::with_name("short-term-tax-rate")
+                    .long("short-term-tax-rate")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Value of brokerage international stock held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .default_value("24")
+                    .help("Percent that short term gains and ordinary income, such as interest, are taxed at"),
             )
             .arg(
-                Arg::with_name("add-int-bond-brokerage")
-                    .long("add-int-bond-brokerage")
+                Arg::with_name("long-term-tax-rate")
+                    .long("long-term-tax-rate")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Value of brokerage international bond held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .default_value("15")
+                    .help("Percent that long term gains and qualified dividends are taxed at"),
             )
             .arg(
-                Arg::with_name("add-cash-traditional")
-                    .long("adjust-cash-traditional")
-                    .short("T")
-                    .allow_hyphen_values(true)
+                Arg::with_name("max-tax-cost")
+                    .long("max-tax-cost")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Amount of cash added to or withdraw from the traditional IRA account"),
+                    .requires("lot-selection")
+                    .help("Maximum estimated tax of the sales within each taxable account, leaving drift within the funds which are not sold"),
+            )
+            .arg(
+                Arg::with_name("harvest")
+                    .long("harvest")
+                    .takes_value(false)
+                    .requires("unrealized-gains")
+                    .help("Scan the tax lots of the taxable accounts for losses to harvest into a substitute fund, checking every account for wash sales"),
             )
             .arg(
-                Arg::with_name("add-us-stock-traditional")
-                    .long("add-us-stock-traditional")
+                Arg::with_name("harvest-threshold")
+                    .long("harvest-threshold")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Value of traditional IRA US stock held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .default_value("1000")
+                    .help("Smallest loss across the lots of a fund which is proposed to be harvested"),
             )
             .arg(
-                Arg::with_name("add-us-bond-traditional")
-                    .long("add-us-bond-traditional")
+                Arg::with_name("harvest-pairs")
+                    .long("harvest-pairs")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Value of traditional IRA US bond held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .help("Path of a TOML file with the substitute fund of each fund, which replaces the built-in substitutes"),
             )
             .arg(
-                Arg::with_name("add-int-stock-traditional")
-                    .long("add-int-stock-traditional")
+                Arg::with_name("asset-location")
+                    .long("asset-location")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Value of traditional IRA international stock held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .help("Path of a TOML file with the expected return, yield and placement preferences of each fund used to place the funds within the retirement accounts"),
+            )
+            .arg(
+                Arg::with_name("cost-aware")
+                    .long("cost-aware")
+                    .takes_value(false)
+                    .help("Drop the trades which cost more than the drift they remove is worth, or are smaller than the minimum trade, merging their cash into the other trades, and show the estimated trade costs"),
+            )
+            .arg(
+                Arg::with_name("trade-costs")
+                    .long("trade-costs")
+                    .takes_value(true)
+                    .requires("cost-aware")
+                    .help("Path of a TOML file with the commission, bid-ask spread, minimum trade and mutual fund fee of the accounts and funds"),
+            )
+            .arg(
+                Arg::with_name("execution-plan")
+                    .long("execution-plan")
+                    .takes_value(false)
+                    .help("List the trades of each account in the order they can be made: sales, money market sweeps, then purchases once their cash settles"),
+            )
+            .arg(
+                Arg::with_name("buy-only")
+                    .long("buy-only")
+                    .takes_value(false)
+                    .help("Never sell, and only invest the cash of each account, including cash added with the adjust cash flags, into the funds furthest below their targets"),
+            )
+            .arg(
+                Arg::with_name("whole-shares")
+                    .long("whole-shares")
+                    .takes_value(false)
+                    .help("Round the ETF orders of every account to whole shares without spending more than the cash held.  An account within the accounts file keeps fractional shares with whole_shares = false"),
             )
             .arg(
-                Arg::with_name("add-int-bond-traditional")
-                    .long("add-int-bond-traditional")
+                Arg::with_name("percent-bond-brokerage")
+                    .long("bond-percent-brokerage")
+                    .takes_value(true)
+                    .default_value("40")
+                    .help("Percentage to allocate in bonds in the brokerage account"),
+            )
+            .arg(
+                Arg::with_name("percent-inflation-brokerage")
+                    .long("inflation-percent-brokerage")
                     .takes_value(true)
                     .default_value("0")
-                    .help("Value of traditional IRA international bond held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .help("Percentage to allocate in inflation protected bonds in the brokerage account"),
             )
             .arg(
-                Arg::with_name("add-cash-roth")
-                    .long("adjust-cash-roth")
-                    .short("R")
+                Arg::with_name("brokerage-goal-year")
+                    .long("brokerage-goal-year")
+                    .takes_value(true)
+                    .help("Year of the goal the brokerage account is saved for, such as a house purchase, in the format of YYYY.  The brokerage account follows its glide path to the goal year instead of its percentages"),
+            )
+            .arg(
+                Arg::with_name("brokerage-glide-path")
+                    .long("brokerage-glide-path")
+                    .takes_value(true)
+                    .default_value(DEFAULT_GLIDE_PATH)
+                    .help("Glide path the brokerage account follows to its goal year"),
+            )
+            .arg(
+                Arg::with_name("percent-stock-brokerage")
+                    .long("stock-percent-brokerage")
+                    .takes_value(true)
+                    .default_value("60")
+                    .help("Percentage to allocate in stocks in the brokerage account"),
+            )
+            .arg(
+                Arg::with_name("percent-bond-retirement")
+                    .long("bond-percent-retirement")
+                    .takes_value(true)
+                    .help("Percentage to allocate in bonds in the retirement account"),
+            )
+            .arg(
+                Arg::with_name("percent-stock-retirement")
+                    .long("stock-percent-retirement")
+                    .takes_value(true)
+                    .help("Percentage to allocate in stocks in the retirement account"),
+            )
+            .arg(
+                Arg::with_name("accounts")
+                    .long("accounts")
+                    .takes_value(true)
+                    .help("Path of a TOML file listing the Vanguard accounts along with their account kind"),
+            )
+            .arg(
+                Arg::with_name("add-cash")
+                    .long("adjust-cash")
                     .allow_hyphen_values(true)
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Amount of cash added to or withdraw from the roth IRA account"),
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Amount of cash added to or withdraw from an account in the format of <account number>=<amount>"),
             )
             .arg(
-                Arg::with_name("add-us-stock-roth")
-                    .long("add-us-stock-roth")
+                Arg::with_name("cash-reserve")
+                    .long("cash-reserve")
                     .takes_value(true)
-                    .default_value("0")
-                    .help("Value of roth IRA US stock held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Cash kept within the settlement fund of an account in the format of <account number>=<amount> or <account number>=<percent>%"),
+            )
+            .arg(
+                Arg::with_name("withdraw")
+                    .long("withdraw")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Amount to withdraw from an account in the format of <account number>=<amount>.  The account only sells, starting with the funds furthest above their targets"),
+            )
+            .arg(
+                Arg::with_name("withdraw-by-lots")
+                    .long("withdraw-by-lots")
+                    .takes_value(false)
+                    .help("Withdrawals from taxable accounts first sell the tax lots which are long term or at a loss, before realizing short term gains"),
             )
             .arg(
-                Arg::with_name("add-us-bond-roth")
-                    .long("add-us-bond-roth")
+                Arg::with_name("add-cash-brokerage")
+                    .long("adjust-cash-brokerage")
+                    .short("B")
+                    .allow_hyphen_values(true)
                     .takes_value(true)
                     .default_value("0")
-                    .help("Value of roth IRA US bond held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .help("Amount of cash added to or withdraw from the brokerage account"),
             )
             .arg(
-                Arg::with_name("add-int-stock-roth")
-                    .long("add-int-stock-roth")
+                Arg::with_name("add-cash-traditional")
+                    .long("adjust-cash-traditional")
+                    .short("T")
+                    .allow_hyphen_values(true)
                     .takes_value(true)
                     .default_value("0")
-                    .help("Value of roth IRA international stock held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .help("Amount of cash added to or withdraw from the traditional IRA account"),
             )
             .arg(
-                Arg::with_name("add-int-bond-roth")
-                    .long("add-int-bond-roth")
+                Arg::with_name("add-cash-roth")
+                    .long("adjust-cash-roth")
+                    .short("R")
+                    .allow_hyphen_values(true)
                     .takes_value(true)
                     .default_value("0")
-                    .help("Value of roth IRA international bond held outside of Vanguard.  Used for calculating stock/bond ratios."),
+                    .help("Amount of cash added to or withdraw from the roth IRA account"),
             )
             .arg(
                 Arg::with_name("acct-num-b")
                     .long("brokerage-acct")
                     .short("b")
                     .takes_value(true)
-                    .required_unless_one(&["acct-num-r", "acct-num-t"])
+                    .required_unless_one(&["acct-num-r", "acct-num-t", "accounts"])
                     .help("Brokerage account number"),
             )
             .arg(
@@ -215,7 +379,7 @@ impl Args {
                     .short("r")
                     .requires("retirement-year")
                     .takes_value(true)
-                    .required_unless_one(&["acct-num-b", "acct-num-t"])
+                    .required_unless_one(&["acct-num-b", "acct-num-t", "accounts"])
                     .help("Roth IRA account number"),
             )
             .arg(
@@ -224,9 +388,16 @@ impl Args {
                     .short("t")
                     .requires("retirement-year")
                     .takes_value(true)
-                    .required_unless_one(&["acct-num-b", "acct-num-r"])
+                    .required_unless_one(&["acct-num-b", "acct-num-r", "accounts"])
                     .help("Traditional IRA account number"),
             )
+            .arg(
+                Arg::with_name("share-decimals")
+                    .long("share-decimals")
+                    .takes_value(true)
+                    .default_value(&default_share_decimals)
+                    .help("Number of decimals that share quantities to buy or sell are rounded to"),
+            )
             .arg(
                 Arg::with_name("output")
                     .long("output")
@@ -244,7 +415,7 @@ impl Args {
                 Arg::with_name("birth_year")
                     .long("birth-year")
                     .takes_value(true)
-                    .help("Birth year is used to calculate minimum distribution"),
+                    .help("Birth year is used to calculate minimum distribution and the age based strategies of the accounts without an owner"),
             )
             .arg(
                 Arg::with_name("distribution_year")
@@ -264,89 +435,27 @@ impl Args {
         let percent_stock_brokerage = args
             .value_of("percent-stock-brokerage")
             .unwrap()
-            .parse::<f32>()
+            .parse::<Decimal>()
             .unwrap();
         let percent_bond_brokerage = args
             .value_of("percent-bond-brokerage")
             .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let brokerage_cash_add = args
-            .value_of("add-cash-brokerage")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let brokerage_us_stock_add = args
-            .value_of("add-us-stock-brokerage")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let brokerage_us_bond_add = args
-            .value_of("add-us-bond-brokerage")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let brokerage_int_stock_add = args
-            .value_of("add-int-stock-brokerage")
-            .unwrap()
-            .parse::<f32>()
+            .parse::<Decimal>()
             .unwrap();
-        let brokerage_int_bond_add = args
-            .value_of("add-int-bond-brokerage")
+        let percent_inflation_brokerage = args
+            .value_of("percent-inflation-brokerage")
             .unwrap()
-            .parse::<f32>()
+            .parse::<Decimal>()
             .unwrap();
-
-        let traditional_cash_add = args
-            .value_of("add-cash-traditional")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let traditional_us_stock_add = args
-            .value_of("add-us-stock-traditional")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let traditional_us_bond_add = args
-            .value_of("add-us-bond-traditional")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let traditional_int_stock_add = args
-            .value_of("add-int-stock-traditional")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let traditional_int_bond_add = args
-            .value_of("add-int-bond-traditional")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-
-        let roth_cash_add = args
-            .value_of("add-cash-roth")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let roth_us_stock_add = args
-            .value_of("add-us-stock-roth")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let roth_us_bond_add = args
-            .value_of("add-us-bond-roth")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let roth_int_stock_add = args
-            .value_of("add-int-stock-roth")
-            .unwrap()
-            .parse::<f32>()
-            .unwrap();
-        let roth_int_bond_add = args
-            .value_of("add-int-bond-roth")
+        let mut brokerage_goal_year_option = None;
+        if let Some(brokerage_goal_year) = args.value_of("brokerage-goal-year") {
+            brokerage_goal_year_option = Some(brokerage_goal_year.parse::<i32>().unwrap())
+        }
+        let brokerage_glide_path = args.value_of("brokerage-glide-path").unwrap().to_string();
+        let share_decimals = args
+            .value_of("share-decimals")
             .unwrap()
-            .parse::<f32>()
+            .parse::<u32>()
             .unwrap();
         let distribution_year = args
             .value_of("distribution_year")
@@ -360,11 +469,13 @@ impl Args {
         }
         let mut percent_stock_retirement_option = None;
         if let Some(percent_stock_retirement) = args.value_of("percent-stock-retirement") {
-            percent_stock_retirement_option = Some(percent_stock_retirement.parse::<f32>().unwrap())
+            percent_stock_retirement_option =
+                Some(percent_stock_retirement.parse::<Decimal>().unwrap())
         }
         let mut percent_bond_retirement_option = None;
         if let Some(percent_bond_retirement) = args.value_of("percent-bond-retirement") {
-            percent_bond_retirement_option = Some(percent_bond_retirement.parse::<f32>().unwrap())
+            percent_bond_retirement_option =
+                Some(percent_bond_retirement.parse::<Decimal>().unwrap())
         }
 
         let mut brok_acct_option = None;
@@ -379,10 +490,139 @@ impl Args {
         if let Some(roth_acct_str) = args.value_of("acct-num-r") {
             roth_acct_option = Some(roth_acct_str.parse::<u32>().unwrap())
         }
+        // The cash flags of the brokerage, traditional and roth accounts apply to the account
+        // number given with their account flag
+        let mut cash_adds = Vec::new();
+        for (acct_option, cash_flag) in [
+            (brok_acct_option, "add-cash-brokerage"),
+            (trad_acct_option, "add-cash-traditional"),
+            (roth_acct_option, "add-cash-roth"),
+        ] {
+            if let Some(acct_num) = acct_option {
+                let cash_add = args
+                    .value_of(cash_flag)
+                    .unwrap()
+                    .parse::<Decimal>()
+                    .unwrap();
+                cash_adds.push((acct_num, cash_add));
+            }
+        }
+        for cash_add_str in args.values_of("add-cash").unwrap_or_default() {
+            let (acct_num, cash_add) = cash_add_str
+                .split_once('=')
+                .expect("--adjust-cash needs the format <account number>=<amount>");
+            cash_adds.push((
+                acct_num.trim().parse::<u32>().unwrap(),
+                cash_add.trim().parse::<Decimal>().unwrap(),
+            ));
+        }
+        let mut cash_reserves = Vec::new();
+        for cash_reserve_str in args.values_of("cash-reserve").unwrap_or_default() {
+            let (acct_num, cash_reserve) = cash_reserve_str
+                .split_once('=')
+                .expect("--cash-reserve needs the format <account number>=<amount or percent>");
+            cash_reserves.push((
+                acct_num.trim().parse::<u32>().unwrap(),
+                cash_reserve.parse::<CashReserve>().unwrap(),
+            ));
+        }
+        let mut withdrawals = Vec::new();
+        for withdrawal_str in args.values_of("withdraw").unwrap_or_default() {
+            let (acct_num, withdrawal) = withdrawal_str
+                .split_once('=')
+                .expect("--withdraw needs the format <account number>=<amount>");
+            let withdrawal = withdrawal.trim().parse::<Decimal>().unwrap();
+            assert!(
+                withdrawal > Decimal::ZERO,
+                "--withdraw needs a positive amount: {}",
+                withdrawal_str
+            );
+            withdrawals.push((acct_num.trim().parse::<u32>().unwrap(), withdrawal));
+        }
+        let withdraw_by_lots = args.is_present("withdraw-by-lots");
+        let accounts_path_option = args.value_of("accounts").map(|path| path.to_string());
+        let mut birth_year_option = None;
         let mut age_option = None;
         if let Some(birth_year) = args.value_of("birth_year") {
-            age_option = Some(distribution_year - birth_year.parse::<u32>().unwrap())
+            let birth_year = birth_year.parse::<u32>().unwrap();
+            birth_year_option = Some(birth_year);
+            age_option = Some(distribution_year - birth_year)
         }
+        let fund_universe_path_option = args.value_of("fund-universe").map(|path| path.to_string());
+        let keep_money_market_option = args
+            .value_of("keep-money-market")
+            .map(|symbol| symbol.to_string());
+        let lots_path_option = args
+            .value_of("unrealized-gains")
+            .map(|path| path.to_string());
+        let outside_holdings_path_option = args
+            .value_of("outside-holdings")
+            .map(|path| path.to_string());
+        let weights_path_option = args
+            .value_of("allocation-weights")
+            .map(|path| path.to_string());
+        let glide_path = args.value_of("glide-path").unwrap().to_string();
+        let glide_paths_path_option = args.value_of("glide-paths").map(|path| path.to_string());
+        let strategy = args
+            .value_of("strategy")
+            .unwrap()
+            .parse::<AllocationStrategy>()
+            .unwrap();
+        let compare_strategies = args.is_present("compare-strategies");
+        let policy = RebalancePolicy {
+            asset_class_band: args
+                .value_of("asset-class-bands")
+                .map(|band| band.parse::<Band>().unwrap()),
+            fund_band: args
+                .value_of("fund-bands")
+                .map(|band| band.parse::<Band>().unwrap()),
+            mode: args
+                .value_of("band-mode")
+                .unwrap()
+                .parse::<BandMode>()
+                .unwrap(),
+            buy_only: args.is_present("buy-only"),
+        };
+        let whole_shares = args.is_present("whole-shares");
+        let harvest = args.is_present("harvest");
+        let harvest_threshold = args
+            .value_of("harvest-threshold")
+            .unwrap()
+            .parse::<Decimal>()
+            .unwrap();
+        let harvest_pairs_path_option = args.value_of("harvest-pairs").map(|path| path.to_string());
+        let asset_location_path_option =
+            args.value_of("asset-location").map(|path| path.to_string());
+        let execution_plan = args.is_present("execution-plan");
+        let cost_aware = args.is_present("cost-aware");
+        let trade_costs_path_option = args.value_of("trade-costs").map(|path| path.to_string());
+        let tax_rates = TaxRates {
+            short_term: args
+                .value_of("short-term-tax-rate")
+                .unwrap()
+                .parse::<Decimal>()
+                .unwrap(),
+            long_term: args
+                .value_of("long-term-tax-rate")
+                .unwrap()
+                .parse::<Decimal>()
+                .unwrap(),
+        };
+        let tax_policy_option = args
+            .value_of("lot-selection")
+            .map(|lot_selection| TaxPolicy {
+                lot_selection: lot_selection.parse::<LotSelection>().unwrap(),
+                rates: tax_rates,
+                max_tax_cost: args.value_of("max-tax-cost").map(|max_tax_cost_str| {
+                    let max_tax_cost = max_tax_cost_str.trim().parse::<Decimal>().unwrap();
+                    assert!(
+                        max_tax_cost >= Decimal::ZERO,
+                        "--max-tax-cost needs an amount of at least zero: {}",
+                        max_tax_cost_str
+                    );
+                    max_tax_cost
+                }),
+            });
         let output = args.is_present("output");
         let use_brokerage_retirement = args.is_present("use_brokerage");
         Args {
@@ -390,35 +630,208 @@ impl Args {
             retirement_year_option,
             percent_stock_brokerage,
             percent_bond_brokerage,
+            percent_inflation_brokerage,
+            brokerage_goal_year_option,
+            brokerage_glide_path,
             percent_stock_retirement_option,
             percent_bond_retirement_option,
-            brokerage_cash_add,
-            brokerage_us_stock_add,
-            brokerage_us_bond_add,
-            brokerage_int_stock_add,
-            brokerage_int_bond_add,
-            traditional_cash_add,
-            traditional_us_stock_add,
-            traditional_us_bond_add,
-            traditional_int_stock_add,
-            traditional_int_bond_add,
-            roth_cash_add,
-            roth_us_stock_add,
-            roth_us_bond_add,
-            roth_int_stock_add,
-            roth_int_bond_add,
+            cash_adds,
+            cash_reserves,
+            withdrawals,
+            withdraw_by_lots,
+            accounts_path_option,
             brok_acct_option,
             trad_acct_option,
             roth_acct_option,
             output,
             age_option,
+            birth_year_option,
             distribution_year,
             distribution_table_path,
             use_brokerage_retirement,
+            fund_universe_path_option,
+            share_decimals,
+            whole_shares,
+            keep_money_market_option,
+            lots_path_option,
+            outside_holdings_path_option,
+            weights_path_option,
+            glide_path,
+            glide_paths_path_option,
+            strategy,
+            compare_strategies,
+            policy,
+            tax_rates,
+            tax_policy_option,
+            harvest,
+            harvest_threshold,
+            harvest_pairs_path_option,
+            asset_location_path_option,
+            cost_aware,
+            trade_costs_path_option,
+            execution_plan,
         }
     }
 }
 
+impl Args {
+    /// accounts returns the accounts listed within the accounts file followed by the traditional
+    /// IRA, roth IRA and brokerage accounts given through their own flags
+    ///
+    /// # Errors
+    ///
+    /// Errors when the accounts file is invalid or an account number is given more than once
+    pub fn accounts(&self) -> Result<Accounts> {
+        let mut accounts = match &self.accounts_path_option {
+            Some(accounts_path) => Accounts::from_file(accounts_path)?,
+            None => Accounts::new(),
+        };
+        for (acct_option, kind, name) in [
+            (
+                self.trad_acct_option,
+                AccountKind::TaxDeferred,
+                "Traditional IRA",
+            ),
+            (self.roth_acct_option, AccountKind::TaxFree, "Roth IRA"),
+            (self.brok_acct_option, AccountKind::Taxable, "Brokerage"),
+        ] {
+            if let Some(acct_num) = acct_option {
+                accounts.add_account(Account::new(acct_num, kind, Some(name)))?;
+            }
+        }
+        for (acct_num, _) in &self.cash_adds {
+            accounts.account(*acct_num).ok_or_else(|| {
+                anyhow!(
+                    "Cash is adjusted for account {}, which is not listed",
+                    acct_num
+                )
+            })?;
+        }
+        for (acct_num, _) in &self.withdrawals {
+            accounts.account(*acct_num).ok_or_else(|| {
+                anyhow!(
+                    "Cash is withdrawn from account {}, which is not listed",
+                    acct_num
+                )
+            })?;
+        }
+        for (acct_num, _) in &self.cash_reserves {
+            accounts.account(*acct_num).ok_or_else(|| {
+                anyhow!(
+                    "Cash is reserved within account {}, which is not listed",
+                    acct_num
+                )
+            })?;
+        }
+        Ok(accounts)
+    }
+
+    /// allocation_weights returns the weights within the allocation weights file, otherwise the
+    /// default weights
+    pub fn allocation_weights(&self) -> Result<AllocationWeights> {
+        match &self.weights_path_option {
+            Some(weights_path) => AllocationWeights::from_file(weights_path),
+            None => Ok(AllocationWeights::new()),
+        }
+    }
+
+    /// glide_paths returns the built-in glide paths along with those within the glide paths file
+    ///
+    /// # Errors
+    ///
+    /// Errors when the glide paths file is invalid or does not contain the glide path of the
+    /// household or the brokerage account
+    pub fn glide_paths(&self) -> Result<GlidePaths> {
+        let glide_paths = match &self.glide_paths_path_option {
+            Some(glide_paths_path) => GlidePaths::from_file(glide_paths_path)?,
+            None => GlidePaths::new(),
+        };
+        glide_paths.glide_path(&self.glide_path)?;
+        glide_paths.glide_path(&self.brokerage_glide_path)?;
+        Ok(glide_paths)
+    }
+
+    /// harvest_pairs returns the substitute pairs within the harvest pairs file, otherwise the
+    /// built-in pairs
+    pub fn harvest_pairs(&self) -> Result<HarvestPairs> {
+        match &self.harvest_pairs_path_option {
+            Some(harvest_pairs_path) => HarvestPairs::from_file(harvest_pairs_path),
+            None => Ok(HarvestPairs::default()),
+        }
+    }
+
+    /// location_assumptions returns the assumptions within the asset location file, otherwise the
+    /// built-in assumptions
+    pub fn location_assumptions(&self) -> Result<LocationAssumptions> {
+        match &self.asset_location_path_option {
+            Some(asset_location_path) => LocationAssumptions::from_file(asset_location_path),
+            None => Ok(LocationAssumptions::default()),
+        }
+    }
+
+    /// trade_costs returns the trade costs within the trade costs file, otherwise the built-in trade
+    /// costs, when the rebalance is cost aware
+    pub fn trade_costs(&self) -> Result<Option<TradeCosts>> {
+        if !self.cost_aware {
+            return Ok(None);
+        }
+        match &self.trade_costs_path_option {
+            Some(trade_costs_path) => TradeCosts::from_file(trade_costs_path).map(Some),
+            None => Ok(Some(TradeCosts::default())),
+        }
+    }
+
+    /// investor returns the owner as an investor following the glide path.  Accounts without an
+    /// owner use the birth year and retirement year arguments, and an owner without a retirement
+    /// year uses the retirement year argument.
+    pub fn investor<'a>(&self, owner: Option<&Owner>, glide_path: &'a GlidePath) -> Investor<'a> {
+        Investor {
+            birth_year: owner.map_or(self.birth_year_option, |owner| owner.birth_year),
+            retirement_year: owner
+                .and_then(|owner| owner.retirement_year)
+                .or(self.retirement_year_option),
+            glide_path,
+        }
+    }
+
+    /// cash_reserve returns the cash reserve of the account, where a reserve given through the CLI
+    /// arguments replaces the one within the accounts file
+    pub fn cash_reserve(&self, account: &Account) -> Option<CashReserve> {
+        self.cash_reserves
+            .iter()
+            .rev()
+            .find(|(acct_num, _)| *acct_num == account.number)
+            .map(|(_, cash_reserve)| *cash_reserve)
+            .or(account.cash_reserve)
+    }
+
+    /// rounds_whole_shares returns whether or not the ETF orders of the account are rounded to
+    /// whole shares, where the accounts file setting of the account replaces the CLI flag
+    pub fn rounds_whole_shares(&self, account: &Account) -> bool {
+        account.whole_shares.unwrap_or(self.whole_shares)
+    }
+
+    /// cash_add returns the total amount of cash added to, or withdrawn from when negative, the
+    /// account, including the withdrawal of the account
+    pub fn cash_add(&self, acct_num: u32) -> Decimal {
+        self.cash_adds
+            .iter()
+            .filter(|(cash_acct_num, _)| *cash_acct_num == acct_num)
+            .map(|(_, cash_add)| *cash_add)
+            .sum::<Decimal>()
+            - self.withdrawal(acct_num)
+    }
+
+    /// withdrawal returns the total amount withdrawn from the account by only selling
+    pub fn withdrawal(&self, acct_num: u32) -> Decimal {
+        self.withdrawals
+            .iter()
+            .filter(|(withdrawal_acct_num, _)| *withdrawal_acct_num == acct_num)
+            .map(|(_, withdrawal)| *withdrawal)
+            .sum()
+    }
+}
+
 impl Default for Args {
     fn default() -> Self {
         Self::new()
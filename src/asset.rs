@@ -1,83 +1,93 @@
-use anyhow::{ensure, Result};
-use chrono::prelude::*;
-use std::fmt;
+use crate::{
+    glide::GlidePath,
+    holdings::StockSymbol,
+    universe::{fund_universe, AssetClass, TaxCharacter},
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, fs};
+
+/// Fraction holds an exact numerator and denominator so that the proportions below do not lose
+/// precision before they are applied to a percentage.
+#[derive(Clone, Copy)]
+struct Fraction(i64, i64);
+
+impl Fraction {
+    const fn mul(self, numerator: i64, denominator: i64) -> Fraction {
+        Fraction(self.0 * numerator, self.1 * denominator)
+    }
+
+    /// of returns the fraction of the value, multiplying before dividing to keep precision
+    fn of(self, value: Decimal) -> Decimal {
+        value * Decimal::from(self.0) / Decimal::from(self.1)
+    }
+}
 
 // Constants used for proportion of portfolio contained within each.
 // Split by stocks and bonds
 // US stock as 2/3 of total stock.  Then split by 3 for Large, medium, and small cap
-const US_STOCK_FRACTION: f32 = 2.0 / 3.0;
-const LARGE_US_STOCK: f32 = US_STOCK_FRACTION / 2.0;
-const SMALL_MID_US_STOCK: f32 = US_STOCK_FRACTION / 4.0;
+const US_STOCK_FRACTION: Fraction = Fraction(2, 3);
+const LARGE_US_STOCK: Fraction = US_STOCK_FRACTION.mul(1, 2);
+const SMALL_MID_US_STOCK: Fraction = US_STOCK_FRACTION.mul(1, 4);
 // International stock as 1/3 of total stock.  Then 1/3 of that as emerging markets and 2/3 as
 // total international
-const INT_STOCK_FRACTION: f32 = 1.0 / 3.0;
-const INT_EMERGING: f32 = INT_STOCK_FRACTION / 3.0;
-const INT_TOTAL: f32 = INT_STOCK_FRACTION * 2.0 / 3.0;
+const INT_STOCK_FRACTION: Fraction = Fraction(1, 3);
+const INT_EMERGING: Fraction = INT_STOCK_FRACTION.mul(1, 3);
+const INT_TOTAL: Fraction = INT_STOCK_FRACTION.mul(2, 3);
 // 2/3 of total bonds in US corporate bonds, 1/3 in internation bonds
-const US_BOND_FRACTION: f32 = 2.0 / 3.0;
-const US_CORP_BOND_FRACTION: f32 = US_BOND_FRACTION / 2.0;
-const US_TOT_BOND_FRACTION: f32 = US_BOND_FRACTION / 2.0;
-const INT_BOND_FRACTION: f32 = 1.0 / 3.0;
+const US_BOND_FRACTION: Fraction = Fraction(2, 3);
+const US_CORP_BOND_FRACTION: Fraction = US_BOND_FRACTION.mul(1, 2);
+const US_TOT_BOND_FRACTION: Fraction = US_BOND_FRACTION.mul(1, 2);
+const INT_BOND_FRACTION: Fraction = Fraction(1, 3);
+
+// Number of decimals that sub allocation percentages need to add up to 100 within.  Thirds and
+// ninths do not divide evenly into decimals.
+const SUB_ALLOCATION_DECIMALS: u32 = 8;
 
 /// Holds the stock, bond, and inflation protected percentages.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Allocations {
-    total_stock: f32,
-    total_bond: f32,
-    total_inflation_protected: f32,
+    total_stock: Decimal,
+    total_bond: Decimal,
+    total_inflation_protected: Decimal,
 }
 
 impl Allocations {
     /// Default asset allocations at 60% stock and 40% bond
     pub fn new() -> Self {
         Allocations {
-            total_stock: 60.0,
-            total_bond: 40.0,
-            total_inflation_protected: 0.0,
+            total_stock: dec!(60),
+            total_bond: dec!(40),
+            total_inflation_protected: Decimal::ZERO,
         }
     }
     /// Calculates the stock, bond, and inflation protected percentages based on Vanguard target
-    /// asset allocation.
+    /// asset allocation, which is the current built-in glide path.
     pub fn retirement(year: i32) -> Result<Self> {
-        ensure!(
-            (2000..3000).contains(&year),
-            format!(
-                "Year needs to be between 2000 and 3000.  Year input: {}",
-                year
-            )
-        );
-        let this_year = chrono::Local::now().year();
-        let years_to_retirement = (year - this_year) as f32;
-        let mut total_stock = 90.0;
-        let mut total_bond = 10.0;
-        let mut total_inflation_protected = 0.0;
-        if (5.0..30.0).contains(&years_to_retirement) {
-            total_stock = 90.0 - (1.5 * (25.0 - years_to_retirement));
-            total_bond = 100.0 - total_stock;
-        } else if (-5.0..5.0).contains(&years_to_retirement) {
-            total_stock = 60.0 - (-2.8 * (years_to_retirement - 5.0));
-            total_inflation_protected = -1.8 * (years_to_retirement - 5.0);
-            total_bond = 100.0 - total_stock - total_inflation_protected;
-        } else if years_to_retirement < -5.0 {
-            total_stock = 29.0;
-            total_bond = 53.0;
-            total_inflation_protected = 18.0;
-        }
-        Ok(Allocations {
-            total_stock,
-            total_bond,
-            total_inflation_protected,
-        })
+        GlidePath::current().allocations(year)
     }
 
     /// Creates a Allocations struct with custom input values for stock, bond, and inflaction
     /// protected precentages.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::asset::Allocations;
+    ///
+    /// assert!(Allocations::custom(dec!(33.3), dec!(33.3), dec!(33.4)).is_ok());
+    /// assert!(Allocations::custom(dec!(60), dec!(30), dec!(0)).is_err());
+    /// ```
     pub fn custom(
-        total_stock: f32,
-        total_bond: f32,
-        total_inflation_protected: f32,
+        total_stock: Decimal,
+        total_bond: Decimal,
+        total_inflation_protected: Decimal,
     ) -> Result<Self> {
         ensure!(
-            total_stock + total_bond + total_inflation_protected == 100.0,
+            total_stock + total_bond + total_inflation_protected == Decimal::ONE_HUNDRED,
             format!(
                 "Stock ({}) + bond ({}) + inflation protected ({}) does not equal 100",
                 total_stock, total_bond, total_inflation_protected
@@ -90,18 +100,61 @@ impl Allocations {
         })
     }
 
+    /// Creates a Allocations struct which blends the allocations, where each is weighted by a value
+    /// such as each owner's share of the household's accounts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::asset::Allocations;
+    ///
+    /// let blend = Allocations::weighted(&[
+    ///     (Allocations::custom(dec!(90), dec!(10), dec!(0)).unwrap(), dec!(30000)),
+    ///     (Allocations::custom(dec!(50), dec!(40), dec!(10)).unwrap(), dec!(10000)),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(blend.total_stock(), dec!(80));
+    /// assert_eq!(blend.total_bond(), dec!(17.5));
+    /// assert_eq!(blend.total_inflation_protected(), dec!(2.5));
+    /// ```
+    pub fn weighted(weighted_allocations: &[(Allocations, Decimal)]) -> Result<Self> {
+        let total_weight = weighted_allocations
+            .iter()
+            .map(|(_, weight)| *weight)
+            .sum::<Decimal>();
+        ensure!(
+            total_weight > Decimal::ZERO,
+            "Weighted allocations need a total weight above zero"
+        );
+        let weighted_percent = |percent: fn(&Allocations) -> Decimal| -> Decimal {
+            weighted_allocations
+                .iter()
+                .map(|(allocations, weight)| percent(allocations) * weight)
+                .sum::<Decimal>()
+                / total_weight
+        };
+        let total_stock = weighted_percent(Allocations::total_stock);
+        let total_inflation_protected = weighted_percent(Allocations::total_inflation_protected);
+        Allocations::custom(
+            total_stock,
+            Decimal::ONE_HUNDRED - total_stock - total_inflation_protected,
+            total_inflation_protected,
+        )
+    }
+
     /// Return total stock asset allocation percentage.
-    pub fn total_stock(&self) -> f32 {
+    pub fn total_stock(&self) -> Decimal {
         self.total_stock
     }
 
     /// Returns total bond asset allocation percentage.
-    pub fn total_bond(&self) -> f32 {
+    pub fn total_bond(&self) -> Decimal {
         self.total_bond
     }
 
     /// Returns total inflation protected asset allocation percentage.
-    pub fn total_inflation_protected(&self) -> f32 {
+    pub fn total_inflation_protected(&self) -> Decimal {
         self.total_inflation_protected
     }
 }
@@ -122,63 +175,413 @@ impl fmt::Display for Allocations {
     }
 }
 
-/// Holds the percentage of allocation for each type of stock, bond, etc.  splitting by US and
-/// international and other categories.
+/// SubClassWeight is the weight of a sub class within its asset class along with the weight of
+/// each fund within the sub class.  Without fund weights, the first fund within the fund universe
+/// which fills the sub class holds all of it.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SubClassWeight {
+    pub weight: Decimal,
+    #[serde(default)]
+    pub funds: Option<BTreeMap<String, Decimal>>,
+}
+
+impl SubClassWeight {
+    fn new(fraction: Fraction) -> Self {
+        SubClassWeight {
+            weight: fraction.of(Decimal::ONE_HUNDRED),
+            funds: None,
+        }
+    }
+}
+
+/// AllocationWeights is the tree of weights which splits each asset class into sub classes and
+/// each sub class into funds.  The weights are percentages which add up to 100 at every level.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AllocationWeights {
+    #[serde(default)]
+    pub stock: BTreeMap<String, SubClassWeight>,
+    #[serde(default)]
+    pub bond: BTreeMap<String, SubClassWeight>,
+    #[serde(default)]
+    pub inflation_protected: BTreeMap<String, SubClassWeight>,
+}
+
+impl AllocationWeights {
+    /// new creates the default weights, where US stock is 2/3 of stock split between large, mid
+    /// and small cap, international stock is 1/3 of stock split between total international and
+    /// emerging markets, and bonds are split evenly between US total, US corporate and
+    /// international bonds
+    pub fn new() -> Self {
+        AllocationWeights {
+            stock: BTreeMap::from([
+                (
+                    "us_stock_large".to_string(),
+                    SubClassWeight::new(LARGE_US_STOCK),
+                ),
+                (
+                    "us_stock_mid".to_string(),
+                    SubClassWeight::new(SMALL_MID_US_STOCK),
+                ),
+                (
+                    "us_stock_small".to_string(),
+                    SubClassWeight::new(SMALL_MID_US_STOCK),
+                ),
+                ("int_tot_stock".to_string(), SubClassWeight::new(INT_TOTAL)),
+                (
+                    "int_emerging_stock".to_string(),
+                    SubClassWeight::new(INT_EMERGING),
+                ),
+            ]),
+            bond: BTreeMap::from([
+                (
+                    "us_tot_bond".to_string(),
+                    SubClassWeight::new(US_TOT_BOND_FRACTION),
+                ),
+                (
+                    "us_corp_bond".to_string(),
+                    SubClassWeight::new(US_CORP_BOND_FRACTION),
+                ),
+                (
+                    "int_bond".to_string(),
+                    SubClassWeight::new(INT_BOND_FRACTION),
+                ),
+            ]),
+            inflation_protected: BTreeMap::from([(
+                "inflation_protected".to_string(),
+                SubClassWeight::new(Fraction(1, 1)),
+            )]),
+        }
+    }
+
+    /// from_toml parses and validates the weights from the contents of a TOML file.  An asset
+    /// class which is left out keeps its default weights.
+    ///
+    /// # Errors
+    ///
+    /// Errors naming the node of the tree whose weights do not add up to 100, the sub class which
+    /// no fund of the asset class fills, or the fund which does not fill its sub class.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::asset::AllocationWeights;
+    ///
+    /// let weights = AllocationWeights::from_toml(
+    ///     r#"
+    ///     [stock.us_stock_large]
+    ///     weight = 60
+    ///
+    ///     [stock.int_tot_stock]
+    ///     weight = 40
+    ///     funds = { VXUS = 100 }
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(weights.stock["us_stock_large"].weight, dec!(60));
+    /// assert_eq!(weights.bond, AllocationWeights::new().bond);
+    ///
+    /// let error = AllocationWeights::from_toml("[stock.us_stock_large]\nweight = 90").unwrap_err();
+    /// assert!(format!("{:#}", error).contains("within stock add up to 90"));
+    ///
+    /// let error = AllocationWeights::from_toml(
+    ///     "[bond.us_tot_bond]\nweight = 100\nfunds = { BND = 50, VTC = 50 }",
+    /// )
+    /// .unwrap_err();
+    /// assert!(format!("{:#}", error).contains("bond.us_tot_bond.VTC"));
+    /// ```
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let mut weights: AllocationWeights =
+            toml::from_str(contents).context("Unable to parse allocation weights")?;
+        let default_weights = AllocationWeights::new();
+        if weights.stock.is_empty() {
+            weights.stock = default_weights.stock;
+        }
+        if weights.bond.is_empty() {
+            weights.bond = default_weights.bond;
+        }
+        if weights.inflation_protected.is_empty() {
+            weights.inflation_protected = default_weights.inflation_protected;
+        }
+        weights.validate()?;
+        Ok(weights)
+    }
+
+    /// from_file reads an allocation weights TOML file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Allocation weights file not found: {}", path))?;
+        Self::from_toml(&contents).with_context(|| format!("Invalid allocation weights: {}", path))
+    }
+
+    /// validate checks that the weights add up to 100 within each asset class and sub class, that
+    /// each sub class is filled by a fund of its asset class, and that each weighted fund fills
+    /// its sub class within the fund universe
+    pub fn validate(&self) -> Result<()> {
+        for (asset_class, class_name, sub_weights) in self.asset_classes() {
+            let total = sub_weights
+                .values()
+                .map(|sub_weight| sub_weight.weight)
+                .sum::<Decimal>();
+            ensure!(
+                total.round_dp(SUB_ALLOCATION_DECIMALS) == Decimal::ONE_HUNDRED,
+                "Weights of the sub classes within {} add up to {} rather than 100",
+                class_name,
+                total
+            );
+            for (sub_class, sub_weight) in sub_weights {
+                let node = format!("{}.{}", class_name, sub_class);
+                ensure!(
+                    !sub_weight.weight.is_sign_negative(),
+                    "Weight of {} is negative",
+                    node
+                );
+                let symbol = fund_universe()
+                    .sub_class_symbol(sub_class)
+                    .ok_or_else(|| anyhow!("{} is not the sub class of any fund", node))?;
+                ensure!(
+                    fund_universe().asset_class(&symbol) == Some(asset_class),
+                    "{} is filled by {}, which is not within the {} asset class",
+                    node,
+                    symbol,
+                    class_name
+                );
+                if let Some(funds) = &sub_weight.funds {
+                    let fund_total = funds.values().sum::<Decimal>();
+                    ensure!(
+                        fund_total.round_dp(SUB_ALLOCATION_DECIMALS) == Decimal::ONE_HUNDRED,
+                        "Weights of the funds within {} add up to {} rather than 100",
+                        node,
+                        fund_total
+                    );
+                    for (symbol, fund_weight) in funds {
+                        ensure!(
+                            !fund_weight.is_sign_negative(),
+                            "Weight of {}.{} is negative",
+                            node,
+                            symbol
+                        );
+                        let fills_sub_class = fund_universe()
+                            .fund(&StockSymbol::new(symbol))
+                            .is_some_and(|fund| fund.sub_class.as_ref() == Some(sub_class));
+                        ensure!(
+                            fills_sub_class,
+                            "{}.{} is not a fund within the fund universe which fills {}",
+                            node,
+                            symbol,
+                            sub_class
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn asset_classes(&self) -> [(AssetClass, &'static str, &BTreeMap<String, SubClassWeight>); 3] {
+        [
+            (AssetClass::Stock, "stock", &self.stock),
+            (AssetClass::Bond, "bond", &self.bond),
+            (
+                AssetClass::InflationProtected,
+                "inflation_protected",
+                &self.inflation_protected,
+            ),
+        ]
+    }
+}
+
+impl Default for AllocationWeights {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Holds the percentage of allocation for each sub class of stock, bond, etc. splitting by US and
+/// international and other categories, along with the percentage of each fund which fills them.
 pub struct SubAllocations {
-    pub us_stock_large: f32,
-    pub us_stock_mid: f32,
-    pub us_stock_small: f32,
-    pub us_tot_bond: f32,
-    pub us_corp_bond: f32,
-    pub int_tot_stock: f32,
-    pub int_emerging_stock: f32,
-    pub int_bond: f32,
-    pub inflation_protected: f32,
+    sub_classes: Vec<(String, Decimal)>,
+    funds: Vec<(StockSymbol, Decimal)>,
 }
 
 impl SubAllocations {
     /// Creates a default SubAllocations struct using the default Allocations of 60% stock and 40%
-    /// bond
+    /// bond along with the default weights
     pub fn new() -> Result<Self> {
         let allocations = Allocations::new();
-        Self::new_custom(allocations)
+        Self::new_custom(allocations, &AllocationWeights::new())
     }
 
     /// Divides the asset bond/stock allocations set by the Allocations struct into percentages for
-    /// the SubAllocations of how much within international, domestic, bond, stock etc.
-    pub fn new_custom(allocations: Allocations) -> Result<Self> {
-        let us_stock_large = allocations.total_stock() * LARGE_US_STOCK;
-        let us_stock_mid = allocations.total_stock() * SMALL_MID_US_STOCK;
-        let us_stock_small = allocations.total_stock() * SMALL_MID_US_STOCK;
-        let us_tot_bond = allocations.total_bond() * US_TOT_BOND_FRACTION;
-        let us_corp_bond = allocations.total_bond() * US_CORP_BOND_FRACTION;
-        let int_tot_stock = allocations.total_stock() * INT_TOTAL;
-        let int_emerging_stock = allocations.total_stock() * INT_EMERGING;
-        let int_bond = allocations.total_bond() * INT_BOND_FRACTION;
-        let inflation_protected = allocations.total_inflation_protected();
-        let sum = us_stock_large
-            + us_stock_mid
-            + us_stock_small
-            + us_tot_bond
-            + us_corp_bond
-            + int_tot_stock
-            + int_emerging_stock
-            + int_bond
-            + inflation_protected;
+    /// the SubAllocations of how much within international, domestic, bond, stock etc. by the
+    /// weights of each sub class, and then into the funds of each sub class.
+    ///
+    /// # Errors
+    ///
+    /// Errors when a sub class has a percentage to fill but no fund within the fund universe has
+    /// that sub class.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{asset::{AllocationWeights, Allocations, SubAllocations}, holdings::StockSymbol};
+    ///
+    /// let weights = AllocationWeights::from_toml(
+    ///     "[stock.us_stock_large]\nweight = 75\n[stock.int_tot_stock]\nweight = 25",
+    /// )
+    /// .unwrap();
+    /// let allocations = Allocations::custom(dec!(80), dec!(20), dec!(0)).unwrap();
+    ///
+    /// let sub_allocations = SubAllocations::new_custom(allocations, &weights).unwrap();
+    /// let fund_percents = sub_allocations.fund_percents();
+    /// assert!(fund_percents.contains(&(StockSymbol::new("VV"), dec!(60))));
+    /// assert!(fund_percents.contains(&(StockSymbol::new("VXUS"), dec!(20))));
+    /// ```
+    pub fn new_custom(allocations: Allocations, weights: &AllocationWeights) -> Result<Self> {
+        let mut sub_classes = Vec::new();
+        let mut funds = Vec::new();
+        for (total, (_, class_name, sub_weights)) in [
+            allocations.total_stock(),
+            allocations.total_bond(),
+            allocations.total_inflation_protected(),
+        ]
+        .into_iter()
+        .zip(weights.asset_classes())
+        {
+            ensure!(
+                total.is_zero() || !sub_weights.is_empty(),
+                "There are no sub class weights for {}, which is {}% of the allocation",
+                class_name,
+                total
+            );
+            for (sub_class, sub_weight) in sub_weights {
+                let percent = total * sub_weight.weight / Decimal::ONE_HUNDRED;
+                sub_classes.push((sub_class.clone(), percent));
+                if let Some(fund_weights) = &sub_weight.funds {
+                    for (symbol, fund_weight) in fund_weights {
+                        funds.push((
+                            StockSymbol::new(symbol),
+                            percent * fund_weight / Decimal::ONE_HUNDRED,
+                        ));
+                    }
+                } else if let Some(symbol) = fund_universe().sub_class_symbol(sub_class) {
+                    funds.push((symbol, percent));
+                } else if !percent.is_zero() {
+                    return Err(anyhow!(
+                        "No fund within the fund universe has the sub class {}",
+                        sub_class
+                    ));
+                }
+            }
+        }
+        let sum = funds.iter().map(|(_, percent)| *percent).sum::<Decimal>();
         ensure!(
-            (99.9..100.1).contains(&sum),
+            sum.round_dp(SUB_ALLOCATION_DECIMALS) == Decimal::ONE_HUNDRED,
             format!("Total sub allocations did not add up to 100: {}", sum)
         );
+        Ok(SubAllocations { sub_classes, funds })
+    }
+
+    /// sub_class_percents returns the percentage of each sub allocation along with the name of
+    /// the sub class.  The sub class names match the sub class of the funds within the fund
+    /// universe.
+    pub fn sub_class_percents(&self) -> Vec<(String, Decimal)> {
+        self.sub_classes.clone()
+    }
+
+    /// fund_percents returns the percentage of the allocation held within each fund
+    pub fn fund_percents(&self) -> Vec<(StockSymbol, Decimal)> {
+        self.funds.clone()
+    }
+
+    /// without_tax_exempt moves the percentage of tax exempt funds, such as municipal bonds, onto
+    /// the other funds of the same asset class in proportion to their percentages.  This is used
+    /// for tax-advantaged accounts, where tax exempt interest is wasted.
+    ///
+    /// # Errors
+    ///
+    /// Errors when a tax exempt fund has a percentage but no other fund of its asset class does
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{asset::{AllocationWeights, Allocations, SubAllocations}, holdings::StockSymbol};
+    ///
+    /// let weights = AllocationWeights::from_toml(
+    ///     "[bond.us_tot_bond]\nweight = 50\n[bond.us_muni_bond]\nweight = 50",
+    /// )
+    /// .unwrap();
+    /// let allocations = Allocations::custom(dec!(60), dec!(40), dec!(0)).unwrap();
+    /// let sub_allocations = SubAllocations::new_custom(allocations, &weights).unwrap();
+    /// assert!(sub_allocations.fund_percents().contains(&(StockSymbol::new("VTEB"), dec!(20))));
+    ///
+    /// let fund_percents = sub_allocations.without_tax_exempt().unwrap().fund_percents();
+    /// assert!(fund_percents.contains(&(StockSymbol::new("BND"), dec!(40))));
+    /// assert!(!fund_percents.iter().any(|(symbol, _)| symbol == &StockSymbol::new("VTEB")));
+    ///
+    /// let muni_weights = AllocationWeights::from_toml("[bond.us_muni_bond]\nweight = 100").unwrap();
+    /// let muni_allocations = SubAllocations::new_custom(allocations, &muni_weights).unwrap();
+    /// assert!(muni_allocations.without_tax_exempt().is_err());
+    /// ```
+    pub fn without_tax_exempt(&self) -> Result<Self> {
         Ok(SubAllocations {
-            us_stock_large,
-            us_stock_mid,
-            us_stock_small,
-            us_tot_bond,
-            us_corp_bond,
-            int_tot_stock,
-            int_emerging_stock,
-            int_bond,
-            inflation_protected,
+            sub_classes: move_tax_exempt(&self.sub_classes, |sub_class| {
+                fund_universe().sub_class_symbol(sub_class)
+            })?,
+            funds: move_tax_exempt(&self.funds, |symbol| Some(symbol.clone()))?,
+        })
+    }
+}
+
+// move_tax_exempt moves the percentage of the tax exempt entries onto the other entries of the
+// same asset class in proportion to their percentages, where symbol_of returns the fund of an entry
+fn move_tax_exempt<K: Clone + fmt::Display>(
+    percents: &[(K, Decimal)],
+    symbol_of: impl Fn(&K) -> Option<StockSymbol>,
+) -> Result<Vec<(K, Decimal)>> {
+    let universe = fund_universe();
+    let classified = percents
+        .iter()
+        .map(|(key, percent)| {
+            let fund = symbol_of(key).and_then(|symbol| universe.model_fund(&symbol));
+            let asset_class = fund.map(|fund| fund.asset_class);
+            let tax_exempt = fund
+                .map(|fund| fund.tax_character == TaxCharacter::TaxExempt)
+                .unwrap_or(false);
+            (key, *percent, asset_class, tax_exempt)
         })
+        .collect::<Vec<(&K, Decimal, Option<AssetClass>, bool)>>();
+    let mut moved = Vec::new();
+    for (key, percent, asset_class, tax_exempt) in &classified {
+        let class_total = |exempt: bool| {
+            classified
+                .iter()
+                .filter(|(_, _, other_class, other_exempt)| {
+                    other_class == asset_class && *other_exempt == exempt
+                })
+                .map(|(_, percent, _, _)| *percent)
+                .sum::<Decimal>()
+        };
+        let (exempt_total, taxed_total) = (class_total(true), class_total(false));
+        if *tax_exempt {
+            ensure!(
+                percent.is_zero() || !taxed_total.is_zero(),
+                "{} is tax exempt and needs another fund of its asset class to take its place \
+                within tax-advantaged accounts",
+                key
+            );
+        } else if exempt_total.is_zero() || taxed_total.is_zero() {
+            moved.push(((*key).clone(), *percent));
+        } else {
+            moved.push((
+                (*key).clone(),
+                *percent * (taxed_total + exempt_total) / taxed_total,
+            ));
+        }
     }
+    Ok(moved)
 }
@@ -1,340 +1,652 @@
 use anyhow::{ensure, Context, Result};
+use chrono::prelude::*;
+use rust_decimal::Decimal;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader},
 };
 
 use crate::{
+    account::{Account, AccountKind, Accounts},
     arguments::Args,
-    asset::{Allocations, SubAllocations},
-    holdings::{
-        AccountHoldings, HoldingType, ShareValues, StockSymbol, VanguardHoldings, VanguardRebalance,
-    },
+    asset::{AllocationWeights, Allocations, SubAllocations},
+    glide::GlidePaths,
+    holdings::{AccountHoldings, ShareValues, StockSymbol, VanguardHoldings, VanguardRebalance},
+    location::LocationComparison,
+    money::round_dollars,
+    outside::OutsideHoldings,
+    policy::withdraw,
+    universe::{fund_universe, TaxCharacter},
 };
 
-const HIGH_TO_LOW_RISK: [StockSymbol; 9] = [
-    StockSymbol::VWO,
-    StockSymbol::VXUS,
-    StockSymbol::VB,
-    StockSymbol::VO,
-    StockSymbol::VV,
-    StockSymbol::BNDX,
-    StockSymbol::BND,
-    StockSymbol::VTC,
-    StockSymbol::VTIP,
-];
-
 /// to_buy calculates how much of each stock and bond should be bought and sold to rebalance the
-/// portfolio.
-pub fn to_buy(vanguard_holdings: VanguardHoldings, args: Args) -> Result<VanguardRebalance> {
+/// portfolio.  Holdings outside of Vanguard count towards the allocation of their account kind.
+/// The retirement accounts of every owner within the household are rebalanced together.
+pub fn to_buy(
+    vanguard_holdings: VanguardHoldings,
+    household: &Accounts,
+    outside_holdings: &OutsideHoldings,
+    args: Args,
+) -> Result<VanguardRebalance> {
     let mut rebalance = VanguardRebalance::new();
+    let weights = args.allocation_weights()?;
+    let glide_paths = args.glide_paths()?;
+    // Cash added to or withdrawn from an account is part of the account before it is rebalanced
+    let accounts = vanguard_holdings
+        .accounts()
+        .iter()
+        .map(|(account, holdings)| {
+            let mut holdings = holdings.clone();
+            holdings.add_cash(args.cash_add(account.number));
+            (account.clone(), holdings)
+        })
+        .collect::<Vec<(Account, ShareValues)>>();
     let (
-        traditional_ira_account_option,
-        roth_ira_account_option,
-        brokerage_account_option,
+        mut retirement_accounts,
         target_overall_retirement_option,
-    ) = retirement_calc(&vanguard_holdings, args.clone())?;
-    if let Some(traditional_account) = traditional_ira_account_option {
-        rebalance.add_account_holdings(traditional_account, HoldingType::TraditionalIra)
-    }
-    if let Some(roth_account) = roth_ira_account_option {
-        rebalance.add_account_holdings(roth_account, HoldingType::RothIra)
-    }
-    if let Some(brokerage_account) = brokerage_account_option {
-        rebalance.add_account_holdings(brokerage_account, HoldingType::Brokerage)
-    } else if let Some(brokerage_holdings) = vanguard_holdings.brokerage_holdings() {
-        rebalance.add_account_holdings(
-            brokerage_calc(vanguard_holdings.stock_quotes(), brokerage_holdings, args)?,
-            HoldingType::Brokerage,
-        )
+        location_comparison_option,
+        notices,
+    ) = retirement_calc(
+        &accounts,
+        &vanguard_holdings,
+        household,
+        outside_holdings,
+        &weights,
+        &glide_paths,
+        args.clone(),
+    )?;
+    // Outside holdings of an account kind which is not part of the retirement accounts count
+    // towards the first account of that kind
+    let mut outside_kinds = HashSet::new();
+    for (account, holdings) in accounts {
+        if let Some(retirement_account) = retirement_accounts.remove(&account.number) {
+            rebalance.add_account_holdings(account, retirement_account)
+        } else {
+            let outside_exposure = if outside_kinds.insert(account.kind) {
+                outside_holdings.exposure(account.kind)?
+            } else {
+                ShareValues::new()
+            };
+            let brokerage_holdings = brokerage_calc(
+                &vanguard_holdings,
+                &account,
+                holdings,
+                outside_exposure,
+                brokerage_allocations(&account, &glide_paths, &args)?,
+                &weights,
+                args.clone(),
+            )?;
+            rebalance.add_account_holdings(account, brokerage_holdings)
+        }
     }
     if let Some(target_overall_retirement) = target_overall_retirement_option {
         rebalance.add_retirement_target(target_overall_retirement);
     }
+    if let Some(location_comparison) = location_comparison_option {
+        rebalance.add_location_comparison(location_comparison);
+    }
+    for notice in notices {
+        rebalance.add_notice(notice);
+    }
     Ok(rebalance)
 }
 
-/// brokerage_calc calculates the amount of stocks and bonds that should be bought/sold within the
-/// brokerage account in order to rebalance
+/// brokerage_allocations returns the allocation of an account which is balanced on its own.  An
+/// account with a goal year, or any such account when a brokerage goal year is given, follows its
+/// glide path to the goal year, otherwise the brokerage percentages are used.  The glide path is
+/// the account's own when it has one, otherwise the brokerage glide path.
+fn brokerage_allocations(
+    account: &Account,
+    glide_paths: &GlidePaths,
+    args: &Args,
+) -> Result<Allocations> {
+    match account.goal_year.or(args.brokerage_goal_year_option) {
+        Some(goal_year) => glide_paths
+            .glide_path(
+                account
+                    .glide_path
+                    .as_deref()
+                    .unwrap_or(&args.brokerage_glide_path),
+            )?
+            .allocations(goal_year)
+            .with_context(|| format!("Goal year of {}", account)),
+        None => Allocations::custom(
+            args.percent_stock_brokerage,
+            args.percent_bond_brokerage,
+            args.percent_inflation_brokerage,
+        ),
+    }
+}
+
+/// brokerage_calc calculates the amount of stocks and bonds that should be bought/sold within an
+/// account which is balanced on its own, such as the brokerage account or an education account,
+/// in order to rebalance.  Tax exempt funds are only held within taxable accounts.  The cash
+/// reserve is carved out of the account before the allocation is applied.
 fn brokerage_calc(
-    quotes: ShareValues,
+    vanguard_holdings: &VanguardHoldings,
+    account: &Account,
     mut brokerage: ShareValues,
+    outside_exposure: ShareValues,
+    asset_allocations: Allocations,
+    weights: &AllocationWeights,
     args: Args,
 ) -> Result<AccountHoldings> {
-    brokerage.add_stock_value(
-        StockSymbol::VMFXX,
-        brokerage.stock_value(StockSymbol::VMFXX) + args.brokerage_cash_add,
-    );
-    brokerage.add_outside_stock_value(args.brokerage_us_stock_add + args.brokerage_int_stock_add);
-    brokerage.add_outside_bond_value(args.brokerage_us_bond_add + args.brokerage_int_bond_add);
-    let asset_allocations = Allocations::custom(
-        args.percent_stock_brokerage,
-        args.percent_bond_brokerage,
-        0.0,
-    )?;
-    let sub_allocations = SubAllocations::new_custom(asset_allocations)?;
-    let target_holdings = ShareValues::new_target(
+    let trade_costs = args.trade_costs()?;
+    let quotes = vanguard_holdings.stock_quotes();
+    let cash_reserve = cash_reserve(account, &brokerage, &args)?;
+    brokerage.add_outside_exposure(outside_exposure.clone());
+    let mut sub_allocations = SubAllocations::new_custom(asset_allocations, weights)?;
+    if account.kind != AccountKind::Taxable {
+        sub_allocations = sub_allocations.without_tax_exempt()?;
+    }
+    let mut target_holdings = ShareValues::new_target(
         sub_allocations,
-        brokerage.total_value(),
-        args.brokerage_us_stock_add,
-        args.brokerage_us_bond_add,
-        args.brokerage_int_stock_add,
-        args.brokerage_int_bond_add,
-    );
-    let difference = target_holdings - brokerage;
-    let stock_purchase = difference / quotes;
-    Ok(AccountHoldings::new(
-        brokerage,
+        brokerage.total_value() - cash_reserve,
+        outside_exposure,
+        brokerage.kept(),
+    )?;
+    target_holdings.add_cash(cash_reserve);
+    let target_holdings = target_holdings.use_mutual_fund_share_classes(&brokerage);
+    let target_holdings = policy_target(
+        account,
+        &brokerage,
         target_holdings,
-        stock_purchase,
-    ))
+        vanguard_holdings,
+        &args,
+    );
+    let target_holdings = match &trade_costs {
+        Some(trade_costs) => trade_costs.apply(account.number, &brokerage, target_holdings),
+        None => target_holdings,
+    };
+    let difference = target_holdings.clone() - brokerage.clone();
+    let stock_purchase = difference.to_orders(quotes.clone(), args.share_decimals);
+    let mut brokerage_holdings = AccountHoldings::new(brokerage, target_holdings, stock_purchase);
+    brokerage_holdings.add_cash_reserve(cash_reserve);
+    brokerage_holdings.add_withdrawal(args.withdrawal(account.number));
+    if args.rounds_whole_shares(account) {
+        brokerage_holdings.round_whole_shares(&quotes, args.share_decimals);
+    }
+    add_lot_sales(account, &mut brokerage_holdings, vanguard_holdings, &args);
+    if let Some(trade_costs) = &trade_costs {
+        brokerage_holdings.add_trade_costs(trade_costs, account.number, &quotes);
+    }
+    if args.execution_plan {
+        brokerage_holdings.add_execution_plan(&quotes);
+    }
+    Ok(brokerage_holdings)
 }
 
-type TraditionalIraAccount = AccountHoldings;
-type RothIraAccount = AccountHoldings;
-type BrokerageAccount = AccountHoldings;
-type TargetOverallRetirement = ShareValues;
+// policy_target applies the rebalance policy to the target of the account, where the sales of a
+// taxable account with tax lots are limited to the maximum tax cost.  An account with a
+// withdrawal only sells to raise it, where a taxable account first sells the lots which do not
+// realize a short term gain when the withdrawal is set to follow the tax lots.
+fn policy_target(
+    account: &Account,
+    holdings: &ShareValues,
+    target: ShareValues,
+    vanguard_holdings: &VanguardHoldings,
+    args: &Args,
+) -> ShareValues {
+    if args.withdrawal(account.number).is_zero() {
+        let target = args.policy.apply(holdings, target);
+        return match (
+            &args.tax_policy_option,
+            vanguard_holdings.lots(account.number),
+        ) {
+            (Some(tax_policy), Some(lots)) if account.kind == AccountKind::Taxable => tax_policy
+                .limit_sales(
+                    holdings,
+                    target,
+                    &lots,
+                    &vanguard_holdings.stock_quotes(),
+                    Local::now().date_naive(),
+                ),
+            _ => target,
+        };
+    }
+    let mut first_sales_option = None;
+    if args.withdraw_by_lots && account.kind == AccountKind::Taxable {
+        if let Some(lots) = vanguard_holdings.lots(account.number) {
+            let quotes = vanguard_holdings.stock_quotes();
+            let today = Local::now().date_naive();
+            let mut first_sales = ShareValues::new();
+            for symbol in lots.symbols() {
+                let price = quotes.stock_value(symbol.clone());
+                first_sales.add_stock_value(
+                    symbol.clone(),
+                    lots.value_without_short_term_gain(&symbol, price, today),
+                );
+            }
+            first_sales_option = Some(first_sales);
+        }
+    }
+    withdraw(holdings, target, first_sales_option.as_ref())
+}
 
-/// retirement_calc calculates the amount of stocks and bonds that should be bought/sold within the
-/// retirement account in order to rebalance.  If there are both a roth and traditional IRA
-/// account, the riskiest assets are shifted towards the roth account while the less risky assets
-/// are within the tradiitonal account.  This is to keep the largest growth within the account that
-/// is not taxed after withdrawals
-fn retirement_calc(
+// add_lot_sales lists the tax lots sold by the orders of a taxable account with tax lots, along with
+// their estimated gains and tax, when a lot selection is given.  This needs to run after the orders
+// are rounded to whole shares so that the lots match the final orders.
+fn add_lot_sales(
+    account: &Account,
+    account_holdings: &mut AccountHoldings,
     vanguard_holdings: &VanguardHoldings,
-    args: Args,
-) -> Result<(
-    Option<TraditionalIraAccount>,
-    Option<RothIraAccount>,
-    Option<BrokerageAccount>,
-    Option<TargetOverallRetirement>,
-)> {
-    let mut traditional_ira_account_option = None;
-    let mut roth_ira_account_option = None;
-    let mut brokerage_account_option = None;
-    let mut target_overall_retirement_option = None;
+    args: &Args,
+) {
+    if let (Some(tax_policy), Some(lots)) = (
+        &args.tax_policy_option,
+        vanguard_holdings.lots(account.number),
+    ) {
+        account_holdings.add_lot_sales(
+            &lots,
+            &vanguard_holdings.stock_quotes(),
+            tax_policy,
+            Local::now().date_naive(),
+        );
+    }
+}
+
+// cash_reserve returns the cash kept within the settlement fund of the account, which needs to fit
+// within the part of the account that is not kept at its current value
+fn cash_reserve(account: &Account, holdings: &ShareValues, args: &Args) -> Result<Decimal> {
+    let cash_reserve = match args.cash_reserve(account) {
+        Some(cash_reserve) => cash_reserve.amount(holdings.total_value()),
+        None => return Ok(Decimal::ZERO),
+    };
+    let available = holdings.total_value() - holdings.kept().total_value();
+    ensure!(
+        cash_reserve <= available,
+        "Cash reserve of ${:.2} within {} is more than the ${:.2} available to rebalance",
+        cash_reserve,
+        account,
+        available
+    );
+    Ok(cash_reserve)
+}
 
-    let mut allocations = Allocations::new();
+/// is_retirement returns whether accounts of the kind are balanced together as the retirement
+/// accounts.  The brokerage account is only included when asked for, while education accounts
+/// are always balanced on their own.
+fn is_retirement(kind: AccountKind, args: &Args) -> bool {
+    match kind {
+        AccountKind::TaxDeferred | AccountKind::TaxFree | AccountKind::Hsa => true,
+        AccountKind::Taxable => args.use_brokerage_retirement,
+        AccountKind::Education => false,
+    }
+}
 
-    if let Some(retirement_year) = args.retirement_year_option {
-        allocations = Allocations::retirement(retirement_year)?;
+// The order in which the retirement accounts are filled.  Tax-free accounts take the riskiest
+// assets, the brokerage takes the least risky assets, and tax-deferred accounts take the rest.
+fn placement_order(kind: AccountKind) -> u32 {
+    match kind {
+        AccountKind::TaxFree | AccountKind::Hsa => 0,
+        AccountKind::Taxable => 1,
+        AccountKind::TaxDeferred => 2,
+        AccountKind::Education => 3,
     }
+}
+
+// The order in which an account within the retirement accounts is filled from the overall target.
+// Tax-free accounts take the riskiest funds first and the other accounts the least risky.
+// Taxable accounts take tax exempt funds first and REITs last, leaving REITs to the
+// tax-advantaged accounts, which never take tax exempt funds.
+fn fill_order(kind: AccountKind) -> Vec<StockSymbol> {
+    let universe = fund_universe();
+    let mut fill_order = universe.high_to_low_risk();
+    if !kind.is_tax_free() {
+        fill_order.reverse();
+    }
+    if kind == AccountKind::Taxable {
+        fill_order.sort_by_key(|symbol| match universe.tax_character(symbol) {
+            TaxCharacter::TaxExempt => 0,
+            TaxCharacter::Qualified | TaxCharacter::Interest => 1,
+            TaxCharacter::NonQualified => 2,
+        });
+    } else {
+        fill_order.retain(|symbol| universe.tax_character(symbol) != TaxCharacter::TaxExempt);
+    }
+    fill_order
+}
+
+/// retirement_allocations returns the allocation of the retirement accounts.  Percentages given
+/// through the CLI arguments apply to the whole household.  Otherwise each owner's share of the
+/// retirement accounts, including their holdings outside of Vanguard, follows their own allocation
+/// strategy, and the household allocation blends them by value.  Accounts without an owner follow
+/// the birth year and retirement year of the CLI arguments.  Each account follows its own strategy
+/// and glide path when it has them, otherwise those of its owner and then the household.
+fn retirement_allocations(
+    retirement_accounts: &[(Account, ShareValues)],
+    household: &Accounts,
+    outside_holdings: &OutsideHoldings,
+    glide_paths: &GlidePaths,
+    args: &Args,
+) -> Result<Allocations> {
     if let Some(stock_percent) = args.percent_stock_retirement_option {
         let bond_percent;
         if let Some(input_bond_percent) = args.percent_bond_retirement_option {
             bond_percent = input_bond_percent;
         } else {
-            bond_percent = 100.0 - stock_percent;
+            bond_percent = Decimal::ONE_HUNDRED - stock_percent;
         }
-        allocations = Allocations::custom(stock_percent, bond_percent, 0.0)?;
+        return Allocations::custom(stock_percent, bond_percent, Decimal::ZERO);
     } else if let Some(bond_percent) = args.percent_bond_retirement_option {
-        let stock_percent = 100.0 - bond_percent;
-        allocations = Allocations::custom(stock_percent, bond_percent, 0.0)?;
+        let stock_percent = Decimal::ONE_HUNDRED - bond_percent;
+        return Allocations::custom(stock_percent, bond_percent, Decimal::ZERO);
     };
 
-    let sub_allocations = SubAllocations::new_custom(allocations)?;
+    // Outside holdings of someone who is not an owner within the household count as unowned and
+    // follow the strategy and glide path of the household
+    let this_year = chrono::Local::now().year();
+    let mut owner_allocations = Vec::new();
+    let retirement_values = retirement_accounts
+        .iter()
+        .map(|(account, holdings)| {
+            let owner = account
+                .owner
+                .as_deref()
+                .and_then(|owner| household.owner(owner));
+            (
+                owner,
+                account.strategy,
+                account.glide_path.as_deref(),
+                holdings.total_value(),
+            )
+        })
+        .chain(
+            outside_holdings
+                .holdings()
+                .iter()
+                .filter(|holding| is_retirement(holding.account, args))
+                .map(|holding| {
+                    let owner = holding
+                        .owner
+                        .as_deref()
+                        .and_then(|owner| household.owner(owner));
+                    (owner, None, None, holding.value)
+                }),
+        );
+    for (owner, strategy_option, glide_path_option, value) in retirement_values {
+        let strategy = strategy_option
+            .or(owner.and_then(|owner| owner.strategy))
+            .unwrap_or(args.strategy);
+        let glide_path = glide_paths.glide_path(glide_path_option.unwrap_or(&args.glide_path))?;
+        let allocations = strategy
+            .allocations(&args.investor(owner, glide_path), this_year)
+            .with_context(|| match owner {
+                Some(owner) => format!("Allocation strategy of {}", owner.name),
+                None => "Allocation strategy of the accounts without an owner".to_string(),
+            })?;
+        owner_allocations.push((allocations, value));
+    }
+    owner_allocations.dedup_by(|(allocations, value), (kept_allocations, kept_value)| {
+        let same = allocations == kept_allocations;
+        if same {
+            *kept_value += *value;
+        }
+        same
+    });
+    match owner_allocations.as_slice() {
+        [] => Ok(Allocations::new()),
+        [(allocations, _)] => Ok(*allocations),
+        _ if owner_allocations.iter().all(|(_, value)| value.is_zero()) => {
+            Ok(owner_allocations[0].0)
+        }
+        _ => Allocations::weighted(&owner_allocations),
+    }
+}
 
-    let mut holdings_value = 0.0;
-    let mut us_stock_add = 0.0;
-    let mut us_bond_add = 0.0;
-    let mut int_stock_add = 0.0;
-    let mut int_bond_add = 0.0;
+type TargetOverallRetirement = ShareValues;
+type RetirementAccounts = (
+    HashMap<u32, AccountHoldings>,
+    Option<TargetOverallRetirement>,
+    Option<LocationComparison>,
+    Vec<String>,
+);
 
-    let mut include_roth = false;
-    let mut include_traditional = false;
-    let mut include_brokerage = false;
+/// retirement_calc calculates the amount of stocks and bonds that should be bought/sold within the
+/// retirement accounts in order to rebalance.  The allocation is solved across all retirement
+/// accounts together, where the funds are placed within the accounts where they are worth the most
+/// after taxes at the end of the horizon.  The placement is compared against the greedy placement,
+/// which shifts the riskiest assets towards the tax-free accounts, such as roth IRAs and HSAs, and
+/// the less risky assets towards the tax-deferred accounts.  Outside holdings of every account
+/// kind within the retirement accounts count towards the allocation, and the accounts of every
+/// owner within the household are balanced together.
+fn retirement_calc(
+    accounts: &[(Account, ShareValues)],
+    vanguard_holdings: &VanguardHoldings,
+    household: &Accounts,
+    outside_holdings: &OutsideHoldings,
+    weights: &AllocationWeights,
+    glide_paths: &GlidePaths,
+    args: Args,
+) -> Result<RetirementAccounts> {
+    let trade_costs = args.trade_costs()?;
+    let mut retirement_holdings = HashMap::new();
 
-    let mut roth_total = 0.0;
-    let mut brokerage_total = 0.0;
+    let mut retirement_accounts = accounts
+        .iter()
+        .filter(|(account, _)| is_retirement(account.kind, &args))
+        .cloned()
+        .collect::<Vec<(Account, ShareValues)>>();
+    if retirement_accounts.is_empty() {
+        return Ok((retirement_holdings, None, None, Vec::new()));
+    }
+    retirement_accounts.sort_by_key(|(account, _)| placement_order(account.kind));
 
-    let mut roth_holdings_final = ShareValues::new();
-    let mut brokerage_holdings_final = ShareValues::new();
-    let mut traditional_holdings_final = ShareValues::new();
-    if let Some(mut roth_holdings) = vanguard_holdings.roth_ira_holdings() {
-        roth_holdings.add_stock_value(
-            StockSymbol::VMFXX,
-            roth_holdings.stock_value(StockSymbol::VMFXX) + args.roth_cash_add,
-        );
-        holdings_value += roth_holdings.total_value();
-        us_stock_add += args.roth_us_stock_add;
-        us_bond_add += args.roth_us_bond_add;
-        int_stock_add += args.roth_int_stock_add;
-        int_bond_add += args.roth_int_bond_add;
-        include_roth = true;
-        roth_total = roth_holdings.total_value();
-        roth_holdings_final = roth_holdings;
+    let allocations = retirement_allocations(
+        &retirement_accounts,
+        household,
+        outside_holdings,
+        glide_paths,
+        &args,
+    )?;
+    // Tax exempt funds are only held when a taxable account is balanced with the retirement
+    // accounts
+    let mut sub_allocations = SubAllocations::new_custom(allocations, weights)?;
+    if !retirement_accounts
+        .iter()
+        .any(|(account, _)| account.kind == AccountKind::Taxable)
+    {
+        sub_allocations = sub_allocations.without_tax_exempt()?;
     }
-    // If there are both Roth and Traditional accounts, shift the risky assets to the roth
-    // account
-    if let Some(mut traditional_holdings) = vanguard_holdings.traditional_ira_holdings() {
-        traditional_holdings.add_stock_value(
-            StockSymbol::VMFXX,
-            traditional_holdings.stock_value(StockSymbol::VMFXX) + args.traditional_cash_add,
-        );
-        holdings_value += traditional_holdings.total_value();
-        us_stock_add += args.traditional_us_stock_add;
-        us_bond_add += args.traditional_us_bond_add;
-        int_stock_add += args.traditional_int_stock_add;
-        int_bond_add += args.traditional_int_bond_add;
-        include_traditional = true;
-        traditional_holdings_final = traditional_holdings;
+
+    // Cash reserves are carved out of each account before the allocation is applied
+    let mut holdings_value = Decimal::ZERO;
+    let mut kept_holdings = ShareValues::new();
+    let mut cash_reserves = Vec::new();
+    for (account, holdings) in &retirement_accounts {
+        let cash_reserve = cash_reserve(account, holdings, &args)?;
+        holdings_value += holdings.total_value() - cash_reserve;
+        kept_holdings = kept_holdings + holdings.kept();
+        cash_reserves.push(cash_reserve);
     }
-    if args.use_brokerage_retirement {
-        if let Some(mut brokerage_holdings) = vanguard_holdings.brokerage_holdings() {
-            brokerage_holdings.add_stock_value(
-                StockSymbol::VMFXX,
-                brokerage_holdings.stock_value(StockSymbol::VMFXX) + args.brokerage_cash_add,
-            );
-            holdings_value += brokerage_holdings.total_value();
-            us_stock_add += args.brokerage_us_stock_add;
-            us_bond_add += args.brokerage_us_bond_add;
-            int_stock_add += args.brokerage_int_stock_add;
-            int_bond_add += args.brokerage_int_bond_add;
-            include_brokerage = true;
-            brokerage_total = brokerage_holdings.total_value();
-            brokerage_holdings_final = brokerage_holdings;
+    let mut outside_exposure = ShareValues::new();
+    for kind in [
+        AccountKind::TaxDeferred,
+        AccountKind::TaxFree,
+        AccountKind::Hsa,
+        AccountKind::Taxable,
+    ] {
+        if is_retirement(kind, &args) {
+            outside_exposure = outside_exposure + outside_holdings.exposure(kind)?;
         }
     }
 
-    let mut target_overall_retirement = ShareValues::new();
-    if [include_brokerage, include_traditional, include_roth]
+    let mut target_overall_retirement = ShareValues::new_target(
+        sub_allocations,
+        holdings_value,
+        outside_exposure,
+        kept_holdings,
+    )?;
+
+    // Each account is filled from the overall target, where the last account, which is a
+    // tax-deferred account when there is one, takes the rest.  Blended funds and a kept money
+    // market stay within the account, so only the rest of the account is filled.
+    let fill_accounts = retirement_accounts
         .iter()
-        .any(|&x| x)
-    {
-        target_overall_retirement = ShareValues::new_target(
-            sub_allocations,
-            holdings_value,
-            us_stock_add,
-            us_bond_add,
-            int_stock_add,
-            int_bond_add,
-        );
-        target_overall_retirement_option = Some(target_overall_retirement);
+        .zip(&cash_reserves)
+        .map(|((account, holdings), cash_reserve)| {
+            (
+                account.clone(),
+                holdings.total_value() - holdings.kept().total_value() - cash_reserve,
+            )
+        })
+        .collect::<Vec<(Account, Decimal)>>();
+    // A single account takes the whole target, so there is nothing to place.  When the built-in
+    // assumptions do not cover the funds, such as with a custom fund universe, the greedy fill is
+    // kept with a notice, while an asset location file given on purpose needs to be valid.
+    let mut notices = Vec::new();
+    let mut fills = greedy_fills(&fill_accounts, &target_overall_retirement)?;
+    let mut location_comparison_option = None;
+    if fill_accounts.len() > 1 {
+        let accounts = fill_accounts
+            .iter()
+            .map(|(account, _)| account.clone())
+            .collect::<Vec<Account>>();
+        let optimized = args.location_assumptions().and_then(|assumptions| {
+            let optimized_fills = assumptions.optimize(
+                &args.tax_rates,
+                &fill_accounts,
+                &target_overall_retirement,
+            )?;
+            let comparison =
+                assumptions.compare(&args.tax_rates, &accounts, &fills, &optimized_fills)?;
+            Ok((optimized_fills, comparison))
+        });
+        match optimized {
+            Ok((optimized_fills, comparison)) => {
+                fills = optimized_fills;
+                location_comparison_option = Some(comparison);
+            }
+            Err(err) if args.asset_location_path_option.is_none() => notices.push(format!(
+                "The retirement accounts are filled by risk since the asset location could not be \
+                optimized: {:#}",
+                err
+            )),
+            Err(err) => return Err(err),
+        }
     }
 
-    let mut remaining_target = target_overall_retirement;
-    if include_roth {
-        let mut roth_target = ShareValues::new();
-        for stock_symbol in HIGH_TO_LOW_RISK {
-            let value = target_overall_retirement
-                .stock_value(stock_symbol.clone())
-                .min(roth_total);
-            roth_total -= value;
-            roth_target.add_stock_value(stock_symbol.clone(), value);
-            if roth_total <= 0.0 {
-                break;
+    let mut remaining_target = target_overall_retirement.clone();
+    let last_index = retirement_accounts.len() - 1;
+    for (index, (((account, holdings), cash_reserve), fill)) in retirement_accounts
+        .into_iter()
+        .zip(cash_reserves)
+        .zip(fills)
+        .enumerate()
+    {
+        let mut target = if index == last_index {
+            if account.kind != AccountKind::Taxable {
+                for stock_symbol in fund_universe().high_to_low_risk() {
+                    if fund_universe().tax_character(&stock_symbol) == TaxCharacter::TaxExempt
+                        && remaining_target.stock_value(stock_symbol.clone()) > Decimal::ZERO
+                    {
+                        notices.push(format!(
+                            "{} does not fit within the taxable accounts and is held within {}",
+                            stock_symbol, account
+                        ));
+                    }
+                }
             }
+            remaining_target.clone()
+        } else {
+            let target = holdings.kept() + fill;
+            ensure!(
+                target.total_value() + cash_reserve == holdings.total_value(),
+                "{account} target and total do not match\n\n{account} target:\n{target}\n\n{account}:\n{holdings}",
+            );
+            remaining_target = remaining_target - target.clone();
+            target
+        };
+        target.add_cash(cash_reserve);
+        target_overall_retirement.add_cash(cash_reserve);
+        let target = target.use_mutual_fund_share_classes(&holdings);
+        let target = policy_target(&account, &holdings, target, vanguard_holdings, &args);
+        let target = match &trade_costs {
+            Some(trade_costs) => trade_costs.apply(account.number, &holdings, target),
+            None => target,
+        };
+        let difference = target.clone() - holdings.clone();
+        let purchase = difference.to_orders(vanguard_holdings.stock_quotes(), args.share_decimals);
+        let mut account_holdings = AccountHoldings::new(holdings, target, purchase);
+        account_holdings.add_cash_reserve(cash_reserve);
+        account_holdings.add_withdrawal(args.withdrawal(account.number));
+        if args.rounds_whole_shares(&account) {
+            account_holdings
+                .round_whole_shares(&vanguard_holdings.stock_quotes(), args.share_decimals);
         }
-        ensure!(roth_total == 0.0, "Unexpected leftover roth cash");
-        ensure!(
-            roth_target.total_value() > (0.99 * roth_holdings_final.total_value())
-                && roth_target.total_value() < (1.01 * roth_holdings_final.total_value()),
-            "Roth target and total do not match\n\nRoth target:\n{}\n\nRoth:\n{}",
-            roth_target,
-            roth_holdings_final
-        );
-        let roth_difference = roth_target - roth_holdings_final;
-        let roth_purchase = roth_difference / vanguard_holdings.stock_quotes();
-        let roth_account = AccountHoldings::new(roth_holdings_final, roth_target, roth_purchase);
-        remaining_target = remaining_target - roth_target;
-        roth_ira_account_option = Some(roth_account);
+        add_lot_sales(&account, &mut account_holdings, vanguard_holdings, &args);
+        if let Some(trade_costs) = &trade_costs {
+            account_holdings.add_trade_costs(
+                trade_costs,
+                account.number,
+                &vanguard_holdings.stock_quotes(),
+            );
+        }
+        if args.execution_plan {
+            account_holdings.add_execution_plan(&vanguard_holdings.stock_quotes());
+        }
+        retirement_holdings.insert(account.number, account_holdings);
     }
 
-    if include_brokerage {
-        let mut brokerage_target = ShareValues::new();
-        for stock_symbol in HIGH_TO_LOW_RISK.iter().rev() {
-            let value = target_overall_retirement
+    Ok((
+        retirement_holdings,
+        Some(target_overall_retirement),
+        location_comparison_option,
+        notices,
+    ))
+}
+
+// greedy_fills fills each account other than the last from the overall target by walking the
+// fill order of its account kind, where the last account takes the rest of the ranked funds.  The
+// fill of each account is returned in the same order as the accounts.  This is the baseline which
+// the optimized asset location is compared against.
+fn greedy_fills(
+    fill_accounts: &[(Account, Decimal)],
+    target_overall: &ShareValues,
+) -> Result<Vec<ShareValues>> {
+    let mut remaining_target = target_overall.clone();
+    let mut fills = Vec::new();
+    for (account, fill_total) in fill_accounts.iter().take(fill_accounts.len() - 1) {
+        let mut fill = ShareValues::new();
+        let mut fill_total = *fill_total;
+        for stock_symbol in fill_order(account.kind) {
+            let value = remaining_target
                 .stock_value(stock_symbol.clone())
-                .min(brokerage_total);
-            brokerage_total -= value;
-            brokerage_target.add_stock_value(stock_symbol.clone(), value);
-            if brokerage_total <= 0.0 {
+                .max(Decimal::ZERO)
+                .min(fill_total);
+            fill_total -= value;
+            fill.add_stock_value(stock_symbol, value);
+            if fill_total <= Decimal::ZERO {
                 break;
             }
         }
-        ensure!(brokerage_total == 0.0, "Unexpected leftover brokerage cash");
         ensure!(
-            brokerage_target.total_value() > (0.99 * brokerage_holdings_final.total_value())
-                && brokerage_target.total_value() < (1.01 * brokerage_holdings_final.total_value()),
-            "brokerage target and total do not match\n\nbrokerage target:\n{}\n\nbrokerage:\n{}",
-            brokerage_target,
-            brokerage_holdings_final
-        );
-        let brokerage_difference = brokerage_target - brokerage_holdings_final;
-        let brokerage_purchase = brokerage_difference / vanguard_holdings.stock_quotes();
-        let brokerage_account = AccountHoldings::new(
-            brokerage_holdings_final,
-            brokerage_target,
-            brokerage_purchase,
+            fill_total.is_zero(),
+            "Unexpected leftover cash within {}",
+            account
         );
-        remaining_target = remaining_target - brokerage_target;
-        brokerage_account_option = Some(brokerage_account);
+        remaining_target = remaining_target - fill.clone();
+        fills.push(fill);
     }
-
-    if include_traditional {
-        let traditional_target = remaining_target;
-        let traditional_difference = traditional_target - traditional_holdings_final;
-        let traditional_purchase = traditional_difference / vanguard_holdings.stock_quotes();
-        let traditional_account = AccountHoldings::new(
-            traditional_holdings_final,
-            traditional_target,
-            traditional_purchase,
-        );
-        traditional_ira_account_option = Some(traditional_account);
+    let mut last_fill = ShareValues::new();
+    for stock_symbol in fund_universe().high_to_low_risk() {
+        let value = remaining_target.stock_value(stock_symbol.clone());
+        if !value.is_zero() {
+            last_fill.add_stock_value(stock_symbol, value);
+        }
     }
-
-    // } else {
-    //     let roth_target = ShareValues::new_target(
-    //         sub_allocations,
-    //         roth_holdings.total_value(),
-    //         args.roth_us_stock_add,
-    //         args.roth_us_bond_add,
-    //         args.roth_int_stock_add,
-    //         args.roth_int_bond_add,
-    //     );
-    //     let roth_difference = roth_target - roth_holdings;
-    //     let roth_purchase = roth_difference / vanguard_holdings.stock_quotes();
-    //     let roth_account = AccountHoldings::new(roth_holdings, roth_target, roth_purchase);
-    //     roth_ira_account_option = Some(roth_account);
-    // }
-    // } else if let Some(mut traditional_holdings) = vanguard_holdings.traditional_ira_holdings() {
-    //     traditional_holdings.add_stock_value(
-    //         StockSymbol::VMFXX,
-    //         traditional_holdings.stock_value(StockSymbol::VMFXX) + args.traditional_cash_add,
-    //     );
-    //     let traditional_target = ShareValues::new_target(
-    //         sub_allocations,
-    //         traditional_holdings.total_value(),
-    //         args.traditional_us_stock_add,
-    //         args.traditional_us_bond_add,
-    //         args.traditional_int_stock_add,
-    //         args.traditional_int_bond_add,
-    //     );
-    //     let traditional_difference = traditional_target - traditional_holdings;
-    //     let traditional_purchase = traditional_difference / vanguard_holdings.stock_quotes();
-    //     let traditional_account = AccountHoldings::new(
-    //         traditional_holdings,
-    //         traditional_target,
-    //         traditional_purchase,
-    //     );
-    //     traditional_ira_account_option = Some(traditional_account)
-    // }
-    Ok((
-        traditional_ira_account_option,
-        roth_ira_account_option,
-        brokerage_account_option,
-        target_overall_retirement_option,
-    ))
+    fills.push(last_fill);
+    Ok(fills)
 }
 
 // Calculates the minimum distribution for an unmarried individual or someone without a spouse
-// greater than 10 years younger.
+// greater than 10 years younger.  The distribution is rounded to the nearest cent.
 pub fn calculate_minimum_distribution(
     age: u32,
-    traditional_value: f32,
+    traditional_value: Decimal,
     csv_path: &str,
-) -> Result<f32> {
+) -> Result<Decimal> {
     // Distribution table retrieved from here appendix B: https://www.irs.gov/publications/p590b#en_US_2022_publink100090310
     // May need to periodically be updated
     let csv_file = File::open(csv_path).context("Minimum distribution file from IRS not found")?;
@@ -352,16 +664,23 @@ pub fn calculate_minimum_distribution(
                     header = row_split
                 } else {
                     ensure!(header.iter().take(2).collect::<Vec<&String>>() == ["Age", "Distribution Period"], "Header of distribution table ({:?}) does not match ['Age','Distribution Period']", header);
-                    distribution_table
-                        .insert(row_split[0].parse::<u32>()?, row_split[1].parse::<f32>()?);
+                    distribution_table.insert(
+                        row_split[0].parse::<u32>()?,
+                        row_split[1].parse::<Decimal>()?,
+                    );
                 }
             }
         }
     }
 
-    if distribution_table.contains_key(&age) {
-        Ok(traditional_value / distribution_table[&age])
+    if let Some(distribution_period) = distribution_table.get(&age) {
+        ensure!(
+            !distribution_period.is_zero(),
+            "Distribution period for age {} is zero",
+            age
+        );
+        Ok(round_dollars(traditional_value / distribution_period))
     } else {
-        Ok(0.0)
+        Ok(Decimal::ZERO)
     }
 }
@@ -0,0 +1,395 @@
+use crate::{
+    holdings::{ShareValues, StockSymbol},
+    policy::traded_symbols,
+    universe::fund_universe,
+};
+use anyhow::{ensure, Context, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::fs;
+
+// DEFAULT_TRADE_COSTS holds the built-in trade costs of the cost aware rebalance
+const DEFAULT_TRADE_COSTS: &str = include_str!("../trade_costs.toml");
+
+/// TradeCost is what a trade costs within an account.  ETF trades pay the commission and the
+/// bid-ask spread, in percent of the trade, while mutual fund trades pay the mutual fund fee.
+/// Trades smaller than the minimum trade are not made.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TradeCost {
+    pub commission: Decimal,
+    pub spread: Decimal,
+    pub minimum_trade: Decimal,
+    pub mutual_fund_fee: Decimal,
+}
+
+/// CostOverride changes the costs of an account, a fund, or a fund within an account, where only
+/// the costs which are given are changed
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CostOverride {
+    #[serde(default)]
+    pub account: Option<u32>,
+    #[serde(default)]
+    pub symbol: Option<StockSymbol>,
+    #[serde(default)]
+    pub commission: Option<Decimal>,
+    #[serde(default)]
+    pub spread: Option<Decimal>,
+    #[serde(default)]
+    pub minimum_trade: Option<Decimal>,
+    #[serde(default)]
+    pub mutual_fund_fee: Option<Decimal>,
+}
+
+impl CostOverride {
+    // specificity returns how specific the override is, where the overrides of a fund within an
+    // account are applied last.  None is returned when the override does not apply to the fund
+    // within the account.
+    fn specificity(&self, account_number: u32, symbol: &StockSymbol) -> Option<u32> {
+        match (&self.account, &self.symbol) {
+            (Some(account), _) if *account != account_number => None,
+            (_, Some(override_symbol)) if override_symbol != symbol => None,
+            (Some(_), Some(_)) => Some(2),
+            (None, Some(_)) => Some(1),
+            _ => Some(0),
+        }
+    }
+}
+
+/// TradeCosts is the cost model of the cost aware rebalance.  The drift cost is what each dollar
+/// held away from its target costs, in percent, which is what a trade is worth for each dollar of
+/// drift it removes.  The costs apply to every trade unless they are overridden for an account or
+/// a fund.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TradeCosts {
+    pub drift_cost: Decimal,
+    pub commission: Decimal,
+    pub spread: Decimal,
+    pub minimum_trade: Decimal,
+    pub mutual_fund_fee: Decimal,
+    #[serde(default, rename = "override")]
+    pub overrides: Vec<CostOverride>,
+}
+
+impl TradeCosts {
+    /// from_toml reads the trade costs within the contents of a TOML file
+    ///
+    /// # Errors
+    ///
+    /// Errors when a cost is negative or an override does not name an account or a fund
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{costs::TradeCosts, holdings::StockSymbol};
+    ///
+    /// let trade_costs = TradeCosts::from_toml(
+    ///     r#"
+    ///     drift_cost = 1.0
+    ///     commission = 0.0
+    ///     spread = 0.02
+    ///     minimum_trade = 50.0
+    ///     mutual_fund_fee = 0.0
+    ///
+    ///     [[override]]
+    ///     account = 33333333
+    ///     commission = 1.0
+    ///
+    ///     [[override]]
+    ///     symbol = "VWO"
+    ///     spread = 0.05
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// let vwo = trade_costs.trade_cost(33333333, &StockSymbol::new("VWO"));
+    /// assert_eq!(vwo.commission, dec!(1.0));
+    /// assert_eq!(vwo.spread, dec!(0.05));
+    /// assert_eq!(trade_costs.trade_cost(22222222, &StockSymbol::new("VV")).spread, dec!(0.02));
+    ///
+    /// assert!(TradeCosts::from_toml(
+    ///     "drift_cost = 1.0\ncommission = 0.0\nspread = -0.02\nminimum_trade = 0.0\nmutual_fund_fee = 0.0"
+    /// )
+    /// .is_err());
+    /// ```
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let trade_costs: TradeCosts =
+            toml::from_str(contents).context("Unable to parse trade costs")?;
+        trade_costs.validate()?;
+        Ok(trade_costs)
+    }
+
+    /// from_file reads a trade costs TOML file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Trade costs file not found: {}", path))?;
+        Self::from_toml(&contents).with_context(|| format!("Invalid trade costs: {}", path))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let costs = [
+            self.drift_cost,
+            self.commission,
+            self.spread,
+            self.minimum_trade,
+            self.mutual_fund_fee,
+        ];
+        ensure!(
+            costs.iter().all(|cost| !cost.is_sign_negative()),
+            "Trade costs cannot be negative"
+        );
+        for cost_override in &self.overrides {
+            ensure!(
+                cost_override.account.is_some() || cost_override.symbol.is_some(),
+                "A trade cost override needs an account, a fund, or both"
+            );
+            let costs = [
+                cost_override.commission,
+                cost_override.spread,
+                cost_override.minimum_trade,
+                cost_override.mutual_fund_fee,
+            ];
+            ensure!(
+                costs.iter().flatten().all(|cost| !cost.is_sign_negative()),
+                "Trade cost overrides cannot be negative"
+            );
+        }
+        Ok(())
+    }
+
+    /// trade_cost returns the costs of trading the fund within the account after the overrides
+    /// are applied
+    pub fn trade_cost(&self, account_number: u32, symbol: &StockSymbol) -> TradeCost {
+        let mut trade_cost = TradeCost {
+            commission: self.commission,
+            spread: self.spread,
+            minimum_trade: self.minimum_trade,
+            mutual_fund_fee: self.mutual_fund_fee,
+        };
+        let mut overrides = self
+            .overrides
+            .iter()
+            .filter_map(|cost_override| {
+                cost_override
+                    .specificity(account_number, symbol)
+                    .map(|specificity| (specificity, cost_override))
+            })
+            .collect::<Vec<(u32, &CostOverride)>>();
+        overrides.sort_by_key(|(specificity, _)| *specificity);
+        for (_, cost_override) in overrides {
+            trade_cost.commission = cost_override.commission.unwrap_or(trade_cost.commission);
+            trade_cost.spread = cost_override.spread.unwrap_or(trade_cost.spread);
+            trade_cost.minimum_trade = cost_override
+                .minimum_trade
+                .unwrap_or(trade_cost.minimum_trade);
+            trade_cost.mutual_fund_fee = cost_override
+                .mutual_fund_fee
+                .unwrap_or(trade_cost.mutual_fund_fee);
+        }
+        trade_cost
+    }
+
+    /// cost returns the estimated cost of a trade of the dollar amount of the fund within the
+    /// account, where a sale is a negative amount
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{costs::TradeCosts, holdings::StockSymbol};
+    ///
+    /// let trade_costs = TradeCosts::default();
+    /// // ETFs pay the bid-ask spread
+    /// assert_eq!(trade_costs.cost(33333333, &StockSymbol::new("VV"), dec!(-1000)), dec!(0.20));
+    /// // Mutual funds fill at the NAV
+    /// assert_eq!(trade_costs.cost(33333333, &StockSymbol::new("VLCAX"), dec!(1000)), dec!(0));
+    /// ```
+    pub fn cost(&self, account_number: u32, symbol: &StockSymbol, amount: Decimal) -> Decimal {
+        let universe = fund_universe();
+        if amount.is_zero() || universe.is_pooled_cash(symbol) {
+            return Decimal::ZERO;
+        }
+        let trade_cost = self.trade_cost(account_number, symbol);
+        if universe.is_mutual_fund(symbol) {
+            trade_cost.mutual_fund_fee
+        } else {
+            trade_cost.commission + amount.abs() * trade_cost.spread / Decimal::ONE_HUNDRED
+        }
+    }
+
+    /// apply returns the target of the account where the trades which cost more than the drift
+    /// they remove is worth, or are smaller than the minimum trade, are dropped.  The trade worth
+    /// the least is dropped first, and its cash is merged into the other trades of the account: a
+    /// dropped purchase is added to the largest purchase below its target, up to that target, with
+    /// any remainder kept as cash, while the cash a dropped sale does not
+    /// raise is taken from the largest purchases and then from the largest sales.  A sale whose
+    /// cash cannot be made up by the other trades is kept, so the account is never overdrawn.
+    /// Each trade is valued again after every merge, until every trade left is worth its cost.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     costs::TradeCosts,
+    ///     holdings::{ShareValues, StockSymbol},
+    /// };
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("VV"), dec!(5030.0));
+    /// current.add_stock_value(StockSymbol::new("VXUS"), dec!(2500.0));
+    /// current.add_stock_value(StockSymbol::new("BND"), dec!(2470.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(5000.0));
+    /// target.add_stock_value(StockSymbol::new("VXUS"), dec!(3000.0));
+    /// target.add_stock_value(StockSymbol::new("BND"), dec!(2000.0));
+    ///
+    /// // The $30 sale of VV is smaller than the minimum trade, so VXUS is bought with less cash
+    /// let costed = TradeCosts::default().apply(33333333, &current, target);
+    /// assert_eq!(costed.stock_value(StockSymbol::new("VV")), dec!(5030.0));
+    /// assert_eq!(costed.stock_value(StockSymbol::new("VXUS")), dec!(2970.0));
+    /// assert_eq!(costed.stock_value(StockSymbol::new("BND")), dec!(2000.0));
+    /// assert_eq!(costed.cash_value(), dec!(0.0));
+    ///
+    /// // The $40 purchase of VXUS is smaller than the minimum trade, and VV, the largest
+    /// // purchase, is already bought up to its target, so the cash is kept
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("VV"), dec!(4000.0));
+    /// current.add_stock_value(StockSymbol::new("VXUS"), dec!(2960.0));
+    /// current.add_stock_value(StockSymbol::new("BND"), dec!(3000.0));
+    /// current.add_cash(dec!(40.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(5000.0));
+    /// target.add_stock_value(StockSymbol::new("VXUS"), dec!(3000.0));
+    /// target.add_stock_value(StockSymbol::new("BND"), dec!(2000.0));
+    ///
+    /// let costed = TradeCosts::default().apply(33333333, &current, target);
+    /// assert_eq!(costed.stock_value(StockSymbol::new("VV")), dec!(5000.0));
+    /// assert_eq!(costed.stock_value(StockSymbol::new("VXUS")), dec!(2960.0));
+    /// assert_eq!(costed.stock_value(StockSymbol::new("BND")), dec!(2000.0));
+    /// assert_eq!(costed.cash_value(), dec!(40.0));
+    ///
+    /// // The $30 sale of VV raises cash which no other trade can make up, so it is kept
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("VV"), dec!(1030.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(1000.0));
+    /// target.add_cash(dec!(30.0));
+    ///
+    /// let costed = TradeCosts::default().apply(33333333, &current, target);
+    /// assert_eq!(costed.stock_value(StockSymbol::new("VV")), dec!(1000.0));
+    /// assert_eq!(costed.cash_value(), dec!(30.0));
+    /// ```
+    pub fn apply(
+        &self,
+        account_number: u32,
+        current: &ShareValues,
+        mut target: ShareValues,
+    ) -> ShareValues {
+        let symbols = traded_symbols(current, &target);
+        let goals = symbols
+            .iter()
+            .map(|symbol| target.stock_value(symbol.clone()) - current.stock_value(symbol.clone()))
+            .collect::<Vec<Decimal>>();
+        let mut trades = goals.clone();
+        let mut kept = vec![false; trades.len()];
+        loop {
+            // Trades smaller than the minimum are dropped before the trades which cost too much
+            let dropped = trades
+                .iter()
+                .enumerate()
+                .filter(|(index, trade)| !trade.is_zero() && !kept[*index])
+                .filter_map(|(index, trade)| {
+                    let symbol = &symbols[index];
+                    let above_minimum =
+                        trade.abs() >= self.trade_cost(account_number, symbol).minimum_trade;
+                    let drift_removed = goals[index].abs() - (goals[index] - trade).abs();
+                    let worth = drift_removed * self.drift_cost / Decimal::ONE_HUNDRED
+                        - self.cost(account_number, symbol, *trade);
+                    if above_minimum && !worth.is_sign_negative() {
+                        None
+                    } else {
+                        Some((index, (above_minimum, worth)))
+                    }
+                })
+                .min_by_key(|(_, key)| *key)
+                .map(|(index, _)| index);
+            let index = match dropped {
+                Some(index) => index,
+                None => break,
+            };
+            let amount = trades[index];
+            if amount.is_sign_negative() {
+                let coverable = trades
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != index)
+                    .map(|(other, trade)| {
+                        if trade.is_sign_positive() {
+                            *trade
+                        } else {
+                            (current.stock_value(symbols[other].clone()) + trade).max(Decimal::ZERO)
+                        }
+                    })
+                    .sum::<Decimal>();
+                if coverable < -amount {
+                    kept[index] = true;
+                    continue;
+                }
+            }
+            trades[index] = Decimal::ZERO;
+            if amount.is_sign_positive() {
+                // The dropped purchase is added to the largest purchase up to the rest of its
+                // goal, where any remainder stays as cash
+                let largest = (0..trades.len())
+                    .filter(|other| {
+                        trades[*other] > Decimal::ZERO && goals[*other] > trades[*other]
+                    })
+                    .max_by_key(|other| trades[*other]);
+                if let Some(largest) = largest {
+                    let room = goals[largest] - trades[largest];
+                    trades[largest] += amount.min(room);
+                }
+                continue;
+            }
+            // The cash a dropped sale does not raise is first taken from the purchases, then
+            // raised by the other sales up to what is held
+            let mut shortfall = -amount;
+            let mut order = (0..trades.len()).collect::<Vec<usize>>();
+            order.sort_by_key(|index| -trades[*index].abs());
+            for index in &order {
+                if trades[*index] > Decimal::ZERO && shortfall > Decimal::ZERO {
+                    let taken = trades[*index].min(shortfall);
+                    trades[*index] -= taken;
+                    shortfall -= taken;
+                }
+            }
+            for index in &order {
+                if trades[*index] < Decimal::ZERO && shortfall > Decimal::ZERO {
+                    let room = current.stock_value(symbols[*index].clone()) + trades[*index];
+                    let raised = room.max(Decimal::ZERO).min(shortfall);
+                    trades[*index] -= raised;
+                    shortfall -= raised;
+                }
+            }
+        }
+
+        let mut spent = Decimal::ZERO;
+        for (symbol, trade) in symbols.into_iter().zip(trades) {
+            spent += trade;
+            target.add_stock_value(symbol.clone(), current.stock_value(symbol) + trade);
+        }
+        target.add_cash(current.cash_value() - spent - target.cash_value());
+        target
+    }
+}
+
+impl Default for TradeCosts {
+    fn default() -> Self {
+        Self::from_toml(DEFAULT_TRADE_COSTS).expect("Built-in trade costs are invalid")
+    }
+}
@@ -0,0 +1,303 @@
+use crate::asset::Allocations;
+use anyhow::{anyhow, ensure, Context, Result};
+use chrono::prelude::*;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+use std::{collections::HashSet, fs};
+
+/// DEFAULT_GLIDE_PATH is the name of the built-in glide path used when none is selected
+pub const DEFAULT_GLIDE_PATH: &str = "current";
+
+/// GlidePoint is the stock, bond and inflation protected percentages at a number of years before
+/// retirement.  Years after retirement are negative.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GlidePoint {
+    pub years_to_retirement: i32,
+    pub stock: Decimal,
+    pub bond: Decimal,
+    #[serde(default)]
+    pub inflation_protected: Decimal,
+}
+
+impl GlidePoint {
+    /// new creates a point of a glide path
+    pub fn new(
+        years_to_retirement: i32,
+        stock: Decimal,
+        bond: Decimal,
+        inflation: Decimal,
+    ) -> Self {
+        GlidePoint {
+            years_to_retirement,
+            stock,
+            bond,
+            inflation_protected: inflation,
+        }
+    }
+}
+
+/// GlidePath is a named table of years to retirement to allocation percentages.  Allocations are
+/// interpolated between the points and held at the first or last point outside of the table.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GlidePath {
+    pub name: String,
+    #[serde(rename = "point")]
+    points: Vec<GlidePoint>,
+}
+
+impl GlidePath {
+    /// new creates a glide path from its points, which are sorted by the years to retirement
+    ///
+    /// # Errors
+    ///
+    /// Errors when there are no points, more than one point for the same year, or a point whose
+    /// percentages do not add up to 100
+    pub fn new(name: &str, mut points: Vec<GlidePoint>) -> Result<Self> {
+        ensure!(!points.is_empty(), "Glide path {} has no points", name);
+        let mut years = HashSet::new();
+        for point in &points {
+            ensure!(
+                years.insert(point.years_to_retirement),
+                "Glide path {} has more than one point at {} years to retirement",
+                name,
+                point.years_to_retirement
+            );
+            Allocations::custom(point.stock, point.bond, point.inflation_protected).with_context(
+                || {
+                    format!(
+                        "Glide path {} at {} years to retirement",
+                        name, point.years_to_retirement
+                    )
+                },
+            )?;
+        }
+        points.sort_by_key(|point| point.years_to_retirement);
+        Ok(GlidePath {
+            name: name.to_string(),
+            points,
+        })
+    }
+
+    /// current is the glide path which holds 90% stock until 25 years before retirement, then
+    /// moves 1.5% a year into bonds until 5 years before retirement, then moves into bonds and
+    /// inflation protected bonds until 5 years after retirement, where it settles at 29% stock,
+    /// 53% bond and 18% inflation protected
+    pub fn current() -> Self {
+        GlidePath {
+            name: "current".to_string(),
+            points: vec![
+                GlidePoint::new(-6, dec!(29), dec!(53), dec!(18)),
+                GlidePoint::new(-5, dec!(32), dec!(50), dec!(18)),
+                GlidePoint::new(5, dec!(60), dec!(40), dec!(0)),
+                GlidePoint::new(25, dec!(90), dec!(10), dec!(0)),
+            ],
+        }
+    }
+
+    /// conservative is a glide path which starts at 75% stock and moves into bonds earlier,
+    /// settling at 25% stock, 55% bond and 20% inflation protected 10 years after retirement
+    pub fn conservative() -> Self {
+        GlidePath {
+            name: "conservative".to_string(),
+            points: vec![
+                GlidePoint::new(-10, dec!(25), dec!(55), dec!(20)),
+                GlidePoint::new(-5, dec!(35), dec!(50), dec!(15)),
+                GlidePoint::new(5, dec!(50), dec!(45), dec!(5)),
+                GlidePoint::new(25, dec!(75), dec!(25), dec!(0)),
+            ],
+        }
+    }
+
+    /// rising_equity is a glide path which is lowest in stock at retirement, when the risk of a
+    /// market drop is largest, and then rises back to 60% stock over the 15 years after retirement
+    pub fn rising_equity() -> Self {
+        GlidePath {
+            name: "rising_equity".to_string(),
+            points: vec![
+                GlidePoint::new(-15, dec!(60), dec!(35), dec!(5)),
+                GlidePoint::new(0, dec!(40), dec!(50), dec!(10)),
+                GlidePoint::new(5, dec!(60), dec!(40), dec!(0)),
+                GlidePoint::new(25, dec!(90), dec!(10), dec!(0)),
+            ],
+        }
+    }
+
+    /// allocations_at returns the allocation at the years to retirement, interpolated between the
+    /// points of the glide path
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::glide::GlidePath;
+    ///
+    /// let glide_path = GlidePath::current();
+    /// assert_eq!(glide_path.allocations_at(40).total_stock(), dec!(90));
+    /// assert_eq!(glide_path.allocations_at(15).total_stock(), dec!(75));
+    /// assert_eq!(glide_path.allocations_at(0).total_inflation_protected(), dec!(9));
+    /// assert_eq!(glide_path.allocations_at(-20).total_bond(), dec!(53));
+    /// ```
+    pub fn allocations_at(&self, years_to_retirement: i32) -> Allocations {
+        let first = &self.points[0];
+        let last = &self.points[self.points.len() - 1];
+        let (lower, upper) = if years_to_retirement <= first.years_to_retirement {
+            (first, first)
+        } else if years_to_retirement >= last.years_to_retirement {
+            (last, last)
+        } else {
+            let upper_index = self
+                .points
+                .iter()
+                .position(|point| point.years_to_retirement >= years_to_retirement)
+                .expect("Years to retirement within the glide path");
+            (&self.points[upper_index - 1], &self.points[upper_index])
+        };
+        let (total_stock, total_inflation_protected) = if lower == upper {
+            (lower.stock, lower.inflation_protected)
+        } else {
+            let fraction = Decimal::from(years_to_retirement - lower.years_to_retirement)
+                / Decimal::from(upper.years_to_retirement - lower.years_to_retirement);
+            (
+                lower.stock + (upper.stock - lower.stock) * fraction,
+                lower.inflation_protected
+                    + (upper.inflation_protected - lower.inflation_protected) * fraction,
+            )
+        };
+        Allocations::custom(
+            total_stock,
+            Decimal::ONE_HUNDRED - total_stock - total_inflation_protected,
+            total_inflation_protected,
+        )
+        .expect("Interpolated percentages add up to 100")
+    }
+
+    /// allocations returns the allocation for the retirement year
+    ///
+    /// # Errors
+    ///
+    /// Errors when the year is not between 2000 and 3000
+    pub fn allocations(&self, retirement_year: i32) -> Result<Allocations> {
+        ensure!(
+            (2000..3000).contains(&retirement_year),
+            format!(
+                "Year needs to be between 2000 and 3000.  Year input: {}",
+                retirement_year
+            )
+        );
+        let this_year = chrono::Local::now().year();
+        Ok(self.allocations_at(retirement_year - this_year))
+    }
+}
+
+/// GlidePaths holds the built-in glide paths along with any read from a glide paths file
+#[derive(Clone, Debug)]
+pub struct GlidePaths {
+    glide_paths: Vec<GlidePath>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GlidePathsFile {
+    #[serde(default, rename = "glide_path")]
+    glide_paths: Vec<GlidePath>,
+}
+
+impl GlidePaths {
+    /// new creates a GlidePaths struct with the built-in glide paths
+    pub fn new() -> Self {
+        GlidePaths {
+            glide_paths: vec![
+                GlidePath::current(),
+                GlidePath::conservative(),
+                GlidePath::rising_equity(),
+            ],
+        }
+    }
+
+    /// from_toml adds the glide paths within the contents of a TOML file to the built-in glide
+    /// paths
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::glide::GlidePaths;
+    ///
+    /// let glide_paths = GlidePaths::from_toml(
+    ///     r#"
+    ///     [[glide_path]]
+    ///     name = "steady"
+    ///
+    ///     [[glide_path.point]]
+    ///     years_to_retirement = 10
+    ///     stock = 70
+    ///     bond = 30
+    ///
+    ///     [[glide_path.point]]
+    ///     years_to_retirement = 0
+    ///     stock = 50
+    ///     bond = 40
+    ///     inflation_protected = 10
+    ///     "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let steady = glide_paths.glide_path("steady").unwrap();
+    /// assert_eq!(steady.allocations_at(5).total_stock(), dec!(60));
+    /// assert!(glide_paths.glide_path("conservative").is_ok());
+    /// assert!(glide_paths.glide_path("aggressive").is_err());
+    /// ```
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let file: GlidePathsFile =
+            toml::from_str(contents).context("Unable to parse glide paths")?;
+        let mut glide_paths = GlidePaths::new();
+        for glide_path in file.glide_paths {
+            glide_paths.add_glide_path(GlidePath::new(&glide_path.name, glide_path.points)?)?;
+        }
+        Ok(glide_paths)
+    }
+
+    /// from_file reads a glide paths TOML file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Glide paths file not found: {}", path))?;
+        Self::from_toml(&contents).with_context(|| format!("Invalid glide paths: {}", path))
+    }
+
+    /// add_glide_path adds a glide path
+    ///
+    /// # Errors
+    ///
+    /// Errors when a glide path with the same name already exists
+    pub fn add_glide_path(&mut self, glide_path: GlidePath) -> Result<()> {
+        ensure!(
+            self.glide_path(&glide_path.name).is_err(),
+            "Glide path {} is defined more than once",
+            glide_path.name
+        );
+        self.glide_paths.push(glide_path);
+        Ok(())
+    }
+
+    /// glide_path returns the glide path with the name
+    pub fn glide_path(&self, name: &str) -> Result<&GlidePath> {
+        self.glide_paths
+            .iter()
+            .find(|glide_path| glide_path.name == name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Glide path {} is not a built-in glide path or within the glide paths file",
+                    name
+                )
+            })
+    }
+}
+
+impl Default for GlidePaths {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{
+    account::Account,
+    holdings::{StockSymbol, Transaction, VanguardHoldings},
+    money::round_dollars,
+    universe::fund_universe,
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use chrono::{Duration, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{collections::HashSet, fmt, fs};
+
+// DEFAULT_HARVEST_PAIRS holds the built-in substitute of each fund
+const DEFAULT_HARVEST_PAIRS: &str = include_str!("../harvest_pairs.toml");
+
+/// WASH_SALE_DAYS is the number of days before and after a sale at a loss in which buying a
+/// substantially identical fund disallows the loss
+pub const WASH_SALE_DAYS: i64 = 30;
+
+/// HarvestPair is a fund within the fund universe and the substitute it is sold into when
+/// harvesting a loss
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HarvestPair {
+    pub fund: StockSymbol,
+    pub substitute: StockSymbol,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HarvestPairsFile {
+    #[serde(rename = "pair")]
+    pairs: Vec<HarvestPair>,
+}
+
+/// HarvestPairs holds the substitute of each fund used when harvesting losses
+#[derive(Clone, Debug, PartialEq)]
+pub struct HarvestPairs {
+    pairs: Vec<HarvestPair>,
+}
+
+impl HarvestPairs {
+    /// from_toml reads the substitute pairs within the contents of a TOML file, which replace the
+    /// built-in pairs
+    ///
+    /// # Errors
+    ///
+    /// Errors when a fund is not an ETF within the fund universe, has more than one substitute, or
+    /// has a substitute which is substantially identical to it or is not a member of its sub class
+    /// within the fund universe
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{harvest::HarvestPairs, holdings::StockSymbol};
+    ///
+    /// let pairs = HarvestPairs::from_toml(
+    ///     r#"
+    ///     [[pair]]
+    ///     fund = "VXUS"
+    ///     substitute = "IXUS"
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// // Mutual fund share classes use the substitute of their ETF equivalent
+    /// assert_eq!(pairs.substitute(&StockSymbol::new("VTIAX")).unwrap().to_string(), "IXUS");
+    /// assert!(pairs.substitute(&StockSymbol::new("VV")).is_none());
+    ///
+    /// assert!(HarvestPairs::from_toml("[[pair]]\nfund = \"VV\"\nsubstitute = \"VLCAX\"").is_err());
+    /// assert!(HarvestPairs::from_toml("[[pair]]\nfund = \"VV\"\nsubstitute = \"VO\"").is_err());
+    /// // Substitutes need to be within the fund universe to keep the target of their fund
+    /// assert!(HarvestPairs::from_toml("[[pair]]\nfund = \"VV\"\nsubstitute = \"SPLG\"").is_err());
+    /// ```
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let file: HarvestPairsFile =
+            toml::from_str(contents).context("Unable to parse harvest pairs")?;
+        let harvest_pairs = HarvestPairs { pairs: file.pairs };
+        harvest_pairs.validate()?;
+        Ok(harvest_pairs)
+    }
+
+    /// from_file reads a harvest pairs TOML file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Harvest pairs file not found: {}", path))?;
+        Self::from_toml(&contents).with_context(|| format!("Invalid harvest pairs: {}", path))
+    }
+
+    /// substitute returns the substitute of the fund, where a mutual fund share class uses the
+    /// substitute of its ETF equivalent
+    pub fn substitute(&self, symbol: &StockSymbol) -> Option<&StockSymbol> {
+        let identical_symbol = identical_symbol(symbol);
+        self.pairs
+            .iter()
+            .find(|pair| pair.fund == identical_symbol)
+            .map(|pair| &pair.substitute)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let universe = fund_universe();
+        let mut funds = HashSet::new();
+        for pair in &self.pairs {
+            let fund = universe.fund(&pair.fund).ok_or_else(|| {
+                anyhow!("Harvest fund {} is not within the fund universe", pair.fund)
+            })?;
+            ensure!(
+                fund.etf_equivalent.is_none(),
+                "Harvest fund {} is a mutual fund share class, list its ETF equivalent instead",
+                pair.fund
+            );
+            ensure!(
+                funds.insert(pair.fund.clone()),
+                "Harvest fund {} has more than one substitute",
+                pair.fund
+            );
+            ensure!(
+                identical_symbol(&pair.substitute) != pair.fund,
+                "Substitute {} is substantially identical to {}",
+                pair.substitute,
+                pair.fund
+            );
+            let substitute = universe.model_fund(&pair.substitute).ok_or_else(|| {
+                anyhow!(
+                    "Substitute {} is not within the fund universe, add it with substitute_for = \
+                    \"{}\"",
+                    pair.substitute,
+                    pair.fund
+                )
+            })?;
+            ensure!(
+                universe.model_fund(&pair.fund).map(|fund| &fund.symbol) == Some(&substitute.symbol),
+                "Substitute {} is not a member of the same sub class as {} within the fund universe",
+                pair.substitute,
+                pair.fund
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for HarvestPairs {
+    fn default() -> Self {
+        Self::from_toml(DEFAULT_HARVEST_PAIRS).expect("Built-in harvest pairs are invalid")
+    }
+}
+
+// identical_symbol returns the fund which the symbol is substantially identical to, which is the
+// ETF equivalent of a mutual fund share class.  Substitutes track a different index and are not
+// identical to the fund they substitute for.
+fn identical_symbol(symbol: &StockSymbol) -> StockSymbol {
+    fund_universe()
+        .identical_fund(symbol)
+        .map(|fund| fund.symbol.clone())
+        .unwrap_or_else(|| symbol.clone())
+}
+
+/// HarvestCandidate is a fund within a taxable account whose lots at a loss can be sold into its
+/// substitute, along with the purchases within the wash sale window which would disallow the loss
+#[derive(Clone, Debug)]
+pub struct HarvestCandidate {
+    pub account: Account,
+    pub symbol: StockSymbol,
+    pub shares: Decimal,
+    pub loss: Decimal,
+    pub proceeds: Decimal,
+    pub substitute: Option<StockSymbol>,
+    pub wash_sales: Vec<Transaction>,
+}
+
+/// Harvest holds the funds within the taxable accounts with losses of at least the threshold
+#[derive(Clone, Debug)]
+pub struct Harvest {
+    pub as_of: NaiveDate,
+    pub threshold: Decimal,
+    pub candidates: Vec<HarvestCandidate>,
+    accounts: Vec<Account>,
+}
+
+/// scan finds the funds within the taxable accounts whose lots at a loss add up to a loss of at
+/// least the threshold.  Each is proposed to be sold into its substitute.  The transactions of
+/// every account, including the IRAs, are checked for purchases of a substantially identical fund
+/// within the wash sale window around the sale, which would disallow the loss.  The purchases which
+/// created the lots being sold are not counted.
+pub fn scan(
+    vanguard_holdings: &VanguardHoldings,
+    harvest_pairs: &HarvestPairs,
+    threshold: Decimal,
+    as_of: NaiveDate,
+) -> Harvest {
+    let quotes = vanguard_holdings.stock_quotes();
+    let transactions = vanguard_holdings.transactions();
+    let window_start = as_of - Duration::days(WASH_SALE_DAYS);
+    let window_end = as_of + Duration::days(WASH_SALE_DAYS);
+    let mut candidates = Vec::new();
+    for (account, holdings) in vanguard_holdings.accounts() {
+        let lots = match vanguard_holdings.lots(account.number) {
+            Some(lots) => lots,
+            None => continue,
+        };
+        for symbol in lots.symbols() {
+            let price = quotes.stock_value(symbol.clone());
+            if holdings.stock_value(symbol.clone()).is_zero() || price <= Decimal::ZERO {
+                continue;
+            }
+            let loss_lots = lots
+                .lots(&symbol)
+                .iter()
+                .filter(|lot| lot.unrealized_gain(price) < Decimal::ZERO)
+                .collect::<Vec<_>>();
+            let loss = round_dollars(
+                loss_lots
+                    .iter()
+                    .map(|lot| lot.unrealized_gain(price))
+                    .sum::<Decimal>(),
+            );
+            if loss_lots.is_empty() || -loss < threshold {
+                continue;
+            }
+            let shares = loss_lots.iter().map(|lot| lot.shares).sum::<Decimal>();
+            // The purchases which created the lots being sold are not replacement purchases, so
+            // each lot removes the first purchase within the account on its date of its shares
+            let mut unmatched_lots = loss_lots.clone();
+            let wash_sales = transactions
+                .iter()
+                .filter(|transaction| {
+                    transaction.is_purchase()
+                        && identical_symbol(transaction.symbol()) == identical_symbol(&symbol)
+                        && (window_start..=window_end).contains(&transaction.trade_date())
+                })
+                .filter(|transaction| {
+                    let sold_lot = unmatched_lots.iter().position(|lot| {
+                        transaction.account_number() == account.number
+                            && transaction.symbol() == &lot.symbol
+                            && transaction.trade_date() == lot.acquired
+                            && transaction.shares() == lot.shares
+                    });
+                    match sold_lot {
+                        Some(index) => {
+                            unmatched_lots.remove(index);
+                            false
+                        }
+                        None => true,
+                    }
+                })
+                .cloned()
+                .collect::<Vec<Transaction>>();
+            candidates.push(HarvestCandidate {
+                account: account.clone(),
+                symbol: symbol.clone(),
+                shares,
+                loss,
+                proceeds: round_dollars(shares * price),
+                substitute: harvest_pairs.substitute(&symbol).cloned(),
+                wash_sales,
+            });
+        }
+    }
+    Harvest {
+        as_of,
+        threshold,
+        candidates,
+        accounts: vanguard_holdings
+            .accounts()
+            .iter()
+            .map(|(account, _)| account.clone())
+            .collect(),
+    }
+}
+
+impl fmt::Display for Harvest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let title = format!(
+            "Tax loss harvest on {} for losses of at least ${:.2}:",
+            self.as_of, self.threshold
+        );
+        if self.candidates.is_empty() {
+            return write!(
+                f,
+                "{}\nNo taxable fund has lots at a loss of at least the threshold",
+                title
+            );
+        }
+        let mut rows = String::new();
+        for candidate in &self.candidates {
+            let substitute = match &candidate.substitute {
+                Some(substitute) => substitute.to_string(),
+                None => "None listed".to_string(),
+            };
+            rows.push_str(&format!(
+                "{:<25}{:<9}{:<12.4}${:<12.2}${:<12.2}{}\n",
+                candidate.account.to_string(),
+                candidate.symbol.to_string(),
+                candidate.shares,
+                candidate.loss,
+                candidate.proceeds,
+                substitute
+            ));
+            for wash_sale in &candidate.wash_sales {
+                let account = self
+                    .accounts
+                    .iter()
+                    .find(|account| account.number == wash_sale.account_number())
+                    .map(|account| account.to_string())
+                    .unwrap_or_else(|| format!("account {}", wash_sale.account_number()));
+                rows.push_str(&format!(
+                    "  Wash sale: {} was bought within {} on {}, which disallows the loss\n",
+                    wash_sale.symbol(),
+                    account,
+                    wash_sale.trade_date()
+                ));
+            }
+        }
+        write!(
+            f,
+            "{}\n\
+            Account                  Symbol   Shares      Loss         Proceeds     Substitute\n\
+            ------------------------------------------------------------------------------------\n\
+            {}\
+            ------------------------------------------------------------------------------------\n\
+            Buying a harvested fund or its share classes within any account, including reinvested \
+            dividends, through {} also disallows the loss",
+            title,
+            rows,
+            self.as_of + Duration::days(WASH_SALE_DAYS)
+        )
+    }
+}
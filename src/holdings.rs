@@ -1,60 +1,39 @@
-use crate::asset::SubAllocations;
+use crate::{
+    account::{Account, AccountKind, Accounts},
+    asset::SubAllocations,
+    costs::TradeCosts,
+    location::LocationComparison,
+    lots::{LotSales, TaxLots},
+    money::{dollars_from_f64, round_dollars, round_shares},
+    plan::ExecutionPlan,
+    policy::TaxPolicy,
+    universe::{fund_universe, AssetClass},
+};
 use anyhow::{anyhow, Context, Result};
-use chrono::{NaiveDate, Duration};
+use chrono::{Duration, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use std::{
     collections::HashMap,
     fmt,
     fs::File,
     io::{BufRead, BufReader},
     ops::{Add, Div, Mul, Sub},
-    vec::Vec, 
+    vec::Vec,
 };
 use time::{macros::format_description, OffsetDateTime};
 use yahoo_finance_api as yahoo;
 
-// STOCK_DESCRIPTION holds the descriptions for the stock symbols which is used to print and
-// display
-lazy_static! {
-    static ref STOCK_DESCRIPTION: HashMap<StockSymbol, &'static str> = {
-        let mut m = HashMap::new();
-        m.insert(StockSymbol::VV, "US large cap");
-        m.insert(StockSymbol::VO, "US mid cap");
-        m.insert(StockSymbol::VB, "US small cap");
-        m.insert(StockSymbol::VTC, "US total corporate bond");
-        m.insert(StockSymbol::BND, "US total bond");
-        m.insert(StockSymbol::VXUS, "Total international stock");
-        m.insert(StockSymbol::VWO, "Emerging markets stock");
-        m.insert(StockSymbol::BNDX, "Total international bond");
-        m.insert(StockSymbol::VTIP, "Inflation protected securities");
-        m.insert(StockSymbol::VTI, "Total domestic stock");
-        m.insert(StockSymbol::VTIVX, "2045 Retirement fund");
-        m
-    };
-}
-
-/// StockSymbol is an enum which holds all stock symbols which are supported.  Empty is used to
-/// initiated structs which use this enum.  Other<String> is a holder of any stock that is not
-/// supported, where the String is the stock symbol.
-#[derive(Clone, Eq, Hash, PartialEq, Debug)]
-pub enum StockSymbol {
-    VXUS,
-    BNDX,
-    VTIP,
-    BND,
-    VWO,
-    VO,
-    VB,
-    VTC,
-    VV,
-    VMFXX,
-    VTI,
-    VTIVX,
-    Empty,
-    Other(String),
-}
+/// StockSymbol holds the ticker of a fund.  Any ticker can be held, where the fund universe
+/// determines which are part of the rebalance model.  An empty symbol is used to initiate structs
+/// which use a StockSymbol before the symbol is known.
+#[derive(Clone, Eq, Hash, PartialEq, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct StockSymbol(String);
 
 impl StockSymbol {
-    /// new creates a new StockSymbol enum based on the string value.
+    /// new creates a new StockSymbol based on the string value.  A warning is printed when the
+    /// symbol is not within the fund universe.
     ///
     ///  # Example
     ///
@@ -62,32 +41,26 @@ impl StockSymbol {
     ///  use vapore::holdings::StockSymbol;
     ///
     ///  let bnd = StockSymbol::new("BND");
-    ///  assert_eq!(bnd, StockSymbol::BND);
+    ///  assert_eq!(bnd.to_string(), "BND");
     ///  ```
     pub fn new(symbol: &str) -> Self {
-        match symbol {
-            "VXUS" => StockSymbol::VXUS,
-            "BNDX" => StockSymbol::BNDX,
-            "VTIP" => StockSymbol::VTIP,
-            "BND" => StockSymbol::BND,
-            "VWO" => StockSymbol::VWO,
-            "VO" => StockSymbol::VO,
-            "VB" => StockSymbol::VB,
-            "VTC" => StockSymbol::VTC,
-            "VV" => StockSymbol::VV,
-            "VMFXX" => StockSymbol::VMFXX,
-            "VTI" => StockSymbol::VTI,
-            "VTIVX" => StockSymbol::VTIVX,
-            "" => StockSymbol::Empty,
-            _ => {
-                eprintln!("{} is not supported within this algorithm\n", symbol);
-                StockSymbol::Other(symbol.to_string())
-            }
+        let stock_symbol = StockSymbol(symbol.to_string());
+        if !stock_symbol.is_empty() && !fund_universe().contains(&stock_symbol) {
+            eprintln!(
+                "{} is not within the fund universe and is targeted to be sold\n",
+                symbol
+            );
         }
+        stock_symbol
+    }
+
+    /// is_empty returns whether or not the symbol has been set
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 
     /// description returns a string of the StockSymbol description.  If the stock is not
-    /// supported, a "No description" String is returned.
+    /// within the fund universe, a "No description" String is returned.
     ///
     /// # Example
     ///
@@ -100,18 +73,23 @@ impl StockSymbol {
     ///
     /// ```
     pub fn description(&self) -> String {
-        let description_option = STOCK_DESCRIPTION.get(self);
-        if let Some(description) = description_option {
-            format!("{:?}: {}", self, description)
+        if let Some(fund) = fund_universe().fund(self) {
+            format!("{}: {}", self, fund.description)
         } else {
-            format!("No description for {:?}", self)
+            format!("No description for {}", self)
         }
     }
 }
 
-/// all_stock_descriptions returns a String containing the description of all stocks which are
-/// supported with each separated by a new line.  This is used to display on screen or write to
-/// file all of the descriptions.
+impl fmt::Display for StockSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// all_stock_descriptions returns a String containing the description of all stocks within the
+/// fund universe with each separated by a new line.  This is used to display on screen or write
+/// to file all of the descriptions.
 ///
 /// # Example
 ///
@@ -124,20 +102,8 @@ impl StockSymbol {
 /// ```
 pub fn all_stock_descriptions() -> String {
     let mut descriptions = String::new();
-    for symbol in [
-        StockSymbol::VV,
-        StockSymbol::VO,
-        StockSymbol::VB,
-        StockSymbol::VTC,
-        StockSymbol::BND,
-        StockSymbol::VXUS,
-        StockSymbol::VWO,
-        StockSymbol::BNDX,
-        StockSymbol::VTIP,
-        StockSymbol::VTI,
-        StockSymbol::VTIVX,
-    ] {
-        descriptions.push_str(&symbol.description());
+    for fund in fund_universe().funds() {
+        descriptions.push_str(&fund.symbol.description());
         descriptions.push('\n')
     }
     descriptions.pop();
@@ -148,9 +114,9 @@ pub fn all_stock_descriptions() -> String {
 pub struct StockInfo {
     pub account_number: u32,
     pub symbol: StockSymbol,
-    pub share_price: f32,
-    pub shares: f32,
-    pub total_value: f32,
+    pub share_price: Decimal,
+    pub shares: Decimal,
+    pub total_value: Decimal,
     account_added: bool,
     symbol_added: bool,
     share_price_added: bool,
@@ -172,10 +138,10 @@ impl StockInfo {
     pub fn new() -> Self {
         StockInfo {
             account_number: 0,
-            symbol: StockSymbol::Empty,
-            share_price: 0.0,
-            shares: 0.0,
-            total_value: 0.0,
+            symbol: StockSymbol::default(),
+            share_price: Decimal::ZERO,
+            shares: Decimal::ZERO,
+            total_value: Decimal::ZERO,
             account_added: false,
             symbol_added: false,
             share_price_added: false,
@@ -210,9 +176,9 @@ impl StockInfo {
     ///
     /// let mut new_stock = holdings::StockInfo::new();
     /// new_stock.add_account(123456789);
-    /// new_stock.add_symbol(holdings::StockSymbol::BND);
+    /// new_stock.add_symbol(holdings::StockSymbol::new("BND"));
     ///
-    /// assert_eq!(new_stock.symbol, holdings::StockSymbol::BND);
+    /// assert_eq!(new_stock.symbol, holdings::StockSymbol::new("BND"));
     /// ```
     pub fn add_symbol(&mut self, symbol: StockSymbol) {
         self.symbol = symbol;
@@ -224,16 +190,17 @@ impl StockInfo {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let mut new_stock = holdings::StockInfo::new();
     /// new_stock.add_account(123456789);
-    /// new_stock.add_symbol(holdings::StockSymbol::BND);
-    /// new_stock.add_share_price(234.50);
+    /// new_stock.add_symbol(holdings::StockSymbol::new("BND"));
+    /// new_stock.add_share_price(dec!(234.50));
     ///
-    /// assert_eq!(new_stock.share_price, 234.50);
+    /// assert_eq!(new_stock.share_price, dec!(234.50));
     /// ```
-    pub fn add_share_price(&mut self, share_price: f32) {
+    pub fn add_share_price(&mut self, share_price: Decimal) {
         self.share_price = share_price;
         self.share_price_added = true;
     }
@@ -243,17 +210,18 @@ impl StockInfo {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let mut new_stock = holdings::StockInfo::new();
     /// new_stock.add_account(123456789);
-    /// new_stock.add_symbol(holdings::StockSymbol::BND);
-    /// new_stock.add_share_price(234.50);
-    /// new_stock.add_shares(10.0)
+    /// new_stock.add_symbol(holdings::StockSymbol::new("BND"));
+    /// new_stock.add_share_price(dec!(234.50));
+    /// new_stock.add_shares(dec!(10.0));
     ///
-    /// assert_eq!(new_stock.shares, 10.0);
+    /// assert_eq!(new_stock.shares, dec!(10.0));
     /// ```
-    pub fn add_shares(&mut self, share_num: f32) {
+    pub fn add_shares(&mut self, share_num: Decimal) {
         self.shares = share_num;
         self.shares_added = true;
     }
@@ -263,17 +231,18 @@ impl StockInfo {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let mut new_stock = holdings::StockInfo::new();
     /// new_stock.add_account(123456789);
-    /// new_stock.add_symbol(holdings::StockSymbol::BND);
-    /// new_stock.add_share_price(234.50);
-    /// new_stock.add_total_value(5000.00);
+    /// new_stock.add_symbol(holdings::StockSymbol::new("BND"));
+    /// new_stock.add_share_price(dec!(234.50));
+    /// new_stock.add_total_value(dec!(5000.00));
     ///
-    /// assert_eq!(new_stock.total_value, 5000.00);
+    /// assert_eq!(new_stock.total_value, dec!(5000.00));
     /// ```
-    pub fn add_total_value(&mut self, total_value: f32) {
+    pub fn add_total_value(&mut self, total_value: Decimal) {
         self.total_value = total_value;
         self.total_value_added = true;
     }
@@ -283,14 +252,15 @@ impl StockInfo {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let mut new_stock = holdings::StockInfo::new();
     /// new_stock.add_account(123456789);
-    /// new_stock.add_symbol(holdings::StockSymbol::BND);
-    /// new_stock.add_share_price(234.50);
-    /// new_stock.add_total_value(5000.00);
-    /// new_stock.add_shares(10.0);
+    /// new_stock.add_symbol(holdings::StockSymbol::new("BND"));
+    /// new_stock.add_share_price(dec!(234.50));
+    /// new_stock.add_total_value(dec!(5000.00));
+    /// new_stock.add_shares(dec!(10.0));
     ///
     /// assert!(new_stock.finished());
     ///
@@ -316,79 +286,68 @@ impl Default for StockInfo {
     }
 }
 
-pub async fn get_yahoo_quote(stock_symbol: StockSymbol) -> Result<f32> {
-    let stock_str = match stock_symbol {
-        StockSymbol::VO => "VO",
-        StockSymbol::VB => "VB",
-        StockSymbol::VV => "VV",
-        StockSymbol::BND => "BND",
-        StockSymbol::VWO => "VWO",
-        StockSymbol::VTC => "VTC",
-        StockSymbol::VXUS => "VXUS",
-        StockSymbol::BNDX => "BNDX",
-        StockSymbol::VTIP => "VTIP",
-        StockSymbol::VTI => "VTI",
-        StockSymbol::VTIVX => "VTIVX",
-        _ => "none",
-    };
-    if stock_str == "none" {
-        eprintln!("Stock symbol not supported for yahoo retrieval");
-        Ok(0.0)
-    } else {
+/// quote_symbol returns the symbol used to retrieve a quote from yahoo.  None is returned for
+/// cash and empty symbols, which do not have quotes.
+fn quote_symbol(stock_symbol: &StockSymbol) -> Option<String> {
+    match fund_universe().fund(stock_symbol) {
+        Some(fund) if fund.asset_class == AssetClass::Cash => None,
+        Some(fund) => Some(fund.quote_symbol()),
+        None if stock_symbol.is_empty() => None,
+        None => Some(stock_symbol.to_string()),
+    }
+}
+
+pub async fn get_yahoo_quote(stock_symbol: StockSymbol) -> Result<Decimal> {
+    // Money market funds hold a stable $1 NAV
+    if fund_universe().asset_class(&stock_symbol) == Some(AssetClass::Cash) {
+        return Ok(Decimal::ONE);
+    }
+    if let Some(stock_str) = quote_symbol(&stock_symbol) {
         let provider = yahoo::YahooConnector::new()?;
         let response_err = provider
-            .get_latest_quotes(stock_str, "1m")
+            .get_latest_quotes(&stock_str, "1m")
             .await
             .with_context(|| format!("Latest quote error for: {}", stock_str));
         // If the market is closed, an error occurs.  If so, get quote history then the last quote
         if let Ok(response) = response_err {
-            Ok(response.last_quote()?.close as f32)
+            dollars_from_f64(response.last_quote()?.close)
         } else {
             let today = OffsetDateTime::now_utc();
             let week_ago = today - time::Duration::days(7);
             let response = provider
-                .get_quote_history(stock_str, week_ago, today)
+                .get_quote_history(&stock_str, week_ago, today)
                 .await
                 .with_context(|| {
                     format!("Both attempts at quote retrieval failed for: {}", stock_str)
                 })?;
-            Ok(response.last_quote()?.close as f32)
+            dollars_from_f64(response.last_quote()?.close)
         }
+    } else {
+        eprintln!("Stock symbol not supported for yahoo retrieval");
+        Ok(Decimal::ZERO)
     }
 }
 
-pub async fn get_yahoo_eoy_quote(stock_symbol: StockSymbol, year: u32) -> Result<f32> {
-    let stock_str = match stock_symbol {
-        StockSymbol::VO => "VO",
-        StockSymbol::VB => "VB",
-        StockSymbol::VV => "VV",
-        StockSymbol::BND => "BND",
-        StockSymbol::VWO => "VWO",
-        StockSymbol::VTC => "VTC",
-        StockSymbol::VXUS => "VXUS",
-        StockSymbol::BNDX => "BNDX",
-        StockSymbol::VTIP => "VTIP",
-        StockSymbol::VTI => "VTI",
-        StockSymbol::VTIVX => "VTIVX",
-        _ => "none",
-    };
-    if stock_str == "none" {
-        eprintln!("Stock symbol not supported for yahoo retrieval");
-        Ok(0.0)
-    } else {
+pub async fn get_yahoo_eoy_quote(stock_symbol: StockSymbol, year: u32) -> Result<Decimal> {
+    // Money market funds hold a stable $1 NAV
+    if fund_universe().asset_class(&stock_symbol) == Some(AssetClass::Cash) {
+        return Ok(Decimal::ONE);
+    }
+    if let Some(stock_str) = quote_symbol(&stock_symbol) {
         let provider = yahoo::YahooConnector::new()?;
         let format = format_description!(
             "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory]"
         );
-        let start =
-            OffsetDateTime::parse(&format!("{}-12-25 00:00:01 -05", year), format)?;
-        let stop =
-            OffsetDateTime::parse(&format!("{}-12-31 23:59:59 -05", year), format)?;
+        let start = OffsetDateTime::parse(&format!("{}-12-25 00:00:01 -05", year), format)?;
+        let stop = OffsetDateTime::parse(&format!("{}-12-31 23:59:59 -05", year), format)?;
         let response = provider
-            .get_quote_history(stock_str, start, stop)
+            .get_quote_history(&stock_str, start, stop)
             .await
             .with_context(|| format!("Quote history error for: {}", stock_str))?;
-        Ok(response.quotes()?.last().unwrap().close as f32)
+        dollars_from_f64(response.quotes()?.last().unwrap().close)
+    } else {
+        eprintln!("Stock symbol not supported for yahoo retrieval");
+        Ok(Decimal::ZERO)
     }
 }
 
@@ -399,24 +358,16 @@ pub enum AddType {
     HoldingValue,
 }
 
-/// ShareValues holds the values for the supported ETF stocks.  The value can represent price,
-/// holding value, stock quantity etc.
-#[derive(Clone, PartialEq, Debug, Copy)]
+/// ShareValues holds a value for each stock symbol.  The value can represent price, holding
+/// value, stock quantity etc.  Any symbol can be held, including ones not supported by the
+/// rebalance model, so that no holdings are dropped from totals.  Symbols without a stored value
+/// return the fill value, which is 0 for holdings and 1 for quotes.  The value of holdings outside
+/// of Vanguard is kept separately as the exposure to the fund of each sub class.
+#[derive(Clone, PartialEq, Debug)]
 pub struct ShareValues {
-    vxus: f32,
-    bndx: f32,
-    bnd: f32,
-    vwo: f32,
-    vo: f32,
-    vb: f32,
-    vtc: f32,
-    vv: f32,
-    vtip: f32,
-    vti: f32,
-    vtivx: f32,
-    vmfxx: f32,
-    outside_bond: f32,
-    outside_stock: f32,
+    values: HashMap<StockSymbol, Decimal>,
+    fill_value: Decimal,
+    outside: HashMap<StockSymbol, Decimal>,
 }
 
 impl ShareValues {
@@ -431,42 +382,17 @@ impl ShareValues {
     /// ```
     pub fn new() -> Self {
         ShareValues {
-            vxus: 0.0,
-            bndx: 0.0,
-            vtip: 0.0,
-            vti: 0.0,
-            vtivx: 0.0,
-            bnd: 0.0,
-            vwo: 0.0,
-            vo: 0.0,
-            vb: 0.0,
-            vtc: 0.0,
-            vv: 0.0,
-            vmfxx: 0.0,
-            outside_bond: 0.0,
-            outside_stock: 0.0,
+            values: HashMap::new(),
+            fill_value: Decimal::ZERO,
+            outside: HashMap::new(),
         }
     }
 
-    pub fn value_added(&self, default_value: f32) -> bool {
-        [
-            self.vxus,
-            self.bndx,
-            self.vtip,
-            self.vti,
-            self.vtivx,
-            self.bnd,
-            self.vwo,
-            self.vo,
-            self.vb,
-            self.vtc,
-            self.vv,
-            self.vmfxx,
-            self.outside_bond,
-            self.outside_stock,
-        ]
-        .iter()
-        .any(|val| val != &default_value)
+    pub fn value_added(&self, default_value: Decimal) -> bool {
+        self.values
+            .values()
+            .chain(self.outside.values())
+            .any(|val| val != &default_value)
     }
     /// new_quote creates a new ShareValues struct where all values are set to 1.  This is used for
     /// creating a new struct for stock quotes.  This way if any quotes are missing, they are
@@ -476,44 +402,28 @@ impl ShareValues {
     ///
     /// # Example
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let new_quotes = holdings::ShareValues::new_quote();
+    /// assert_eq!(new_quotes.stock_value(holdings::StockSymbol::new("XYZ")), dec!(1.0));
     /// ```
     pub fn new_quote() -> Self {
         ShareValues {
-            vxus: 1.0,
-            bndx: 1.0,
-            vtip: 1.0,
-            vti: 1.0,
-            vtivx: 1.0,
-            bnd: 1.0,
-            vwo: 1.0,
-            vo: 1.0,
-            vb: 1.0,
-            vtc: 1.0,
-            vv: 1.0,
-            vmfxx: 1.0,
-            outside_bond: 1.0,
-            outside_stock: 1.0,
+            values: HashMap::new(),
+            fill_value: Decimal::ONE,
+            outside: HashMap::new(),
         }
     }
 
+    /// Retrieves the quotes for all ETFs within the fund universe which were not part of the
+    /// download.  Mutual funds are ordered in dollars at the next closing NAV, so a quote is not
+    /// needed to rebalance them.
     pub async fn add_missing_quotes(&mut self) -> Result<()> {
-        for stock_symbol in [
-            StockSymbol::VV,
-            StockSymbol::VO,
-            StockSymbol::VB,
-            StockSymbol::VTC,
-            StockSymbol::BND,
-            StockSymbol::VXUS,
-            StockSymbol::VWO,
-            StockSymbol::BNDX,
-            StockSymbol::VTIP,
-            StockSymbol::VTI,
-            StockSymbol::VTIVX,
-        ] {
-            if self.stock_value(stock_symbol.clone()) == 1.0 {
+        for stock_symbol in universe_symbols() {
+            if !self.values.contains_key(&stock_symbol)
+                && !fund_universe().is_mutual_fund(&stock_symbol)
+            {
                 let new_quote = get_yahoo_quote(stock_symbol.clone()).await?;
                 self.add_stock_value(stock_symbol, new_quote);
             }
@@ -521,21 +431,11 @@ impl ShareValues {
         Ok(())
     }
 
-    pub async fn add_missing_eoy_quotes(&mut self, year: u32) -> Result<()> {
-        for stock_symbol in [
-            StockSymbol::VV,
-            StockSymbol::VO,
-            StockSymbol::VB,
-            StockSymbol::VTC,
-            StockSymbol::BND,
-            StockSymbol::VXUS,
-            StockSymbol::VWO,
-            StockSymbol::BNDX,
-            StockSymbol::VTIP,
-            StockSymbol::VTI,
-            StockSymbol::VTIVX,
-        ] {
-            if self.stock_value(stock_symbol.clone()) == 1.0 {
+    /// Retrieves the end of year quotes for all stocks within the fund universe along with any
+    /// other symbols that are held
+    pub async fn add_missing_eoy_quotes(&mut self, year: u32, held: &ShareValues) -> Result<()> {
+        for stock_symbol in display_symbols(&[held]) {
+            if !self.values.contains_key(&stock_symbol) {
                 let new_quote = get_yahoo_eoy_quote(stock_symbol.clone(), year).await?;
                 self.add_stock_value(stock_symbol, new_quote);
             }
@@ -544,74 +444,92 @@ impl ShareValues {
     }
 
     /// new_target creates a new target ShareValues struct which determines what to what values to
-    /// rebalance to vanguard portfolio.
-    ///
-    /// # Panic
-    ///
-    /// Panics when the percentages and fractions do not add up to 1 when they are added together.
-    /// This is necessary to make sure everything adds up to 100% of the total portfolio.  Adding
-    /// up to less or more than 100% can happen when the const values determining balance distribution
-    /// are changed without changing other values to make sure everything adds up.
+    /// rebalance to vanguard portfolio.  Each sub allocation is placed within the fund universe funds
+    /// which fill that sub class.  Kept holdings, such as blended funds, stay at their current
+    /// value and outside holdings are held elsewhere, so the look through exposure of both is
+    /// subtracted from the sub class targets.
     ///
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::{asset, holdings};
     ///
     /// let sub_allocations = asset::SubAllocations::new().unwrap();
     ///
-    /// let brokerage_target = holdings::ShareValues::new_target(sub_allocations, 10000.0, 0.0, 0.0, 0.0, 0.0);
+    /// let mut blended = holdings::ShareValues::new();
+    /// blended.add_stock_value(holdings::StockSymbol::new("VTI"), dec!(1000.0));
+    ///
+    /// let mut outside = holdings::ShareValues::new();
+    /// outside.add_stock_value(holdings::StockSymbol::new("BND"), dec!(500.0));
+    ///
+    /// let brokerage_target = holdings::ShareValues::new_target(sub_allocations, dec!(10000.0), outside, blended).unwrap();
+    /// assert_eq!(brokerage_target.total_value(), dec!(10000.0));
+    /// assert_eq!(brokerage_target.stock_value(holdings::StockSymbol::new("VTI")), dec!(1000.0));
+    /// assert_eq!(brokerage_target.outside_exposure().total_value(), dec!(500.0));
     /// ```
     pub fn new_target(
         sub_allocations: SubAllocations,
-        total_vanguard_value: f32,
-        other_us_stock_value: f32,
-        other_us_bond_value: f32,
-        other_int_stock_value: f32,
-        other_int_bond_value: f32,
-    ) -> Self {
+        total_vanguard_value: Decimal,
+        outside_exposure: ShareValues,
+        kept_holdings: ShareValues,
+    ) -> Result<Self> {
+        let fixed_exposure = kept_holdings.look_through() + outside_exposure.look_through();
+
+        // A kept money market or cash held outside of Vanguard sits outside of the allocation
+        let fixed_cash = fixed_exposure
+            .values
+            .iter()
+            .filter(|(symbol, _)| fund_universe().asset_class(symbol) == Some(AssetClass::Cash))
+            .map(|(_, value)| value)
+            .sum::<Decimal>();
+
         // get total value
-        let total_value = total_vanguard_value
-            + other_us_stock_value
-            + other_us_bond_value
-            + other_int_bond_value
-            + other_int_stock_value;
+        let total_value = total_vanguard_value + outside_exposure.total_value() - fixed_cash;
 
         // Calculate values for each stock
-        let vxus_value = (total_value * sub_allocations.int_tot_stock / 100.0)
-            - (other_int_stock_value * 2.0 / 3.0);
-        let bndx_value = (total_value * sub_allocations.int_bond / 100.0) - other_int_bond_value;
-        let bnd_value =
-            (total_value * sub_allocations.us_tot_bond / 100.0) - (other_us_bond_value / 2.0);
-        let vwo_value = (total_value * sub_allocations.int_emerging_stock / 100.0)
-            - (other_int_stock_value / 3.0);
-        let vo_value =
-            (total_value * sub_allocations.us_stock_mid / 100.0) - (other_us_stock_value / 3.0);
-        let vb_value =
-            (total_value * sub_allocations.us_stock_small / 100.0) - (other_us_stock_value / 3.0);
-        let vtc_value =
-            (total_value * sub_allocations.us_corp_bond / 100.0) - (other_us_bond_value / 2.0);
-        let vv_value =
-            (total_value * sub_allocations.us_stock_large / 100.0) - (other_us_stock_value / 3.0);
-        let vtip_value = total_value * sub_allocations.inflation_protected / 100.0;
-
-        // set vmfxx, ie cash, target value to 0 and return ShareValues
-        ShareValues {
-            vxus: vxus_value,
-            bndx: bndx_value,
-            bnd: bnd_value,
-            vwo: vwo_value,
-            vo: vo_value,
-            vb: vb_value,
-            vtc: vtc_value,
-            vv: vv_value,
-            vtip: vtip_value,
-            vti: 0.0,
-            vtivx: 0.0,
-            vmfxx: 0.0,
-            outside_bond: other_int_bond_value + other_us_bond_value,
-            outside_stock: other_us_stock_value + other_int_stock_value,
+        let mut sub_class_target = ShareValues::new();
+        for (symbol, percent) in sub_allocations.fund_percents() {
+            let value = total_value * percent / Decimal::ONE_HUNDRED;
+            let current_value = sub_class_target.stock_value(symbol.clone());
+            sub_class_target.add_stock_value(symbol, current_value + value);
         }
+
+        // Kept and outside holdings are already in place, so their exposure is subtracted from the
+        // targets
+        for symbol in sub_class_target.symbols() {
+            sub_class_target
+                .subtract_stock_value(symbol.clone(), fixed_exposure.stock_value(symbol));
+        }
+
+        // Kept and outside holdings can already exceed the target of a sub class.  These targets
+        // are set to 0 and the excess is taken from the other targets in proportion to their value
+        let excess = sub_class_target
+            .values
+            .values()
+            .filter(|value| value.is_sign_negative())
+            .map(|value| -*value)
+            .sum::<Decimal>();
+        if !excess.is_zero() {
+            let positive_total = sub_class_target
+                .values
+                .values()
+                .filter(|value| value.is_sign_positive())
+                .sum::<Decimal>();
+            let scale = if positive_total > excess {
+                (positive_total - excess) / positive_total
+            } else {
+                Decimal::ZERO
+            };
+            for value in sub_class_target.values.values_mut() {
+                *value = (*value * scale).max(Decimal::ZERO);
+            }
+        }
+
+        // Any symbol left out of the target, including cash, has a target value of 0
+        let mut target = kept_holdings + sub_class_target.round_dollars_keeping_total();
+        target.add_outside_exposure(outside_exposure);
+        Ok(target)
     }
 
     /// add_stockinfo_value adds stock value to the ShareValues struct with a StockInfo input.  StockInfo
@@ -628,18 +546,19 @@ impl ShareValues {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let mut new_stock = holdings::StockInfo::new();
     /// new_stock.add_account(123456789);
-    /// new_stock.add_symbol(holdings::StockSymbol::BND);
-    /// new_stock.add_share_price(234.50);
-    /// new_stock.add_total_value(5000.00);
+    /// new_stock.add_symbol(holdings::StockSymbol::new("BND"));
+    /// new_stock.add_share_price(dec!(234.50));
+    /// new_stock.add_total_value(dec!(5000.00));
     ///
     /// let mut new_quotes = holdings::ShareValues::new_quote();
     /// new_quotes.add_stockinfo_value(new_stock, holdings::AddType::StockPrice);
     ///
-    /// assert_eq!(new_quotes.stock_value(holdings::StockSymbol::BND), 234.50);
+    /// assert_eq!(new_quotes.stock_value(holdings::StockSymbol::new("BND")), dec!(234.50));
     ///
     /// ```
     pub fn add_stockinfo_value(&mut self, stock_info: StockInfo, add_type: AddType) {
@@ -647,25 +566,10 @@ impl ShareValues {
             AddType::StockPrice => stock_info.share_price,
             AddType::HoldingValue => stock_info.total_value,
         };
-        match stock_info.symbol {
-            StockSymbol::VXUS => self.vxus = value,
-            StockSymbol::BNDX => self.bndx = value,
-            StockSymbol::VTIP => self.vtip = value,
-            StockSymbol::VTI => self.vti = value,
-            StockSymbol::VTIVX => self.vtivx = value,
-            StockSymbol::BND => self.bnd = value,
-            StockSymbol::VWO => self.vwo = value,
-            StockSymbol::VO => self.vo = value,
-            StockSymbol::VB => self.vb = value,
-            StockSymbol::VTC => self.vtc = value,
-            StockSymbol::VV => self.vv = value,
-            StockSymbol::VMFXX => self.vmfxx = value,
-            StockSymbol::Empty => panic!("Stock symbol not set before adding value"),
-            StockSymbol::Other(_) => (),
-        }
+        self.add_stock_value(stock_info.symbol, value)
     }
 
-    /// add_stock_value adds stock value to the ShareValues struct with a float.  
+    /// add_stock_value adds stock value to the ShareValues struct with a float.
     ///
     /// # Panic
     ///
@@ -675,73 +579,73 @@ impl ShareValues {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let mut new_values = holdings::ShareValues::new();
-    /// new_values.add_stock_value(holdings::StockSymbol::BND, 5000.0);
-    ///
-    /// assert_eq!(new_values.stock_value(holdings::StockSymbol::BND), 5000.0);
-    ///
-    /// ```
-    pub fn add_stock_value(&mut self, stock_symbol: StockSymbol, value: f32) {
-        match stock_symbol {
-            StockSymbol::VXUS => self.vxus = value,
-            StockSymbol::BNDX => self.bndx = value,
-            StockSymbol::VTIP => self.vtip = value,
-            StockSymbol::VTI => self.vti = value,
-            StockSymbol::VTIVX => self.vtivx = value,
-            StockSymbol::BND => self.bnd = value,
-            StockSymbol::VWO => self.vwo = value,
-            StockSymbol::VO => self.vo = value,
-            StockSymbol::VB => self.vb = value,
-            StockSymbol::VTC => self.vtc = value,
-            StockSymbol::VV => self.vv = value,
-            StockSymbol::VMFXX => self.vmfxx = value,
-            StockSymbol::Empty => panic!("Stock symbol not set before adding value"),
-            StockSymbol::Other(_) => (),
+    /// new_values.add_stock_value(holdings::StockSymbol::new("BND"), dec!(5000.0));
+    /// new_values.add_stock_value(holdings::StockSymbol::new("VNQ"), dec!(1000.0));
+    ///
+    /// assert_eq!(new_values.stock_value(holdings::StockSymbol::new("BND")), dec!(5000.0));
+    /// assert_eq!(new_values.stock_value(holdings::StockSymbol::new("VNQ")), dec!(1000.0));
+    ///
+    /// ```
+    pub fn add_stock_value(&mut self, stock_symbol: StockSymbol, value: Decimal) {
+        if stock_symbol.is_empty() {
+            panic!("Stock symbol not set before adding value")
         }
+        self.values.insert(stock_symbol, value);
     }
 
-    /// Adds other stock value that is not included within the vanguard account.  This is used for
-    /// calculating current stock/bond ratios
-    pub fn add_outside_stock_value(&mut self, stock_value: f32) {
-        self.outside_stock = stock_value
-    }
-
-    pub fn outside_stock_value(&self) -> f32 {
-        self.outside_stock
+    /// Adds the exposure of holdings that are not included within the vanguard account.  This is
+    /// used for calculating current stock/bond ratios
+    pub fn add_outside_exposure(&mut self, exposure: ShareValues) {
+        for (symbol, value) in exposure.look_through().values {
+            *self.outside.entry(symbol).or_insert(Decimal::ZERO) += value;
+        }
     }
 
-    /// Adds other bond value that is not included within the vanguard account.  This is used for
-    /// calculating current stock/bond ratios
-    pub fn add_outside_bond_value(&mut self, bond_value: f32) {
-        self.outside_bond = bond_value
+    /// outside_exposure returns the exposure of holdings outside of the vanguard account within
+    /// the fund of each sub class
+    pub fn outside_exposure(&self) -> ShareValues {
+        let mut exposure = ShareValues::new();
+        exposure.values = self.outside.clone();
+        exposure
     }
 
-    pub fn outside_bond_value(&self) -> f32 {
-        self.outside_bond
+    /// outside_value returns the value of holdings outside of the vanguard account within the
+    /// asset class
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{holdings::{ShareValues, StockSymbol}, universe::AssetClass};
+    ///
+    /// let mut outside = ShareValues::new();
+    /// outside.add_stock_value(StockSymbol::new("VTI"), dec!(1000.0));
+    /// outside.add_stock_value(StockSymbol::new("BND"), dec!(400.0));
+    ///
+    /// let mut brokerage = ShareValues::new();
+    /// brokerage.add_outside_exposure(outside);
+    /// assert_eq!(brokerage.outside_value(AssetClass::Stock), dec!(1000.0));
+    /// assert_eq!(brokerage.outside_value(AssetClass::Bond), dec!(400.0));
+    /// ```
+    pub fn outside_value(&self, asset_class: AssetClass) -> Decimal {
+        self.outside
+            .iter()
+            .filter(|(symbol, _)| fund_universe().asset_class(symbol) == Some(asset_class))
+            .map(|(_, value)| value)
+            .sum()
     }
 
-    pub fn subtract_stock_value(&mut self, stock_symbol: StockSymbol, value: f32) {
-        match stock_symbol {
-            StockSymbol::VXUS => self.vxus -= value,
-            StockSymbol::BNDX => self.bndx -= value,
-            StockSymbol::VTIP => self.vtip -= value,
-            StockSymbol::VTI => self.vti -= value,
-            StockSymbol::VTIVX => self.vtivx -= value,
-            StockSymbol::BND => self.bnd -= value,
-            StockSymbol::VWO => self.vwo -= value,
-            StockSymbol::VO => self.vo -= value,
-            StockSymbol::VB => self.vb -= value,
-            StockSymbol::VTC => self.vtc -= value,
-            StockSymbol::VV => self.vv -= value,
-            StockSymbol::VMFXX => self.vmfxx -= value,
-            StockSymbol::Empty => panic!("Stock symbol not set before adding value"),
-            StockSymbol::Other(_) => (),
-        }
+    pub fn subtract_stock_value(&mut self, stock_symbol: StockSymbol, value: Decimal) {
+        let new_value = self.stock_value(stock_symbol.clone()) - value;
+        self.add_stock_value(stock_symbol, new_value)
     }
 
-    /// stock_value retrieves the stored stock value within the ShareValues struct
+    /// stock_value retrieves the stored stock value within the ShareValues struct.  Symbols
+    /// without a stored value return the fill value.
     ///
     /// # Panic
     ///
@@ -751,31 +655,54 @@ impl ShareValues {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let mut new_values = holdings::ShareValues::new();
-    /// new_values.add_stock_value(holdings::StockSymbol::BND, 5000.0);
-    ///
-    /// assert_eq!(new_values.stock_value(holdings::StockSymbol::BND), 5000.0);
-    ///
-    /// ```
-    pub fn stock_value(&self, stock_symbol: StockSymbol) -> f32 {
-        match stock_symbol {
-            StockSymbol::VXUS => self.vxus,
-            StockSymbol::BNDX => self.bndx,
-            StockSymbol::VTIP => self.vtip,
-            StockSymbol::VTI => self.vti,
-            StockSymbol::VTIVX => self.vtivx,
-            StockSymbol::BND => self.bnd,
-            StockSymbol::VWO => self.vwo,
-            StockSymbol::VO => self.vo,
-            StockSymbol::VB => self.vb,
-            StockSymbol::VTC => self.vtc,
-            StockSymbol::VV => self.vv,
-            StockSymbol::VMFXX => self.vmfxx,
-            StockSymbol::Empty => panic!("Value retrieval not supported for empty stock symbol"),
-            StockSymbol::Other(symbol) => panic!("Value retrieval not supported for {}", symbol),
+    /// new_values.add_stock_value(holdings::StockSymbol::new("BND"), dec!(5000.0));
+    ///
+    /// assert_eq!(new_values.stock_value(holdings::StockSymbol::new("BND")), dec!(5000.0));
+    /// assert_eq!(new_values.stock_value(holdings::StockSymbol::new("VV")), dec!(0.0));
+    ///
+    /// ```
+    pub fn stock_value(&self, stock_symbol: StockSymbol) -> Decimal {
+        if stock_symbol.is_empty() {
+            panic!("Value retrieval not supported for empty stock symbol")
         }
+        *self.values.get(&stock_symbol).unwrap_or(&self.fill_value)
+    }
+
+    /// symbols returns all stock symbols with a stored value.  Supported symbols come first in
+    /// display order, followed by any other symbols sorted alphabetically.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{ShareValues, StockSymbol};
+    ///
+    /// let mut new_values = ShareValues::new();
+    /// new_values.add_stock_value(StockSymbol::new("VNQ"), dec!(1000.0));
+    /// new_values.add_stock_value(StockSymbol::new("BND"), dec!(5000.0));
+    ///
+    /// assert_eq!(new_values.symbols(), vec![StockSymbol::new("BND"), StockSymbol::new("VNQ")]);
+    /// ```
+    pub fn symbols(&self) -> Vec<StockSymbol> {
+        let mut symbols = fund_universe()
+            .funds()
+            .iter()
+            .map(|fund| fund.symbol.clone())
+            .filter(|symbol| self.values.contains_key(symbol))
+            .collect::<Vec<StockSymbol>>();
+        let mut other_symbols = self
+            .values
+            .keys()
+            .filter(|symbol| !fund_universe().contains(symbol))
+            .cloned()
+            .collect::<Vec<StockSymbol>>();
+        other_symbols.sort_by_key(|symbol| symbol.to_string());
+        symbols.extend(other_symbols);
+        symbols
     }
 
     /// total_value returns the sum of all of the values within the StockValue struct
@@ -783,44 +710,379 @@ impl ShareValues {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::holdings;
     ///
     /// let mut new_values = holdings::ShareValues::new();
-    /// new_values.add_stock_value(holdings::StockSymbol::BND, 5000.0);
-    /// new_values.add_stock_value(holdings::StockSymbol::BNDX, 2000.0);
-    /// new_values.add_stock_value(holdings::StockSymbol::VB, 4000.0);
+    /// new_values.add_stock_value(holdings::StockSymbol::new("BND"), dec!(5000.0));
+    /// new_values.add_stock_value(holdings::StockSymbol::new("BNDX"), dec!(2000.0));
+    /// new_values.add_stock_value(holdings::StockSymbol::new("VB"), dec!(4000.0));
+    /// new_values.add_stock_value(holdings::StockSymbol::new("VNQ"), dec!(1000.0));
     ///
-    /// assert_eq!(new_values.total_value(), 11000.0);
+    /// assert_eq!(new_values.total_value(), dec!(12000.0));
     ///
     /// ```
-    pub fn total_value(&self) -> f32 {
-        self.vxus
-            + self.bndx
-            + self.bnd
-            + self.vwo
-            + self.vo
-            + self.vb
-            + self.vtc
-            + self.vv
-            + self.vmfxx
-            + self.vtip
-            + self.vti
-            + self.vtivx
+    pub fn total_value(&self) -> Decimal {
+        self.values.values().sum()
     }
 
     /// percent_stock_bond_infl calculates the percent of stock, bond, and inflation protected
     /// assets within the ShareValues.  This should only be used when the struct contains dollar
     /// value amounts for the stock values.
-    pub fn percent_stock_bond_infl(&self) -> (f32, f32, f32) {
-        let total_bond = self.bndx + self.bnd + self.vtc + self.outside_bond;
-        let total_stock = self.vwo + self.vo + self.vb + self.vv + self.vxus + self.outside_stock;
-        let total = self.total_value() - self.vmfxx + self.outside_bond + self.outside_stock;
+    pub fn percent_stock_bond_infl(&self) -> (Decimal, Decimal, Decimal) {
+        let exposure = self.look_through();
+        let mut total_stock = Decimal::ZERO;
+        let mut total_bond = Decimal::ZERO;
+        let mut total_inflation = Decimal::ZERO;
+        let mut total = Decimal::ZERO;
+        for (symbol, value) in exposure.values.iter().chain(&self.outside) {
+            match fund_universe().asset_class(symbol) {
+                Some(AssetClass::Stock) => total_stock += value,
+                Some(AssetClass::Bond) => total_bond += value,
+                Some(AssetClass::InflationProtected) => total_inflation += value,
+                Some(AssetClass::Cash) => continue,
+                Some(AssetClass::Blend) | None => (),
+            }
+            total += value;
+        }
+        if total.is_zero() {
+            return (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+        }
         (
-            total_stock / total * 100.0,
-            total_bond / total * 100.0,
-            self.vtip / total * 100.0,
+            total_stock / total * Decimal::ONE_HUNDRED,
+            total_bond / total * Decimal::ONE_HUNDRED,
+            total_inflation / total * Decimal::ONE_HUNDRED,
         )
     }
+
+    /// look_through returns the ShareValues with each blended fund replaced by its composition
+    /// within the funds of each sub class.  This should only be used when the struct contains
+    /// dollar value amounts for the stock values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{ShareValues, StockSymbol};
+    ///
+    /// let mut new_values = ShareValues::new();
+    /// new_values.add_stock_value(StockSymbol::new("VV"), dec!(1000.0));
+    /// new_values.add_stock_value(StockSymbol::new("VTI"), dec!(1000.0));
+    ///
+    /// let exposure = new_values.look_through();
+    /// assert_eq!(exposure.stock_value(StockSymbol::new("VV")), dec!(1720.0));
+    /// assert_eq!(exposure.stock_value(StockSymbol::new("VB")), dec!(100.0));
+    /// assert_eq!(exposure.stock_value(StockSymbol::new("VTI")), dec!(0.0));
+    /// ```
+    pub fn look_through(&self) -> ShareValues {
+        let mut exposure = ShareValues {
+            values: HashMap::new(),
+            fill_value: self.fill_value,
+            outside: self.outside.clone(),
+        };
+        for (symbol, value) in &self.values {
+            if let Some(composition) = fund_universe().composition(symbol) {
+                for (sub_class, percent) in composition {
+                    let sub_class_symbol = fund_universe()
+                        .sub_class_symbol(sub_class)
+                        .expect("Fund universe validated composition sub classes");
+                    let current_value = exposure.stock_value(sub_class_symbol.clone());
+                    exposure.add_stock_value(
+                        sub_class_symbol,
+                        current_value + value * percent / Decimal::ONE_HUNDRED,
+                    );
+                }
+            } else {
+                // Mutual fund share classes are exposed through their ETF equivalent
+                let symbol = fund_universe()
+                    .model_fund(symbol)
+                    .map(|fund| fund.symbol.clone())
+                    .unwrap_or_else(|| symbol.clone());
+                let current_value = exposure.stock_value(symbol.clone());
+                exposure.add_stock_value(symbol, current_value + value);
+            }
+        }
+        exposure
+    }
+
+    /// kept returns the holdings which are kept at their current value rather than rebalanced.
+    /// These are blended funds, which are funds with a composition within the fund universe, and
+    /// the money market kept as a holding.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{ShareValues, StockSymbol};
+    ///
+    /// let mut new_values = ShareValues::new();
+    /// new_values.add_stock_value(StockSymbol::new("VV"), dec!(1000.0));
+    /// new_values.add_stock_value(StockSymbol::new("VTI"), dec!(500.0));
+    ///
+    /// assert_eq!(new_values.kept().symbols(), vec![StockSymbol::new("VTI")]);
+    /// ```
+    pub fn kept(&self) -> ShareValues {
+        let mut kept = ShareValues::new();
+        for (symbol, value) in &self.values {
+            if fund_universe().composition(symbol).is_some()
+                || fund_universe().kept_money_market() == Some(symbol)
+            {
+                kept.add_stock_value(symbol.clone(), *value);
+            }
+        }
+        kept
+    }
+
+    /// cash_value returns the investable cash, which is the value pooled across the settlement
+    /// fund and every other money market fund which is not kept as a holding.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{ShareValues, StockSymbol};
+    ///
+    /// let mut new_values = ShareValues::new();
+    /// new_values.add_stock_value(StockSymbol::new("VMFXX"), dec!(100.0));
+    /// new_values.add_stock_value(StockSymbol::new("VUSXX"), dec!(400.0));
+    ///
+    /// assert_eq!(new_values.cash_value(), dec!(500.0));
+    /// ```
+    pub fn cash_value(&self) -> Decimal {
+        self.values
+            .iter()
+            .filter(|(symbol, _)| fund_universe().is_pooled_cash(symbol))
+            .map(|(_, value)| value)
+            .sum()
+    }
+
+    /// add_cash adds to the value held within the settlement fund.  Negative values withdraw cash.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::ShareValues;
+    ///
+    /// let mut new_values = ShareValues::new();
+    /// new_values.add_cash(dec!(500.0));
+    /// new_values.add_cash(dec!(-200.0));
+    ///
+    /// assert_eq!(new_values.cash_value(), dec!(300.0));
+    /// ```
+    pub fn add_cash(&mut self, value: Decimal) {
+        let cash_symbol = fund_universe().cash_symbol();
+        let cash = self.stock_value(cash_symbol.clone());
+        self.add_stock_value(cash_symbol, cash + value)
+    }
+
+    /// round_shares rounds each value toward zero to the number of decimals.  Used to round the
+    /// number of shares to purchase or sell.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{ShareValues, StockSymbol};
+    ///
+    /// let mut shares = ShareValues::new();
+    /// shares.add_stock_value(StockSymbol::new("VV"), dec!(5.55559));
+    /// shares.add_stock_value(StockSymbol::new("BND"), dec!(-2.55559));
+    ///
+    /// let rounded = shares.round_shares(3);
+    /// assert_eq!(rounded.stock_value(StockSymbol::new("VV")), dec!(5.555));
+    /// assert_eq!(rounded.stock_value(StockSymbol::new("BND")), dec!(-2.555));
+    /// ```
+    pub fn round_shares(mut self, decimals: u32) -> ShareValues {
+        for value in self.values.values_mut() {
+            *value = round_shares(*value, decimals);
+        }
+        self
+    }
+
+    /// to_orders converts the dollar value to buy or sell of each fund into an order.  ETF orders
+    /// are a number of shares rounded toward zero to the number of decimals.  Mutual funds are
+    /// bought and sold in dollar amounts at the next closing NAV, so their orders stay in dollars
+    /// rounded to the cent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{ShareValues, StockSymbol};
+    ///
+    /// let mut difference = ShareValues::new();
+    /// difference.add_stock_value(StockSymbol::new("BND"), dec!(1000.0));
+    /// difference.add_stock_value(StockSymbol::new("VBTLX"), dec!(-500.555));
+    ///
+    /// let mut quotes = ShareValues::new_quote();
+    /// quotes.add_stock_value(StockSymbol::new("BND"), dec!(72.50));
+    /// quotes.add_stock_value(StockSymbol::new("VBTLX"), dec!(9.80));
+    ///
+    /// let orders = difference.to_orders(quotes, 2);
+    /// assert_eq!(orders.stock_value(StockSymbol::new("BND")), dec!(13.79));
+    /// assert_eq!(orders.stock_value(StockSymbol::new("VBTLX")), dec!(-500.56));
+    /// ```
+    pub fn to_orders(self, quotes: ShareValues, share_decimals: u32) -> ShareValues {
+        let mut orders = (self.clone() / quotes).round_shares(share_decimals);
+        for (symbol, value) in self.values {
+            if fund_universe().is_mutual_fund(&symbol) {
+                orders.add_stock_value(symbol, round_dollars(value));
+            }
+        }
+        orders
+    }
+
+    /// use_mutual_fund_share_classes moves the target of each ETF onto its mutual fund share class
+    /// for an account which holds mutual funds.  The current holdings decide where the target is
+    /// placed:
+    ///
+    /// - When a share class or substitute is held, the target is split between the held share
+    ///   classes, substitutes and the ETF, if held, in proportion to their current value.  A
+    ///   substitute bought when harvesting a loss keeps the target rather than being sold back into
+    ///   the ETF.
+    /// - When the account holds mutual fund share classes and none of their ETF equivalents, the
+    ///   target moves to the mutual fund as long as it meets the minimum initial purchase.
+    ///
+    /// Otherwise the target stays within the ETF.  This should only be used on a target ShareValues.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{ShareValues, StockSymbol};
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("VBTLX"), dec!(4000.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("BND"), dec!(5000.0));
+    /// target.add_stock_value(StockSymbol::new("VXUS"), dec!(4000.0));
+    /// target.add_stock_value(StockSymbol::new("VWO"), dec!(1000.0));
+    ///
+    /// let target = target.use_mutual_fund_share_classes(&current);
+    /// assert_eq!(target.stock_value(StockSymbol::new("VBTLX")), dec!(5000.0));
+    /// assert_eq!(target.stock_value(StockSymbol::new("BND")), dec!(0.0));
+    /// assert_eq!(target.stock_value(StockSymbol::new("VTIAX")), dec!(4000.0));
+    /// // Below the minimum initial purchase of VEMAX
+    /// assert_eq!(target.stock_value(StockSymbol::new("VWO")), dec!(1000.0));
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("IXUS"), dec!(3000.0));
+    /// current.add_stock_value(StockSymbol::new("VXUS"), dec!(1000.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VXUS"), dec!(5000.0));
+    ///
+    /// let target = target.use_mutual_fund_share_classes(&current);
+    /// assert_eq!(target.stock_value(StockSymbol::new("IXUS")), dec!(3750.0));
+    /// assert_eq!(target.stock_value(StockSymbol::new("VXUS")), dec!(1250.0));
+    /// ```
+    pub fn use_mutual_fund_share_classes(mut self, current: &ShareValues) -> ShareValues {
+        let universe = fund_universe();
+        let held = |symbol: &StockSymbol| current.stock_value(symbol.clone()) > Decimal::ZERO;
+        let held_share_class = current.symbols().iter().any(|symbol| {
+            held(symbol)
+                && universe
+                    .fund(symbol)
+                    .is_some_and(|fund| fund.etf_equivalent.is_some())
+        });
+        let held_etf_equivalent = current
+            .symbols()
+            .iter()
+            .any(|symbol| held(symbol) && !universe.mutual_fund_share_classes(symbol).is_empty());
+        let mutual_fund_account = held_share_class && !held_etf_equivalent;
+
+        for etf_symbol in self.symbols() {
+            let target = self.stock_value(etf_symbol.clone());
+            let share_classes = universe.mutual_fund_share_classes(&etf_symbol);
+            let members = universe.model_members(&etf_symbol);
+            if target <= Decimal::ZERO || members.is_empty() {
+                continue;
+            }
+            let mut held_symbols = members
+                .iter()
+                .map(|fund| fund.symbol.clone())
+                .filter(|symbol| held(symbol))
+                .collect::<Vec<StockSymbol>>();
+            if !held_symbols.is_empty() {
+                // Split the target in proportion to the current value of each held fund, where the
+                // last fund takes any rounding remainder
+                if held(&etf_symbol) {
+                    held_symbols.push(etf_symbol.clone());
+                } else {
+                    self.add_stock_value(etf_symbol.clone(), Decimal::ZERO);
+                }
+                let held_total = held_symbols
+                    .iter()
+                    .map(|symbol| current.stock_value(symbol.clone()))
+                    .sum::<Decimal>();
+                let mut remaining = target;
+                for (index, symbol) in held_symbols.iter().enumerate() {
+                    let value = if index == held_symbols.len() - 1 {
+                        remaining
+                    } else {
+                        round_dollars(target * current.stock_value(symbol.clone()) / held_total)
+                    };
+                    remaining -= value;
+                    self.add_stock_value(symbol.clone(), value);
+                }
+            } else if mutual_fund_account && !share_classes.is_empty() {
+                let share_class = share_classes[0];
+                if target >= share_class.minimum_initial_purchase.unwrap_or_default() {
+                    self.add_stock_value(etf_symbol, Decimal::ZERO);
+                    self.add_stock_value(share_class.symbol.clone(), target);
+                }
+            }
+        }
+        self
+    }
+
+    /// round_dollars_keeping_total rounds each value to the nearest cent.  Any difference between
+    /// the rounded total and the sum of the rounded values is placed within the largest value so
+    /// that the values still add up to the total to the cent.
+    fn round_dollars_keeping_total(mut self) -> ShareValues {
+        let total = round_dollars(self.total_value());
+        for value in self.values.values_mut() {
+            *value = round_dollars(*value);
+        }
+        let remainder = total - self.total_value();
+        if let Some(largest) = self.values.values_mut().max_by_key(|value| value.abs()) {
+            *largest += remainder;
+        }
+        self
+    }
+
+    /// Combines two ShareValues with the operation across the union of their stock symbols
+    fn combine(
+        self,
+        other: ShareValues,
+        operation: fn(Decimal, Decimal) -> Decimal,
+    ) -> ShareValues {
+        let mut values = HashMap::new();
+        for symbol in self.values.keys().chain(other.values.keys()) {
+            values.entry(symbol.clone()).or_insert_with(|| {
+                operation(
+                    self.stock_value(symbol.clone()),
+                    other.stock_value(symbol.clone()),
+                )
+            });
+        }
+        let mut outside = HashMap::new();
+        for symbol in self.outside.keys().chain(other.outside.keys()) {
+            outside.entry(symbol.clone()).or_insert_with(|| {
+                operation(
+                    self.outside.get(symbol).copied().unwrap_or_default(),
+                    other.outside.get(symbol).copied().unwrap_or_default(),
+                )
+            });
+        }
+        ShareValues {
+            values,
+            fill_value: operation(self.fill_value, other.fill_value),
+            outside,
+        }
+    }
 }
 
 impl Default for ShareValues {
@@ -833,22 +1095,7 @@ impl Add for ShareValues {
     type Output = ShareValues;
 
     fn add(self, other: ShareValues) -> ShareValues {
-        ShareValues {
-            vxus: self.vxus + other.vxus,
-            bndx: self.bndx + other.bndx,
-            vtip: self.vtip + other.vtip,
-            vti: self.vti + other.vti,
-            vtivx: self.vtivx + other.vtivx,
-            bnd: self.bnd + other.bnd,
-            vwo: self.vwo + other.vwo,
-            vo: self.vo + other.vo,
-            vb: self.vb + other.vb,
-            vtc: self.vtc + other.vtc,
-            vv: self.vv + other.vv,
-            vmfxx: self.vmfxx + other.vmfxx,
-            outside_bond: self.outside_bond + other.outside_bond,
-            outside_stock: self.outside_stock + other.outside_stock,
-        }
+        self.combine(other, |a, b| a + b)
     }
 }
 
@@ -856,45 +1103,16 @@ impl Sub for ShareValues {
     type Output = ShareValues;
 
     fn sub(self, other: ShareValues) -> ShareValues {
-        ShareValues {
-            vxus: self.vxus - other.vxus,
-            bndx: self.bndx - other.bndx,
-            vtip: self.vtip - other.vtip,
-            vti: self.vti - other.vti,
-            vtivx: self.vtivx - other.vtivx,
-            bnd: self.bnd - other.bnd,
-            vwo: self.vwo - other.vwo,
-            vo: self.vo - other.vo,
-            vb: self.vb - other.vb,
-            vtc: self.vtc - other.vtc,
-            vv: self.vv - other.vv,
-            vmfxx: self.vmfxx - other.vmfxx,
-            outside_bond: self.outside_bond - other.outside_bond,
-            outside_stock: self.outside_stock - other.outside_stock,
-        }
+        self.combine(other, |a, b| a - b)
     }
 }
 
 impl Div for ShareValues {
     type Output = ShareValues;
 
+    /// Division by a zero value, such as a missing quote, results in zero rather than a panic
     fn div(self, other: ShareValues) -> ShareValues {
-        ShareValues {
-            vxus: self.vxus / other.vxus,
-            bndx: self.bndx / other.bndx,
-            vtip: self.vtip / other.vtip,
-            vti: self.vti / other.vti,
-            vtivx: self.vtivx / other.vtivx,
-            bnd: self.bnd / other.bnd,
-            vwo: self.vwo / other.vwo,
-            vo: self.vo / other.vo,
-            vb: self.vb / other.vb,
-            vtc: self.vtc / other.vtc,
-            vv: self.vv / other.vv,
-            vmfxx: self.vmfxx / other.vmfxx,
-            outside_bond: self.outside_bond / other.outside_bond,
-            outside_stock: self.outside_stock / other.outside_stock,
-        }
+        self.combine(other, |a, b| a.checked_div(b).unwrap_or(Decimal::ZERO))
     }
 }
 
@@ -902,67 +1120,42 @@ impl Mul for ShareValues {
     type Output = ShareValues;
 
     fn mul(self, other: ShareValues) -> ShareValues {
-        ShareValues {
-            vxus: self.vxus * other.vxus,
-            bndx: self.bndx * other.bndx,
-            vtip: self.vtip * other.vtip,
-            vti: self.vti * other.vti,
-            vtivx: self.vtivx * other.vtivx,
-            bnd: self.bnd * other.bnd,
-            vwo: self.vwo * other.vwo,
-            vo: self.vo * other.vo,
-            vb: self.vb * other.vb,
-            vtc: self.vtc * other.vtc,
-            vv: self.vv * other.vv,
-            vmfxx: self.vmfxx * other.vmfxx,
-            outside_bond: self.outside_bond * other.outside_bond,
-            outside_stock: self.outside_stock * other.outside_stock,
-        }
+        self.combine(other, |a, b| a * b)
     }
 }
 
 impl fmt::Display for ShareValues {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let (stock, bond, inflation) = self.percent_stock_bond_infl();
+        let mut rows = String::new();
+        for symbol in display_symbols(&[self]) {
+            rows.push_str(&format!(
+                "{:<17}{:.2}\n",
+                symbol.to_string(),
+                self.stock_value(symbol)
+            ));
+        }
         write!(
             f,
             "\
             Symbol         Value\n\
             -------------------------------\n\
-            VV               {:.2}\n\
-            VO               {:.2}\n\
-            VB               {:.2}\n\
-            VTC              {:.2}\n\
-            BND              {:.2}\n\
-            VXUS             {:.2}\n\
-            VWO              {:.2}\n\
-            BNDX             {:.2}\n\
-            VTIP             {:.2}\n\
-            VTI              {:.2}\n\
-            VTIVX            {:.2}\n\
+            {}\
             -------------------------------\n\
             Cash             {:.2}\n\
             Total            {:.2}\n\
             Outside stock    {:.2}\n\
             Outside bond     {:.2}\n\
+            Outside infl     {:.2}\n\
             Stock:Bond:Infl  {:.1}:{:.1}:{:.1}\n\
             ===============================
             ",
-            self.vv,
-            self.vo,
-            self.vb,
-            self.vtc,
-            self.bnd,
-            self.vxus,
-            self.vwo,
-            self.bndx,
-            self.vtip,
-            self.vti,
-            self.vtivx,
-            self.vmfxx,
+            rows,
+            self.cash_value(),
             self.total_value(),
-            self.outside_stock,
-            self.outside_bond,
+            self.outside_value(AssetClass::Stock),
+            self.outside_value(AssetClass::Bond),
+            self.outside_value(AssetClass::InflationProtected),
             stock,
             bond,
             inflation
@@ -970,23 +1163,53 @@ impl fmt::Display for ShareValues {
     }
 }
 
-pub enum HoldingType {
-    Brokerage,
-    TraditionalIra,
-    RothIra,
+/// Symbols of all funds within the fund universe in display order, other than the money markets
+/// which are pooled as cash
+fn universe_symbols() -> Vec<StockSymbol> {
+    fund_universe()
+        .funds()
+        .iter()
+        .filter(|fund| !fund_universe().is_pooled_cash(&fund.symbol))
+        .map(|fund| fund.symbol.clone())
+        .collect()
+}
+
+/// Symbols to display as rows.  All fund universe symbols are always shown, except for mutual
+/// funds which are only shown when they have a value, followed by any other symbols held within
+/// the given ShareValues.  Pooled cash is excluded as it has its own row.
+fn display_symbols(share_values: &[&ShareValues]) -> Vec<StockSymbol> {
+    let mut other_symbols = Vec::new();
+    for values in share_values {
+        for symbol in values.symbols() {
+            if !fund_universe().contains(&symbol) && !other_symbols.contains(&symbol) {
+                other_symbols.push(symbol)
+            }
+        }
+    }
+    other_symbols.sort_by_key(|symbol| symbol.to_string());
+    let mut symbols = universe_symbols()
+        .into_iter()
+        .filter(|symbol| {
+            !fund_universe().is_mutual_fund(symbol)
+                || share_values
+                    .iter()
+                    .any(|values| !values.stock_value(symbol.clone()).is_zero())
+        })
+        .collect::<Vec<StockSymbol>>();
+    symbols.extend(other_symbols);
+    symbols
 }
 
 /// VanguardHoldings contains ShareValues structs for all accounts along with for the quotes.  This
 /// struct is creating during the parsing of the downloaded Vanguard file
 #[derive(Clone, Debug)]
 pub struct VanguardHoldings {
-    brokerage: Option<ShareValues>,
-    traditional_ira: Option<ShareValues>,
-    roth_ira: Option<ShareValues>,
+    accounts: Vec<(Account, ShareValues)>,
     quotes: ShareValues,
     transactions: Vec<Transaction>,
-    traditional_shares_option: Option<ShareValues>,
-    distributions: f32,
+    traditional_shares: HashMap<u32, ShareValues>,
+    distributions: Decimal,
+    lots: HashMap<u32, TaxLots>,
 }
 
 impl VanguardHoldings {
@@ -1004,75 +1227,107 @@ impl VanguardHoldings {
     /// ```
     pub fn new(quotes: ShareValues) -> Self {
         VanguardHoldings {
-            brokerage: None,
-            traditional_ira: None,
-            roth_ira: None,
+            accounts: Vec::new(),
             quotes,
             transactions: Vec::new(),
-            traditional_shares_option: None,
-            distributions: 0.0,
+            traditional_shares: HashMap::new(),
+            distributions: Decimal::ZERO,
+            lots: HashMap::new(),
         }
     }
 
-    /// add_holding adds a new account to the VanguardHoldings struct
+    /// add_holding adds a new account to the VanguardHoldings struct.  An account which was
+    /// already added is replaced.
     ///
     ///
     /// # Example
     ///
     /// ```
-    /// use vapore::holdings;
+    /// use vapore::{account::{Account, AccountKind}, holdings};
     ///
     /// let new_quotes = holdings::ShareValues::new_quote();
     ///
     /// let new_values = holdings::ShareValues::new();
     ///
     /// let mut new_vanguard = holdings::VanguardHoldings::new(new_quotes);
-    /// new_vanguard.add_holding(new_values, holdings::HoldingType::RothIra);
+    /// new_vanguard.add_holding(Account::new(22222222, AccountKind::TaxFree, None), new_values);
+    /// assert!(new_vanguard.holdings(22222222).is_some());
     /// ```
-    pub fn add_holding(&mut self, holding: ShareValues, holding_type: HoldingType) {
-        match holding_type {
-            HoldingType::RothIra => self.roth_ira = Some(holding),
-            HoldingType::Brokerage => self.brokerage = Some(holding),
-            HoldingType::TraditionalIra => self.traditional_ira = Some(holding),
+    pub fn add_holding(&mut self, account: Account, holding: ShareValues) {
+        match self
+            .accounts
+            .iter_mut()
+            .find(|(added, _)| added.number == account.number)
+        {
+            Some(added) => *added = (account, holding),
+            None => self.accounts.push((account, holding)),
         }
     }
 
-    pub fn brokerage_holdings(&self) -> Option<ShareValues> {
-        self.brokerage
-    }
-    pub fn traditional_ira_holdings(&self) -> Option<ShareValues> {
-        self.traditional_ira
+    /// accounts returns each account along with its holdings in the order they were added
+    pub fn accounts(&self) -> &[(Account, ShareValues)] {
+        &self.accounts
     }
-    pub fn roth_ira_holdings(&self) -> Option<ShareValues> {
-        self.roth_ira
+    /// holdings returns the holdings of the account number
+    pub fn holdings(&self, acct_num: u32) -> Option<ShareValues> {
+        self.accounts
+            .iter()
+            .find(|(account, _)| account.number == acct_num)
+            .map(|(_, holdings)| holdings.clone())
     }
     pub fn stock_quotes(&self) -> ShareValues {
-        self.quotes
+        self.quotes.clone()
     }
     pub fn transactions(&self) -> Vec<Transaction> {
         self.transactions.clone()
     }
-    pub fn distributions(&self) -> f32 {
+    /// distributions returns the distributions taken this year from the tax-deferred accounts of
+    /// the owner of the last end of year value calculated
+    pub fn distributions(&self) -> Decimal {
         self.distributions
     }
-    // Calculated the previous end of year holdings value based on the holdings times the quotes
-    // from December 31st of the previous year.
-    pub async fn eoy_value(&mut self, year: u32) -> Result<Option<f32>> {
-        if let Some(holdings) = self.eoy_traditional_holdings(year) {
+    /// lots returns the tax lots of a taxable account when the unrealized gains file was given
+    pub fn lots(&self, acct_num: u32) -> Option<TaxLots> {
+        self.lots.get(&acct_num).cloned()
+    }
+    // Calculated the previous end of year holdings value of the owner's tax-deferred accounts based
+    // on the holdings times the quotes from December 31st of the previous year.  Accounts without
+    // an owner are used when no owner is given.
+    pub async fn eoy_value(&mut self, year: u32, owner: Option<&str>) -> Result<Option<Decimal>> {
+        if let Some(holdings) = self.eoy_traditional_holdings(year, owner) {
             let mut quotes = ShareValues::new_quote();
-            quotes.add_missing_eoy_quotes(year - 1).await?;
+            quotes.add_missing_eoy_quotes(year - 1, &holdings).await?;
             let eoy_value = (holdings * quotes).total_value();
             Ok(Some(eoy_value))
         } else {
             Ok(None)
         }
     }
-    // Takes the current holdings and subtracts all transaction since December 31st to come to the
-    // holdings at that date.
-    fn eoy_traditional_holdings(&mut self, year: u32) -> Option<ShareValues> {
+    // Takes the current holdings of the owner's tax-deferred accounts and subtracts all of their
+    // transaction since December 31st to come to the holdings at that date.
+    fn eoy_traditional_holdings(&mut self, year: u32, owner: Option<&str>) -> Option<ShareValues> {
         let mut enough_transaction = false;
-        if let Some(trad_holdings) = self.traditional_shares_option {
-            if self.transactions.is_empty() {
+        self.distributions = Decimal::ZERO;
+        let traditional_numbers = self
+            .accounts
+            .iter()
+            .filter(|(account, _)| {
+                account.kind == AccountKind::TaxDeferred && account.owner.as_deref() == owner
+            })
+            .map(|(account, _)| account.number)
+            .collect::<Vec<u32>>();
+        let trad_holdings_option = traditional_numbers
+            .iter()
+            .filter_map(|number| self.traditional_shares.get(number).cloned())
+            .reduce(|total, holdings| total + holdings);
+        if let Some(trad_holdings) = trad_holdings_option {
+            let traditional_transactions = self
+                .transactions
+                .iter()
+                .filter(|transaction| traditional_numbers.contains(&transaction.account_number))
+                .cloned()
+                .collect::<Vec<Transaction>>();
+            if traditional_transactions.is_empty() {
                 eprintln!(
                     "No transactions found to calculate EOY holdings for minimum distribution"
                 );
@@ -1081,7 +1336,7 @@ impl VanguardHoldings {
                 let mut eoy_holdings = trad_holdings;
                 let previous_year = NaiveDate::from_ymd_opt(year as i32 - 1, 12, 31)?;
                 let following_year = previous_year + Duration::days(365);
-                for transaction in &self.transactions {
+                for transaction in &traditional_transactions {
                     // If the transaction is newer thand December 31st of the previous year,
                     // subtract from the current holdings.  Also stores a true value if anything is
                     // older to keep track whether or not enough transactions were pulled from
@@ -1089,20 +1344,20 @@ impl VanguardHoldings {
                     if transaction.trade_date > previous_year {
                         // Cash is allocated in VMFXX.  These are not shares in the transaction, so
                         // net amount needs to be subtracted
-                        if transaction.symbol == StockSymbol::VMFXX {
+                        if transaction.symbol == fund_universe().cash_symbol() {
                             eoy_holdings.subtract_stock_value(
                                 transaction.symbol.clone(),
                                 transaction.net_amount,
                             );
-                        } else if transaction.symbol != StockSymbol::Empty {
+                        } else if !transaction.symbol.is_empty() {
                             eoy_holdings.subtract_stock_value(
                                 transaction.symbol.clone(),
                                 transaction.shares,
                             );
-                        } else if transaction.transaction_type == TransactionType::DISTRIBUTION {
-                            if transaction.trade_date < following_year {
-                                self.distributions -= transaction.net_amount
-                            }
+                        } else if transaction.transaction_type == TransactionType::Distribution
+                            && transaction.trade_date < following_year
+                        {
+                            self.distributions -= transaction.net_amount
                         }
                     } else {
                         enough_transaction = true;
@@ -1125,6 +1380,12 @@ pub struct AccountHoldings {
     current: ShareValues,
     target: ShareValues,
     sale_purchases_needed: ShareValues,
+    cash_reserve: Decimal,
+    withdrawal: Decimal,
+    cash_after_trades: Option<Decimal>,
+    lot_sales: Option<LotSales>,
+    trade_costs: Option<Decimal>,
+    execution_plan: Option<ExecutionPlan>,
 }
 
 impl AccountHoldings {
@@ -1134,6 +1395,7 @@ impl AccountHoldings {
     /// # Example
     ///
     /// ```
+    /// use rust_decimal_macros::dec;
     /// use vapore::{asset, holdings};
     ///
     /// let sub_allocations = asset::SubAllocations::new().unwrap();
@@ -1141,8 +1403,8 @@ impl AccountHoldings {
     /// let quotes = holdings::ShareValues::new_quote();
     ///
     /// let brokerage_current = holdings::ShareValues::new();
-    /// let brokerage_target = holdings::ShareValues::new_target(sub_allocations, 10000.0, 0.0, 0.0, 0.0, 0.0);
-    /// let purchase_sales = brokerage_current / quotes;
+    /// let brokerage_target = holdings::ShareValues::new_target(sub_allocations, dec!(10000.0), holdings::ShareValues::new(), holdings::ShareValues::new()).unwrap();
+    /// let purchase_sales = brokerage_current.clone() / quotes;
     ///
     /// let brokerage_account = holdings::AccountHoldings::new(brokerage_current, brokerage_target, purchase_sales);
     /// ```
@@ -1155,7 +1417,291 @@ impl AccountHoldings {
             current,
             target,
             sale_purchases_needed,
+            cash_reserve: Decimal::ZERO,
+            withdrawal: Decimal::ZERO,
+            cash_after_trades: None,
+            lot_sales: None,
+            trade_costs: None,
+            execution_plan: None,
+        }
+    }
+
+    /// add_cash_reserve sets the cash kept within the settlement fund of the account, which is part
+    /// of the target cash and is shown as its own row
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{AccountHoldings, ShareValues};
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_cash(dec!(1500.0));
+    ///
+    /// let mut account = AccountHoldings::new(target.clone(), target, ShareValues::new());
+    /// account.add_cash_reserve(dec!(1500.0));
+    /// assert!(account.to_string().contains("Cash reserve                            $1500.00"));
+    /// ```
+    pub fn add_cash_reserve(&mut self, cash_reserve: Decimal) {
+        self.cash_reserve = cash_reserve;
+    }
+
+    /// add_withdrawal sets the cash withdrawn from the account, which the sales raise and which is
+    /// shown as its own row
+    pub fn add_withdrawal(&mut self, withdrawal: Decimal) {
+        self.withdrawal = withdrawal;
+    }
+
+    /// add_lot_sales picks the tax lots sold by the sales of the account with the tax policy,
+    /// which are listed along with their estimated gains and tax below the account.  This is
+    /// called after the orders are rounded to whole shares, so the lots match the final orders.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     holdings::{AccountHoldings, ShareValues, StockSymbol},
+    ///     lots::{LotSelection, TaxLot, TaxLots, TaxRates},
+    ///     policy::TaxPolicy,
+    /// };
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("VV"), dec!(1000.0));
+    /// current.add_cash(dec!(0.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(625.0));
+    /// target.add_cash(dec!(375.0));
+    ///
+    /// let mut quotes = ShareValues::new_quote();
+    /// quotes.add_stock_value(StockSymbol::new("VV"), dec!(250.0));
+    ///
+    /// let mut lots = TaxLots::new();
+    /// lots.add_lot(TaxLot {
+    ///     symbol: StockSymbol::new("VV"),
+    ///     acquired: NaiveDate::from_ymd_opt(2020, 3, 15).unwrap(),
+    ///     shares: dec!(4.0),
+    ///     cost_basis: dec!(800.00),
+    /// });
+    /// let tax_policy = TaxPolicy {
+    ///     lot_selection: LotSelection::Hifo,
+    ///     rates: TaxRates { short_term: dec!(24), long_term: dec!(15) },
+    ///     max_tax_cost: None,
+    /// };
+    ///
+    /// // The sale of 1.5 shares is rounded to 2 shares, which is what the lots sell
+    /// let orders = (target.clone() - current.clone()).to_orders(quotes.clone(), 4);
+    /// let mut account = AccountHoldings::new(current, target, orders);
+    /// account.round_whole_shares(&quotes, 4);
+    /// account.add_lot_sales(&lots, &quotes, &tax_policy, NaiveDate::from_ymd_opt(2024, 9, 1).unwrap());
+    ///
+    /// let account = account.to_string();
+    /// assert!(account.contains("VV       -2.00 "));
+    /// assert!(account.contains("VV       2020-03-15  2.0000"));
+    /// assert!(account.contains("Estimated long term gain    $100.00"));
+    /// ```
+    pub fn add_lot_sales(
+        &mut self,
+        lots: &TaxLots,
+        quotes: &ShareValues,
+        tax_policy: &TaxPolicy,
+        as_of: NaiveDate,
+    ) {
+        let mut sales = Vec::new();
+        for symbol in self.sale_purchases_needed.symbols() {
+            let order = self.sale_purchases_needed.stock_value(symbol.clone());
+            let price = quotes.stock_value(symbol.clone());
+            if order >= Decimal::ZERO || price <= Decimal::ZERO {
+                continue;
+            }
+            // Mutual fund orders are in dollars
+            let shares = if fund_universe().is_mutual_fund(&symbol) {
+                -order / price
+            } else {
+                -order
+            };
+            sales.extend(lots.sell(&symbol, shares, price, tax_policy.lot_selection, as_of));
+        }
+        if !sales.is_empty() {
+            self.lot_sales = Some(LotSales {
+                sales,
+                rates: tax_policy.rates,
+            });
+        }
+    }
+
+    /// add_trade_costs estimates what the orders of the account cost to trade, which is shown as
+    /// its own row
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     costs::TradeCosts,
+    ///     holdings::{AccountHoldings, ShareValues, StockSymbol},
+    /// };
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_cash(dec!(1000.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(1000.0));
+    ///
+    /// let mut quotes = ShareValues::new_quote();
+    /// quotes.add_stock_value(StockSymbol::new("VV"), dec!(250.0));
+    ///
+    /// let orders = (target.clone() - current.clone()).to_orders(quotes.clone(), 4);
+    /// let mut account = AccountHoldings::new(current, target, orders);
+    /// account.add_trade_costs(&TradeCosts::default(), 33333333, &quotes);
+    /// assert_eq!(account.trade_costs(), Some(dec!(0.20)));
+    /// ```
+    pub fn add_trade_costs(
+        &mut self,
+        trade_costs: &TradeCosts,
+        account_number: u32,
+        quotes: &ShareValues,
+    ) {
+        let mut total = Decimal::ZERO;
+        for symbol in self.sale_purchases_needed.symbols() {
+            let order = self.sale_purchases_needed.stock_value(symbol.clone());
+            // Mutual fund orders are in dollars
+            let amount = if fund_universe().is_mutual_fund(&symbol) {
+                order
+            } else {
+                order * quotes.stock_value(symbol.clone())
+            };
+            total += trade_costs.cost(account_number, &symbol, amount);
         }
+        self.trade_costs = Some(round_dollars(total));
+    }
+
+    /// trade_costs returns the estimated cost of the orders of the account, if they were estimated
+    pub fn trade_costs(&self) -> Option<Decimal> {
+        self.trade_costs
+    }
+
+    /// add_execution_plan orders the trades of the account by when they can be made, which is
+    /// listed below the account
+    pub fn add_execution_plan(&mut self, quotes: &ShareValues) {
+        self.execution_plan = Some(ExecutionPlan::new(
+            &self.current,
+            &self.sale_purchases_needed,
+            quotes,
+            self.withdrawal,
+        ));
+    }
+
+    /// round_whole_shares rounds the ETF orders of the account to whole shares.  The quantities
+    /// are chosen to keep the squared dollar deviation of each fund from its target as small as
+    /// possible, without the orders spending more than the cash held above the cash reserve.  A
+    /// sale may still sell the fraction of a position which is sold in full.  Mutual fund orders
+    /// stay in dollars and the cash left after the trades is shown as its own row.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::holdings::{AccountHoldings, ShareValues, StockSymbol};
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_cash(dec!(1000.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(700.0));
+    /// target.add_stock_value(StockSymbol::new("BND"), dec!(300.0));
+    ///
+    /// let mut quotes = ShareValues::new_quote();
+    /// quotes.add_stock_value(StockSymbol::new("VV"), dec!(250.0));
+    /// quotes.add_stock_value(StockSymbol::new("BND"), dec!(72.50));
+    ///
+    /// let orders = (target.clone() - current.clone()).to_orders(quotes.clone(), 4);
+    /// let mut account = AccountHoldings::new(current, target, orders);
+    /// account.round_whole_shares(&quotes, 4);
+    ///
+    /// // 3 VV and 4 BND would spend $1040, so the BND order gives up a share
+    /// let account = account.to_string();
+    /// assert!(account.contains("VV       3.00 "));
+    /// assert!(account.contains("BND      3.00 "));
+    /// assert!(account.contains("Cash after trades                       $32.50"));
+    /// ```
+    pub fn round_whole_shares(&mut self, quotes: &ShareValues, share_decimals: u32) {
+        let universe = fund_universe();
+        let mut mutual_fund_spent = Decimal::ZERO;
+        let mut orders = Vec::new();
+        for symbol in self.sale_purchases_needed.symbols() {
+            let order = self.sale_purchases_needed.stock_value(symbol.clone());
+            if universe.is_pooled_cash(&symbol) || order.is_zero() {
+                continue;
+            }
+            if universe.is_mutual_fund(&symbol) {
+                mutual_fund_spent += order;
+                continue;
+            }
+            let price = quotes.stock_value(symbol.clone());
+            if price <= Decimal::ZERO {
+                continue;
+            }
+            let current = self.current.stock_value(symbol.clone());
+            let held = round_shares(current / price, share_decimals);
+            let shares = ((self.target.stock_value(symbol.clone()) - current) / price)
+                .round()
+                .max(-held);
+            orders.push(WholeShareOrder {
+                drift: current + shares * price - self.target.stock_value(symbol.clone()),
+                symbol,
+                price,
+                shares,
+                held,
+            });
+        }
+        // Cash the ETF orders may spend while keeping the cash reserve
+        let available = self.current.cash_value() - self.cash_reserve - mutual_fund_spent;
+        let spent = |orders: &[WholeShareOrder]| -> Decimal {
+            orders.iter().map(|order| order.shares * order.price).sum()
+        };
+
+        // Give up the shares which add the least squared deviation per dollar until the orders fit
+        // within the available cash
+        while spent(&orders) > available {
+            let cheapest = orders
+                .iter_mut()
+                .filter(|order| order.shares > -order.held)
+                .min_by_key(|order| {
+                    let step = Decimal::ONE.min(order.shares + order.held);
+                    order.deviation_change(-step) / (step * order.price)
+                });
+            match cheapest {
+                Some(order) => {
+                    let step = Decimal::ONE.min(order.shares + order.held);
+                    order.step(-step);
+                }
+                None => break,
+            }
+        }
+        // Spend the cash which is left over on the shares which lower the deviation the most
+        loop {
+            let left = available - spent(&orders);
+            let best = orders
+                .iter_mut()
+                .filter(|order| {
+                    order.price <= left && order.deviation_change(Decimal::ONE) < Decimal::ZERO
+                })
+                .min_by_key(|order| order.deviation_change(Decimal::ONE));
+            match best {
+                Some(order) => order.step(Decimal::ONE),
+                None => break,
+            }
+        }
+
+        for order in &orders {
+            self.sale_purchases_needed
+                .add_stock_value(order.symbol.clone(), order.shares);
+        }
+        self.cash_after_trades =
+            Some(self.current.cash_value() - mutual_fund_spent - spent(&orders));
     }
 }
 
@@ -1174,82 +1720,134 @@ impl fmt::Display for AccountHoldings {
             target_stock, target_bond, target_inflation
         );
 
+        let mut rows = String::new();
+        let mut mutual_fund_orders = false;
+        for symbol in display_symbols(&[&self.current, &self.target]) {
+            // Mutual fund orders are dollar amounts rather than shares
+            let order = self.sale_purchases_needed.stock_value(symbol.clone());
+            let order = if fund_universe().is_mutual_fund(&symbol) {
+                mutual_fund_orders = true;
+                format!("${:.2}", order)
+            } else {
+                format!("{:.2}", order)
+            };
+            rows.push_str(&format!(
+                "{:<9}{:<15}${:<15.2}${:<15.2}\n",
+                symbol.to_string(),
+                order,
+                self.current.stock_value(symbol.clone()),
+                self.target.stock_value(symbol)
+            ));
+        }
+        let cash_reserve_row = if self.cash_reserve.is_zero() {
+            String::new()
+        } else {
+            format!(
+                "Cash reserve            {:<16}${:<15.2}\n",
+                "", self.cash_reserve
+            )
+        };
+        let withdrawal_row = if self.withdrawal.is_zero() {
+            String::new()
+        } else {
+            format!("Withdrawal              ${:<15.2}\n", self.withdrawal)
+        };
+        let cash_after_trades_row = match self.cash_after_trades {
+            Some(cash) => format!("Cash after trades       {:<16}${:<15.2}\n", "", cash),
+            None => String::new(),
+        };
+        let trade_costs_row = match self.trade_costs {
+            Some(trade_costs) => format!("Trade costs             ${:<15.2}\n", trade_costs),
+            None => String::new(),
+        };
+        let mutual_fund_note = if mutual_fund_orders {
+            "\nMutual fund orders are in dollars and fill at the next closing NAV"
+        } else {
+            ""
+        };
+        let lot_sales = self
+            .lot_sales
+            .as_ref()
+            .map(|lot_sales| format!("\n\n{}", lot_sales))
+            .unwrap_or_default();
+        let execution_plan = self
+            .execution_plan
+            .as_ref()
+            .map(|execution_plan| format!("\n\n{}", execution_plan))
+            .unwrap_or_default();
+
         write!(
             f,
             "Symbol   Purchase/Sell  Current         Target\n\
             ------------------------------------------------------\n\
-            VV       {:<15.2}${:<15.2}${:<15.2}\n\
-            VO       {:<15.2}${:<15.2}${:<15.2}\n\
-            VB       {:<15.2}${:<15.2}${:<15.2}\n\
-            VTC      {:<15.2}${:<15.2}${:<15.2}\n\
-            BND      {:<15.2}${:<15.2}${:<15.2}\n\
-            VXUS     {:<15.2}${:<15.2}${:<15.2}\n\
-            VWO      {:<15.2}${:<15.2}${:<15.2}\n\
-            BNDX     {:<15.2}${:<15.2}${:<15.2}\n\
-            VTIP     {:<15.2}${:<15.2}${:<15.2}\n\
-            VTI      {:<15.2}${:<15.2}${:<15.2}\n\
-            VTIVX    {:<15.2}${:<15.2}${:<15.2}\n\
+            {}\
             ------------------------------------------------------\n\
             Cash                    ${:<15.2}${:<15.2}\n\
+            {}\
+            {}\
+            {}\
+            {}\
             Total                   ${:<15.2}\n\
             Outside stock           ${:<15.2}${:<15.2}\n\
             Outside bond            ${:<15.2}${:<15.2}\n\
+            Outside inflation       ${:<15.2}${:<15.2}\n\
             Stock:Bond:Inflation    {:<16}{:<15}\n\
-            ======================================================",
-            self.sale_purchases_needed.vv,
-            self.current.vv,
-            self.target.vv,
-            self.sale_purchases_needed.vo,
-            self.current.vo,
-            self.target.vo,
-            self.sale_purchases_needed.vb,
-            self.current.vb,
-            self.target.vb,
-            self.sale_purchases_needed.vtc,
-            self.current.vtc,
-            self.target.vtc,
-            self.sale_purchases_needed.bnd,
-            self.current.bnd,
-            self.target.bnd,
-            self.sale_purchases_needed.vxus,
-            self.current.vxus,
-            self.target.vxus,
-            self.sale_purchases_needed.vwo,
-            self.current.vwo,
-            self.target.vwo,
-            self.sale_purchases_needed.bndx,
-            self.current.bndx,
-            self.target.bndx,
-            self.sale_purchases_needed.vtip,
-            self.current.vtip,
-            self.target.vtip,
-            self.sale_purchases_needed.vti,
-            self.current.vti,
-            self.target.vti,
-            self.sale_purchases_needed.vtivx,
-            self.current.vtivx,
-            self.target.vtivx,
-            self.current.vmfxx,
-            self.target.vmfxx,
+            ======================================================{}{}{}",
+            rows,
+            self.current.cash_value(),
+            self.target.cash_value(),
+            cash_reserve_row,
+            withdrawal_row,
+            cash_after_trades_row,
+            trade_costs_row,
             self.current.total_value(),
-            self.current.outside_stock,
-            self.target.outside_stock,
-            self.current.outside_bond,
-            self.target.outside_bond,
+            self.current.outside_value(AssetClass::Stock),
+            self.target.outside_value(AssetClass::Stock),
+            self.current.outside_value(AssetClass::Bond),
+            self.target.outside_value(AssetClass::Bond),
+            self.current.outside_value(AssetClass::InflationProtected),
+            self.target.outside_value(AssetClass::InflationProtected),
             current_stock_bond,
             target_stock_bond,
+            mutual_fund_note,
+            lot_sales,
+            execution_plan,
         )
     }
 }
 
-/// VanguardRebalance holds AccountHoldings structs for each account; brokerage, traditional IRA,
-/// and roth IRA.  Each AccountHoldings struct holds the information of current holdings, target
-/// holdings, and the amount of stocks needed to purchase/sell in order to rebalance
+// WholeShareOrder is an ETF order being rounded to whole shares, along with how far the fund ends
+// up from its target after the order
+struct WholeShareOrder {
+    symbol: StockSymbol,
+    price: Decimal,
+    shares: Decimal,
+    held: Decimal,
+    drift: Decimal,
+}
+
+impl WholeShareOrder {
+    // deviation_change returns the change of the squared deviation from the target when the
+    // order is changed by the number of shares
+    fn deviation_change(&self, shares: Decimal) -> Decimal {
+        let drift = self.drift + shares * self.price;
+        drift * drift - self.drift * self.drift
+    }
+
+    fn step(&mut self, shares: Decimal) {
+        self.shares += shares;
+        self.drift += shares * self.price;
+    }
+}
+
+/// VanguardRebalance holds an AccountHoldings struct for each account.  Each AccountHoldings struct
+/// holds the information of current holdings, target holdings, and the amount of stocks needed to
+/// purchase/sell in order to rebalance
 pub struct VanguardRebalance {
-    brokerage: Option<AccountHoldings>,
-    traditional_ira: Option<AccountHoldings>,
-    roth_ira: Option<AccountHoldings>,
+    accounts: Vec<(Account, AccountHoldings)>,
     retirement_target: Option<ShareValues>,
+    location_comparison: Option<LocationComparison>,
+    notices: Vec<String>,
 }
 
 impl VanguardRebalance {
@@ -1264,45 +1862,71 @@ impl VanguardRebalance {
     /// ```
     pub fn new() -> Self {
         VanguardRebalance {
-            brokerage: None,
-            traditional_ira: None,
-            roth_ira: None,
+            accounts: Vec::new(),
             retirement_target: None,
+            location_comparison: None,
+            notices: Vec::new(),
         }
     }
 
-    /// add_account_holdings adds either roth IRA, traditional IRA, or brokerage AccountHoldings
-    /// struct to the current VanguardRebalance struct.
+    /// add_account_holdings adds the AccountHoldings struct of an account to the current
+    /// VanguardRebalance struct.  Accounts are printed in the order they are added.
     ///
     /// # Example
     ///
     /// ```
-    /// use vapore::{asset, holdings};
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{account::{Account, AccountKind}, asset, holdings};
     ///
     /// let quotes = holdings::ShareValues::new_quote();
     ///
     /// let sub_allocations = asset::SubAllocations::new().unwrap();
     ///
     /// let brokerage_current = holdings::ShareValues::new();
-    /// let brokerage_target = holdings::ShareValues::new_target(sub_allocations, 10000.0, 0.0, 0.0, 0.0, 0.0);
-    /// let purchase_sales = brokerage_current / quotes;
+    /// let brokerage_target = holdings::ShareValues::new_target(sub_allocations, dec!(10000.0), holdings::ShareValues::new(), holdings::ShareValues::new()).unwrap();
+    /// let purchase_sales = brokerage_current.clone() / quotes;
     ///
     /// let brokerage_account = holdings::AccountHoldings::new(brokerage_current, brokerage_target, purchase_sales);
     ///
     /// let mut vanguard_rebalance = holdings::VanguardRebalance::new();
-    /// vanguard_rebalance.add_account_holdings(brokerage_account, holdings::HoldingType::Brokerage);
+    /// let brokerage = Account::new(33333333, AccountKind::Taxable, Some("Brokerage"));
+    /// vanguard_rebalance.add_account_holdings(brokerage, brokerage_account);
+    /// assert!(vanguard_rebalance.to_string().starts_with("Brokerage (33333333):"));
     /// ```
-    pub fn add_account_holdings(&mut self, acct_holding: AccountHoldings, acct_type: HoldingType) {
-        match acct_type {
-            HoldingType::Brokerage => self.brokerage = Some(acct_holding),
-            HoldingType::TraditionalIra => self.traditional_ira = Some(acct_holding),
-            HoldingType::RothIra => self.roth_ira = Some(acct_holding),
-        }
+    pub fn add_account_holdings(&mut self, account: Account, acct_holding: AccountHoldings) {
+        self.accounts.push((account, acct_holding));
+    }
+
+    /// accounts returns each account along with its AccountHoldings struct
+    pub fn accounts(&self) -> &[(Account, AccountHoldings)] {
+        &self.accounts
     }
 
     pub fn add_retirement_target(&mut self, retirement_target: ShareValues) {
         self.retirement_target = Some(retirement_target);
     }
+
+    /// add_location_comparison adds how the optimized asset location of the retirement accounts
+    /// differs from the greedy placement, which is shown after the retirement target
+    pub fn add_location_comparison(&mut self, location_comparison: LocationComparison) {
+        self.location_comparison = Some(location_comparison);
+    }
+
+    /// add_notice adds a notice about the rebalance, such as a fund held outside of the accounts
+    /// it is best placed within, which is shown at the end of the report
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::holdings::VanguardRebalance;
+    ///
+    /// let mut vanguard_rebalance = VanguardRebalance::new();
+    /// vanguard_rebalance.add_notice("VTEB does not fit within the taxable accounts".to_string());
+    /// assert!(vanguard_rebalance.to_string().ends_with("VTEB does not fit within the taxable accounts"));
+    /// ```
+    pub fn add_notice(&mut self, notice: String) {
+        self.notices.push(notice);
+    }
 }
 
 impl Default for VanguardRebalance {
@@ -1320,17 +1944,24 @@ impl fmt::Display for VanguardRebalance {
                 retirement_target_values
             ))
         }
-        if let Some(traditional_ira_account) = &self.traditional_ira {
-            out_string.push_str(&format!(
-                "Traditional IRA:\n{}\n\n",
-                traditional_ira_account
-            ))
+        if let Some(location_comparison) = &self.location_comparison {
+            out_string.push_str(&format!("{}\n\n", location_comparison));
+        }
+        let mut trade_costs_option = None;
+        for (account, account_holdings) in &self.accounts {
+            out_string.push_str(&format!("{}:\n{}\n\n", account, account_holdings));
+            if let Some(trade_costs) = account_holdings.trade_costs() {
+                *trade_costs_option.get_or_insert(Decimal::ZERO) += trade_costs;
+            }
         }
-        if let Some(roth_ira_account) = &self.roth_ira {
-            out_string.push_str(&format!("Roth IRA:\n{}\n\n", roth_ira_account))
+        if let Some(trade_costs) = trade_costs_option {
+            out_string.push_str(&format!(
+                "Total estimated trade costs: ${:.2}\n",
+                trade_costs
+            ));
         }
-        if let Some(brokerage_account) = &self.brokerage {
-            out_string.push_str(&format!("Brokerage:\n{}\n\n", brokerage_account))
+        for notice in &self.notices {
+            out_string.push_str(&format!("{}\n", notice));
         }
         write!(f, "{}", out_string.trim_end_matches('\n'))
     }
@@ -1338,27 +1969,58 @@ impl fmt::Display for VanguardRebalance {
 
 #[derive(Clone, Debug)]
 pub struct Transaction {
-    _account_number: u32,
+    account_number: u32,
     trade_date: NaiveDate,
     symbol: StockSymbol,
-    shares: f32,
-    net_amount: f32,
+    shares: Decimal,
+    net_amount: Decimal,
     transaction_type: TransactionType,
 }
 
+impl Transaction {
+    /// account_number returns the number of the account the transaction was made within
+    pub fn account_number(&self) -> u32 {
+        self.account_number
+    }
+
+    /// trade_date returns the date the transaction was traded on
+    pub fn trade_date(&self) -> NaiveDate {
+        self.trade_date
+    }
+
+    /// symbol returns the fund the transaction was made within
+    pub fn symbol(&self) -> &StockSymbol {
+        &self.symbol
+    }
+
+    /// shares returns the number of shares bought or sold within the transaction
+    pub fn shares(&self) -> Decimal {
+        self.shares
+    }
+
+    /// is_purchase returns whether or not the transaction bought shares, which includes
+    /// reinvested dividends
+    pub fn is_purchase(&self) -> bool {
+        matches!(
+            self.transaction_type,
+            TransactionType::Buy | TransactionType::Reinvestment
+        )
+    }
+}
+
 #[derive(Clone, Eq, Hash, PartialEq, Debug)]
-enum TransactionType {
-    CONVERSIONOUT,
-    DIVIDEND,
-    REINVESTMENT,
-    ADVISORFEE,
-    BUY,
-    CONVERSIONIN,
-    SELL,
-    FUNDSRECEIVED,
-    SWEEPOUT,
-    SWEEPIN,
-    DISTRIBUTION,
+pub enum TransactionType {
+    ConversionOut,
+    Dividend,
+    Reinvestment,
+    AdvisorFee,
+    Buy,
+    ConversionIn,
+    Sell,
+    FundsReceived,
+    SweepOut,
+    SweepIn,
+    Distribution,
     Other(String),
 }
 
@@ -1371,21 +2033,21 @@ impl TransactionType {
     ///  use vapore::holdings::TransactionType;
     ///
     ///  let div = TransactionType::new("Dividend");
-    ///  assert_eq!(div, TransactionType::DIVIDEND);
+    ///  assert_eq!(div, TransactionType::Dividend);
     ///  ```
     pub fn new(transaction_type: &str) -> Self {
         match transaction_type {
-            "Conversion (outgoing)" => TransactionType::CONVERSIONOUT,
-            "Dividend" => TransactionType::DIVIDEND,
-            "Reinvestment" => TransactionType::REINVESTMENT,
-            "Advisor fee" => TransactionType::ADVISORFEE,
-            "Buy" => TransactionType::BUY,
-            "Conversion (incoming)" => TransactionType::CONVERSIONIN,
-            "Sell" => TransactionType::SELL,
-            "Funds Received" => TransactionType::FUNDSRECEIVED,
-            "Sweep out" => TransactionType::SWEEPOUT,
-            "Sweep in" => TransactionType::SWEEPIN,
-            "Distribution" => TransactionType::DISTRIBUTION,
+            "Conversion (outgoing)" => TransactionType::ConversionOut,
+            "Dividend" => TransactionType::Dividend,
+            "Reinvestment" => TransactionType::Reinvestment,
+            "Advisor fee" => TransactionType::AdvisorFee,
+            "Buy" => TransactionType::Buy,
+            "Conversion (incoming)" => TransactionType::ConversionIn,
+            "Sell" => TransactionType::Sell,
+            "Funds Received" => TransactionType::FundsReceived,
+            "Sweep out" => TransactionType::SweepOut,
+            "Sweep in" => TransactionType::SweepIn,
+            "Distribution" => TransactionType::Distribution,
             _ => {
                 eprintln!(
                     "{} is not supported within this algorithm\n",
@@ -1399,9 +2061,10 @@ impl TransactionType {
 
 /// parse_csv_download takes in the file path of the downloaded file from Vanguard and parses it
 /// into VanguardHoldings.  The VanguardHoldings is a struct which holds the values of what is
-/// contained within the vangaurd account along with quotes for each of the ETFs
+/// contained within each of the listed vangaurd accounts along with quotes for each of the ETFs
 pub async fn parse_csv_download(
     csv_path: &str,
+    listed_accounts: &Accounts,
     args: crate::arguments::Args,
 ) -> Result<VanguardHoldings> {
     let mut header = Vec::new();
@@ -1409,7 +2072,7 @@ pub async fn parse_csv_download(
     let csv_file = File::open(csv_path)?;
     let mut accounts: HashMap<u32, ShareValues> = HashMap::new();
     let mut quotes = ShareValues::new_quote();
-    let mut traditional_shares = ShareValues::new();
+    let mut traditional_shares: HashMap<u32, ShareValues> = HashMap::new();
 
     let mut holdings_row = true;
     let mut transactions = Vec::new();
@@ -1443,24 +2106,30 @@ pub async fn parse_csv_download(
                                         break;
                                     }
                                 }
-                                "Shares" => stock_info.add_shares(value.parse::<f32>()?),
-                                "Share Price" => stock_info.add_share_price(value.parse::<f32>()?),
-                                "Total Value" => stock_info.add_total_value(value.parse::<f32>()?),
+                                "Shares" => stock_info.add_shares(value.parse::<Decimal>()?),
+                                "Share Price" => {
+                                    stock_info.add_share_price(value.parse::<Decimal>()?)
+                                }
+                                "Total Value" => {
+                                    stock_info.add_total_value(value.parse::<Decimal>()?)
+                                }
                                 _ => continue,
                             }
                         }
                         if stock_info.finished() {
-                            let account_value = accounts
-                                .entry(stock_info.account_number)
-                                .or_insert_with(ShareValues::new);
+                            let account_value =
+                                accounts.entry(stock_info.account_number).or_default();
                             account_value
                                 .add_stockinfo_value(stock_info.clone(), AddType::HoldingValue);
                             quotes.add_stockinfo_value(stock_info.clone(), AddType::StockPrice);
-                            if let Some(traditional_acct_num) = args.trad_acct_option {
-                                if stock_info.account_number == traditional_acct_num {
-                                    traditional_shares
-                                        .add_stock_value(stock_info.symbol, stock_info.shares);
-                                }
+                            if listed_accounts
+                                .account(stock_info.account_number)
+                                .is_some_and(|account| account.kind == AccountKind::TaxDeferred)
+                            {
+                                traditional_shares
+                                    .entry(stock_info.account_number)
+                                    .or_default()
+                                    .add_stock_value(stock_info.symbol, stock_info.shares);
                             }
                         }
                     }
@@ -1477,12 +2146,12 @@ pub async fn parse_csv_download(
                         match head.as_str() {
                             "Account Number" => account_num_option = Some(value.parse::<u32>()?),
                             "Symbol" => symbol_option = Some(StockSymbol::new(value)),
-                            "Shares" => shares_option = Some(value.parse::<f32>()?),
+                            "Shares" => shares_option = Some(value.parse::<Decimal>()?),
                             "Trade Date" => {
                                 trade_date_option =
                                     Some(NaiveDate::parse_from_str(value, "%Y-%m-%d")?)
                             }
-                            "Net Amount" => net_amount_option = Some(value.parse::<f32>()?),
+                            "Net Amount" => net_amount_option = Some(value.parse::<Decimal>()?),
                             "Transaction Type" => {
                                 transaction_type_option = Some(TransactionType::new(value))
                             }
@@ -1490,25 +2159,19 @@ pub async fn parse_csv_download(
                         }
                     }
                     if let Some(account_number) = account_num_option {
-                        if let Some(trad_account_num) = args.trad_acct_option {
-                            if trad_account_num == account_number {
-                                if let Some(symbol) = symbol_option {
-                                    if let Some(shares) = shares_option {
-                                        if let Some(trade_date) = trade_date_option {
-                                            if let Some(net_amount) = net_amount_option {
-                                                if let Some(transaction_type) =
-                                                    transaction_type_option
-                                                {
-                                                    transactions.push(Transaction {
-                                                        _account_number: account_number,
-                                                        symbol,
-                                                        shares,
-                                                        trade_date,
-                                                        net_amount,
-                                                        transaction_type,
-                                                    })
-                                                }
-                                            }
+                        if let Some(symbol) = symbol_option {
+                            if let Some(shares) = shares_option {
+                                if let Some(trade_date) = trade_date_option {
+                                    if let Some(net_amount) = net_amount_option {
+                                        if let Some(transaction_type) = transaction_type_option {
+                                            transactions.push(Transaction {
+                                                account_number,
+                                                symbol,
+                                                shares,
+                                                trade_date,
+                                                net_amount,
+                                                transaction_type,
+                                            })
                                         }
                                     }
                                 }
@@ -1522,67 +2185,51 @@ pub async fn parse_csv_download(
 
     quotes.add_missing_quotes().await?;
 
-    let account_numbers = accounts.keys().cloned().collect::<Vec<u32>>();
+    let mut account_numbers = accounts.keys().cloned().collect::<Vec<u32>>();
+    account_numbers.sort();
 
-    // if the brokerage account is input through CLI arguments, pull the data from the accounts
-    // hashmap and place the information into a variable which will be input into the
-    // VanguardHoldings struct
-    let mut brokerage = None;
-    if let Some(brokerage_acct) = args.brok_acct_option {
-        if let Some(brokerage_holdings) = accounts.get(&brokerage_acct) {
-            brokerage = Some(*brokerage_holdings)
+    // pull the data of each account input through the CLI arguments or accounts file from the
+    // accounts hashmap and place the information into the VanguardHoldings struct
+    let mut vanguard_accounts = Vec::new();
+    for account in listed_accounts.accounts() {
+        if let Some(holdings) = accounts.get(&account.number) {
+            vanguard_accounts.push((account.clone(), holdings.clone()))
         } else {
             return Err(anyhow!("{account_type} account number not found within vanguard download file\nInput account: {input:?}\nPossible accounts: {all_accounts:?}\n",
-                               account_type= "Brokerage",
-                               input=brokerage_acct,
+                               account_type= account.kind,
+                               input=account.number,
                                all_accounts=account_numbers));
         }
     }
 
-    // if the traditional IRA account is input through CLI arguments, pull the data from the accounts
-    // hashmap and place the information into a variable which will be input into the
-    // VanguardHoldings struct
-    let mut traditional_ira = None;
-    if let Some(traditional_acct) = args.trad_acct_option {
-        if let Some(traditional_holdings) = accounts.get(&traditional_acct) {
-            traditional_ira = Some(*traditional_holdings)
-        } else {
-            return Err(anyhow!("{account_type} account number not found within vanguard download file\nInput account: {input:?}\nPossible accounts: {all_accounts:?}\n",
-                account_type= "Traditional IRA",
-                input= traditional_acct,
-                all_accounts= account_numbers)
-            );
-        }
-    }
-
-    // if the roth IRA account is input through CLI arguments, pull the data from the accounts
-    // hashmap and place the information into a variable which will be input into the
-    // VanguardHoldings struct
-    let mut roth_ira = None;
-    if let Some(roth_acct) = args.roth_acct_option {
-        if let Some(roth_holdings) = accounts.get(&roth_acct) {
-            roth_ira = Some(*roth_holdings)
-        } else {
-            return Err(anyhow!("{account_type:?} account number not found within vanguard download file\nInput account: {input:?}\nPossible accounts: {all_accounts:?}\n",
-                account_type= "Roth IRA",
-                input= roth_acct,
-                all_accounts= account_numbers)
-            );
+    // if the unrealized gains file is input through CLI arguments, attach the tax lots to each
+    // taxable account
+    let mut lots = HashMap::new();
+    if let Some(lots_path) = &args.lots_path_option {
+        let mut account_lots = TaxLots::from_file(lots_path)?;
+        for (account, holdings) in &vanguard_accounts {
+            if account.kind != AccountKind::Taxable {
+                continue;
+            }
+            let taxable_lots = account_lots.remove(&account.number).unwrap_or_default();
+            for symbol in taxable_lots.symbols() {
+                if holdings.stock_value(symbol.clone()).is_zero() {
+                    eprintln!(
+                        "{} has tax lots but is not held within the {} account\n",
+                        symbol, account
+                    );
+                }
+            }
+            lots.insert(account.number, taxable_lots);
         }
     }
 
-    let traditional_shares_option = if traditional_shares.value_added(0.0) {
-        Some(traditional_shares)
-    } else {
-        None
-    };
     Ok(VanguardHoldings {
-        brokerage,
-        traditional_ira,
-        roth_ira,
+        accounts: vanguard_accounts,
         quotes,
         transactions,
-        traditional_shares_option,
-        distributions: 0.0,
+        traditional_shares,
+        distributions: Decimal::ZERO,
+        lots,
     })
 }
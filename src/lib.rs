@@ -1,6 +1,16 @@
+pub mod account;
 pub mod arguments;
 pub mod asset;
 pub mod calc;
+pub mod costs;
+pub mod glide;
+pub mod harvest;
 pub mod holdings;
-#[macro_use]
-extern crate lazy_static;
+pub mod location;
+pub mod lots;
+pub mod money;
+pub mod outside;
+pub mod plan;
+pub mod policy;
+pub mod strategy;
+pub mod universe;
@@ -0,0 +1,541 @@
+use crate::{
+    account::{Account, AccountKind},
+    holdings::{ShareValues, StockSymbol},
+    lots::TaxRates,
+    money::round_dollars,
+    universe::{fund_universe, TaxCharacter},
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+use std::{collections::HashSet, fmt, fs};
+
+// DEFAULT_ASSET_LOCATION holds the built-in assumptions of the asset location optimizer
+const DEFAULT_ASSET_LOCATION: &str = include_str!("../asset_location.toml");
+
+// Value per dollar which keeps tax exempt funds out of the tax-advantaged accounts unless they do
+// not fit anywhere else
+const TAX_EXEMPT_PENALTY: Decimal = dec!(1000000);
+
+// Value per dollar which fills every account other than the last up to its size before the last
+// account, which takes the rest
+const FILL_BONUS: Decimal = dec!(1000000000);
+
+/// FundAssumption is the expected yearly total return of a fund and the part of it paid out as a
+/// yearly distribution, both in percent
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FundAssumption {
+    pub symbol: StockSymbol,
+    pub expected_return: Decimal,
+    #[serde(rename = "yield")]
+    pub distribution_yield: Decimal,
+}
+
+/// LocationPreference adds a bonus in percent to the yearly return of a fund within an account
+/// kind, such as the foreign tax credit of international stock funds within taxable accounts
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LocationPreference {
+    pub symbol: StockSymbol,
+    pub account: AccountKind,
+    pub bonus: Decimal,
+}
+
+/// LocationAssumptions holds what the asset location optimizer assumes about each fund, along
+/// with the number of years the retirement accounts are held
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LocationAssumptions {
+    pub horizon_years: u32,
+    #[serde(rename = "fund")]
+    pub funds: Vec<FundAssumption>,
+    #[serde(default, rename = "preference")]
+    pub preferences: Vec<LocationPreference>,
+}
+
+impl LocationAssumptions {
+    /// from_toml reads the asset location assumptions within the contents of a TOML file
+    ///
+    /// # Errors
+    ///
+    /// Errors when a fund is not a ranked fund within the fund universe, is listed more than once,
+    /// or has a yield above its expected return, or when a preference is for an education account
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::location::LocationAssumptions;
+    ///
+    /// let assumptions = LocationAssumptions::from_toml(
+    ///     r#"
+    ///     horizon_years = 10
+    ///
+    ///     [[fund]]
+    ///     symbol = "VV"
+    ///     expected_return = 7.0
+    ///     yield = 1.5
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(assumptions.horizon_years, 10);
+    ///
+    /// assert!(LocationAssumptions::from_toml(
+    ///     "horizon_years = 10\n[[fund]]\nsymbol = \"VLCAX\"\nexpected_return = 7.0\nyield = 1.5"
+    /// )
+    /// .is_err());
+    /// ```
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let assumptions: LocationAssumptions =
+            toml::from_str(contents).context("Unable to parse asset location assumptions")?;
+        assumptions.validate()?;
+        Ok(assumptions)
+    }
+
+    /// from_file reads an asset location assumptions TOML file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Asset location file not found: {}", path))?;
+        Self::from_toml(&contents)
+            .with_context(|| format!("Invalid asset location assumptions: {}", path))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let universe = fund_universe();
+        let mut symbols = HashSet::new();
+        for fund in &self.funds {
+            ensure!(
+                universe
+                    .fund(&fund.symbol)
+                    .is_some_and(|info| info.risk_rank.is_some()),
+                "{} is not a fund with a risk rank within the fund universe",
+                fund.symbol
+            );
+            ensure!(
+                symbols.insert(fund.symbol.clone()),
+                "{} has more than one asset location assumption",
+                fund.symbol
+            );
+            ensure!(
+                !fund.distribution_yield.is_sign_negative()
+                    && fund.distribution_yield <= fund.expected_return,
+                "Yield of {} needs to be between zero and its expected return",
+                fund.symbol
+            );
+        }
+        for preference in &self.preferences {
+            ensure!(
+                symbols.contains(&preference.symbol),
+                "Preference for {} does not have an asset location assumption",
+                preference.symbol
+            );
+            ensure!(
+                preference.account != AccountKind::Education,
+                "Preference for {} is for education accounts, which are not retirement accounts",
+                preference.symbol
+            );
+        }
+        Ok(())
+    }
+
+    /// after_tax_value returns what a dollar invested within the fund is worth after taxes at the
+    /// end of the horizon when held within the account kind.  Mutual fund share classes use the
+    /// assumptions of their ETF equivalent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     account::AccountKind, holdings::StockSymbol, location::LocationAssumptions,
+    ///     lots::TaxRates,
+    /// };
+    ///
+    /// let assumptions = LocationAssumptions::default();
+    /// let rates = TaxRates { short_term: dec!(24), long_term: dec!(15) };
+    /// let value = |symbol: &str, kind| {
+    ///     assumptions
+    ///         .after_tax_value(&StockSymbol::new(symbol), kind, &rates)
+    ///         .unwrap()
+    /// };
+    ///
+    /// // Stocks grow the most within a tax-free account
+    /// assert!(value("VV", AccountKind::TaxFree) > value("VV", AccountKind::TaxDeferred));
+    /// // Bond interest taxed each year within a taxable account costs more than stock dividends
+    /// let taxable_share = |symbol| value(symbol, AccountKind::Taxable) / value(symbol, AccountKind::TaxFree);
+    /// assert!(taxable_share("BND") < taxable_share("VV"));
+    /// // Municipal bond interest is not taxed
+    /// assert!(value("VTEB", AccountKind::Taxable) > value("VTEB", AccountKind::TaxDeferred));
+    /// ```
+    pub fn after_tax_value(
+        &self,
+        symbol: &StockSymbol,
+        kind: AccountKind,
+        rates: &TaxRates,
+    ) -> Result<Decimal> {
+        let model_symbol = fund_universe()
+            .model_fund(symbol)
+            .map(|fund| fund.symbol.clone())
+            .unwrap_or_else(|| symbol.clone());
+        let fund = self
+            .funds
+            .iter()
+            .find(|fund| fund.symbol == model_symbol)
+            .ok_or_else(|| anyhow!("{} does not have an asset location assumption", symbol))?;
+        let bonus = self
+            .preferences
+            .iter()
+            .filter(|preference| preference.symbol == model_symbol && preference.account == kind)
+            .map(|preference| preference.bonus)
+            .sum::<Decimal>();
+        let growth = (fund.expected_return + bonus) / Decimal::ONE_HUNDRED;
+        let distribution_yield = fund.distribution_yield / Decimal::ONE_HUNDRED;
+        let distribution_tax = if kind == AccountKind::Taxable {
+            match fund_universe().tax_character(&model_symbol) {
+                TaxCharacter::Qualified => rates.long_term,
+                TaxCharacter::Interest | TaxCharacter::NonQualified => rates.short_term,
+                TaxCharacter::TaxExempt => Decimal::ZERO,
+            }
+        } else {
+            Decimal::ZERO
+        } / Decimal::ONE_HUNDRED;
+
+        // Distributions taxed within a taxable account are reinvested after the tax, which adds to
+        // the cost basis
+        let mut value = Decimal::ONE;
+        let mut cost_basis = Decimal::ONE;
+        for _ in 0..self.horizon_years {
+            let distribution = value * distribution_yield;
+            let tax = distribution * distribution_tax;
+            value = (value * (Decimal::ONE + growth) - tax).round_dp(10);
+            cost_basis = (cost_basis + distribution - tax).round_dp(10);
+        }
+        let after_tax_value = match kind {
+            AccountKind::Taxable => {
+                value
+                    - (value - cost_basis).max(Decimal::ZERO) * rates.long_term
+                        / Decimal::ONE_HUNDRED
+            }
+            AccountKind::TaxDeferred => {
+                value * (Decimal::ONE - rates.short_term / Decimal::ONE_HUNDRED)
+            }
+            AccountKind::TaxFree | AccountKind::Hsa | AccountKind::Education => value,
+        };
+        Ok(after_tax_value.round_dp(10))
+    }
+
+    /// optimize places the target of the retirement accounts within the accounts so that the
+    /// after-tax value at the end of the horizon is the largest.  Each account is given with the
+    /// amount it fills, where every account is filled up to its amount other than the last
+    /// account, which takes the rest of the target.  Tax exempt funds are only placed within
+    /// tax-advantaged accounts when they do not fit within the taxable accounts.  The fill of each
+    /// account is returned in the same order as the accounts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     account::{Account, AccountKind},
+    ///     holdings::{ShareValues, StockSymbol},
+    ///     location::LocationAssumptions,
+    ///     lots::TaxRates,
+    /// };
+    ///
+    /// let roth = Account::new(22222222, AccountKind::TaxFree, Some("Roth IRA"));
+    /// let traditional = Account::new(11111111, AccountKind::TaxDeferred, Some("Traditional IRA"));
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("BND"), dec!(1000));
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(1000));
+    ///
+    /// let fills = LocationAssumptions::default()
+    ///     .optimize(
+    ///         &TaxRates { short_term: dec!(24), long_term: dec!(15) },
+    ///         &[(roth, dec!(1000)), (traditional, dec!(1000))],
+    ///         &target,
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(fills[0].stock_value(StockSymbol::new("VV")), dec!(1000));
+    /// assert_eq!(fills[1].stock_value(StockSymbol::new("BND")), dec!(1000));
+    /// ```
+    pub fn optimize(
+        &self,
+        rates: &TaxRates,
+        accounts: &[(Account, Decimal)],
+        target: &ShareValues,
+    ) -> Result<Vec<ShareValues>> {
+        let symbols = fund_universe()
+            .high_to_low_risk()
+            .into_iter()
+            .filter(|symbol| target.stock_value(symbol.clone()) > Decimal::ZERO)
+            .collect::<Vec<StockSymbol>>();
+        let demands = symbols
+            .iter()
+            .map(|symbol| target.stock_value(symbol.clone()))
+            .collect::<Vec<Decimal>>();
+        let total_demand = demands.iter().sum::<Decimal>();
+        let last_index = accounts.len().saturating_sub(1);
+        let capacities = accounts
+            .iter()
+            .enumerate()
+            .map(|(index, (_, capacity))| {
+                if index == last_index {
+                    total_demand
+                } else {
+                    *capacity
+                }
+            })
+            .collect::<Vec<Decimal>>();
+        let mut values = Vec::new();
+        for symbol in &symbols {
+            let tax_exempt = fund_universe().tax_character(symbol) == TaxCharacter::TaxExempt;
+            let mut symbol_values = Vec::new();
+            for (index, (account, _)) in accounts.iter().enumerate() {
+                let mut value = self.after_tax_value(symbol, account.kind, rates)?;
+                if tax_exempt && account.kind != AccountKind::Taxable {
+                    value -= TAX_EXEMPT_PENALTY;
+                }
+                if index != last_index {
+                    value += FILL_BONUS;
+                }
+                symbol_values.push(value);
+            }
+            values.push(symbol_values);
+        }
+        let placement = max_value_placement(&demands, &capacities, &values);
+        let mut fills = vec![ShareValues::new(); accounts.len()];
+        for (symbol, account_values) in symbols.iter().zip(placement) {
+            for (fill, value) in fills.iter_mut().zip(account_values) {
+                if !value.is_zero() {
+                    fill.add_stock_value(symbol.clone(), value);
+                }
+            }
+        }
+        for ((account, capacity), fill) in accounts.iter().zip(&fills).take(last_index) {
+            ensure!(
+                fill.total_value() == *capacity,
+                "Unable to fill {} with the target of the retirement accounts",
+                account
+            );
+        }
+        Ok(fills)
+    }
+
+    /// compare returns the difference between two placements of the retirement accounts, such as
+    /// the greedy placement and the optimized placement, along with their after-tax values
+    pub fn compare(
+        &self,
+        rates: &TaxRates,
+        accounts: &[Account],
+        greedy: &[ShareValues],
+        optimized: &[ShareValues],
+    ) -> Result<LocationComparison> {
+        let mut differences = Vec::new();
+        let mut greedy_value = Decimal::ZERO;
+        let mut optimized_value = Decimal::ZERO;
+        for ((account, greedy_fill), optimized_fill) in accounts.iter().zip(greedy).zip(optimized) {
+            for symbol in fund_universe().high_to_low_risk() {
+                let greedy_amount = greedy_fill.stock_value(symbol.clone());
+                let optimized_amount = optimized_fill.stock_value(symbol.clone());
+                if greedy_amount.is_zero() && optimized_amount.is_zero() {
+                    continue;
+                }
+                let value = self.after_tax_value(&symbol, account.kind, rates)?;
+                greedy_value += greedy_amount * value;
+                optimized_value += optimized_amount * value;
+                if greedy_amount != optimized_amount {
+                    differences.push(LocationDifference {
+                        account: account.clone(),
+                        symbol,
+                        greedy: greedy_amount,
+                        optimized: optimized_amount,
+                    });
+                }
+            }
+        }
+        Ok(LocationComparison {
+            horizon_years: self.horizon_years,
+            differences,
+            greedy_value: round_dollars(greedy_value),
+            optimized_value: round_dollars(optimized_value),
+        })
+    }
+}
+
+impl Default for LocationAssumptions {
+    fn default() -> Self {
+        Self::from_toml(DEFAULT_ASSET_LOCATION)
+            .expect("Built-in asset location assumptions are invalid")
+    }
+}
+
+// max_value_placement places the demand of each fund within the capacity of each account so that
+// the sum of the amounts times their value is the largest.  This is solved as a minimum cost flow
+// from the funds to the accounts, where the path with the largest value per dollar is filled until
+// no demand is left.  The amounts placed are returned for each fund and account.
+fn max_value_placement(
+    demands: &[Decimal],
+    capacities: &[Decimal],
+    values: &[Vec<Decimal>],
+) -> Vec<Vec<Decimal>> {
+    // Nodes are the source, each fund, each account and then the sink
+    let fund_count = demands.len();
+    let node_count = fund_count + capacities.len() + 2;
+    let sink = node_count - 1;
+    let mut edges = Vec::new();
+    let add_edge = |edges: &mut Vec<FlowEdge>, from, to, capacity, cost| {
+        edges.push(FlowEdge {
+            from,
+            to,
+            capacity,
+            cost,
+        });
+        edges.push(FlowEdge {
+            from: to,
+            to: from,
+            capacity: Decimal::ZERO,
+            cost: -cost,
+        });
+    };
+    for (fund, demand) in demands.iter().enumerate() {
+        add_edge(&mut edges, 0, fund + 1, *demand, Decimal::ZERO);
+        for (account, value) in values[fund].iter().enumerate() {
+            add_edge(
+                &mut edges,
+                fund + 1,
+                fund_count + account + 1,
+                *demand,
+                -*value,
+            );
+        }
+    }
+    for (account, capacity) in capacities.iter().enumerate() {
+        add_edge(
+            &mut edges,
+            fund_count + account + 1,
+            sink,
+            *capacity,
+            Decimal::ZERO,
+        );
+    }
+
+    // The residual network never holds a negative cycle, so the cheapest path is found with
+    // Bellman-Ford
+    loop {
+        let mut distances: Vec<Option<Decimal>> = vec![None; node_count];
+        let mut previous_edges = vec![None; node_count];
+        distances[0] = Some(Decimal::ZERO);
+        for _ in 1..node_count {
+            let mut changed = false;
+            for (index, edge) in edges.iter().enumerate() {
+                if edge.capacity <= Decimal::ZERO {
+                    continue;
+                }
+                if let Some(distance) = distances[edge.from] {
+                    let new_distance = distance + edge.cost;
+                    let shorter = match distances[edge.to] {
+                        Some(to_distance) => new_distance < to_distance,
+                        None => true,
+                    };
+                    if shorter {
+                        distances[edge.to] = Some(new_distance);
+                        previous_edges[edge.to] = Some(index);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        if distances[sink].is_none() {
+            break;
+        }
+        let mut path = Vec::new();
+        let mut node = sink;
+        while let Some(index) = previous_edges[node] {
+            path.push(index);
+            node = edges[index].from;
+        }
+        let amount = path
+            .iter()
+            .map(|index| edges[*index].capacity)
+            .min()
+            .unwrap_or_default();
+        for index in path {
+            edges[index].capacity -= amount;
+            // Each edge is followed by its reverse edge
+            edges[index ^ 1].capacity += amount;
+        }
+    }
+
+    let mut placement = vec![vec![Decimal::ZERO; capacities.len()]; fund_count];
+    for edge in edges.iter().step_by(2) {
+        if (1..=fund_count).contains(&edge.from) && edge.to != sink {
+            let fund = edge.from - 1;
+            let account = edge.to - fund_count - 1;
+            placement[fund][account] = demands[fund] - edge.capacity;
+        }
+    }
+    placement
+}
+
+struct FlowEdge {
+    from: usize,
+    to: usize,
+    capacity: Decimal,
+    cost: Decimal,
+}
+
+/// LocationDifference is a fund within an account whose amount differs between the placements
+#[derive(Clone, Debug)]
+pub struct LocationDifference {
+    pub account: Account,
+    pub symbol: StockSymbol,
+    pub greedy: Decimal,
+    pub optimized: Decimal,
+}
+
+/// LocationComparison holds how the optimized placement of the retirement accounts differs from
+/// the greedy placement, along with the after-tax value of each at the end of the horizon
+#[derive(Clone, Debug)]
+pub struct LocationComparison {
+    pub horizon_years: u32,
+    pub differences: Vec<LocationDifference>,
+    pub greedy_value: Decimal,
+    pub optimized_value: Decimal,
+}
+
+impl fmt::Display for LocationComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rows = String::new();
+        for difference in &self.differences {
+            rows.push_str(&format!(
+                "{:<28}{:<9}${:<15.2}${:<15.2}${:.2}\n",
+                difference.account.to_string(),
+                difference.symbol.to_string(),
+                difference.greedy,
+                difference.optimized,
+                difference.optimized - difference.greedy
+            ));
+        }
+        if rows.is_empty() {
+            rows.push_str("The optimized placement is the same as the greedy placement\n");
+        }
+        write!(
+            f,
+            "Asset location of the retirement accounts:\n\
+            Account                     Symbol   Greedy          Optimized       Change\n\
+            ---------------------------------------------------------------------------------\n\
+            {}\
+            ---------------------------------------------------------------------------------\n\
+            After-tax value in {} years: ${:.2} greedy, ${:.2} optimized (${:.2} more)",
+            rows,
+            self.horizon_years,
+            self.greedy_value,
+            self.optimized_value,
+            self.optimized_value - self.greedy_value
+        )
+    }
+}
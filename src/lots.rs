@@ -0,0 +1,578 @@
+use crate::{holdings::StockSymbol, money::round_dollars};
+use anyhow::{anyhow, ensure, Context, Result};
+use chrono::{Months, NaiveDate};
+use rust_decimal::Decimal;
+use std::{collections::HashMap, fmt, fs, str::FromStr};
+
+/// HoldingPeriod is whether gains on a lot are taxed as short term or long term.  A lot is long
+/// term when it has been held for more than one year.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HoldingPeriod {
+    ShortTerm,
+    LongTerm,
+}
+
+impl fmt::Display for HoldingPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HoldingPeriod::ShortTerm => write!(f, "Short term"),
+            HoldingPeriod::LongTerm => write!(f, "Long term"),
+        }
+    }
+}
+
+/// LotSelection is the order in which the lots of a fund are sold.
+/// - Hifo sells the lots with the highest cost per share first
+/// - LossFirst sells the lots at a loss first, from the largest loss per share, followed by the
+///   long term lots and then the short term lots, each from the smallest gain per share
+/// - LongTermFirst sells the long term lots before the short term lots, each from the highest cost
+///   per share
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LotSelection {
+    #[default]
+    Hifo,
+    LossFirst,
+    LongTermFirst,
+}
+
+impl FromStr for LotSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(selection: &str) -> Result<Self> {
+        match selection {
+            "hifo" => Ok(LotSelection::Hifo),
+            "loss_first" => Ok(LotSelection::LossFirst),
+            "long_term_first" => Ok(LotSelection::LongTermFirst),
+            _ => Err(anyhow!(
+                "Unknown lot selection {}.  Selections are hifo, loss_first and long_term_first",
+                selection
+            )),
+        }
+    }
+}
+
+/// TaxRates holds the percent that short and long term gains are taxed at
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TaxRates {
+    pub short_term: Decimal,
+    pub long_term: Decimal,
+}
+
+impl TaxRates {
+    /// tax returns the tax on the gain of the holding period, which is negative for a loss
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::lots::{HoldingPeriod, TaxRates};
+    ///
+    /// let rates = TaxRates { short_term: dec!(24), long_term: dec!(15) };
+    /// assert_eq!(rates.tax(dec!(1000), HoldingPeriod::ShortTerm), dec!(240));
+    /// assert_eq!(rates.tax(dec!(-1000), HoldingPeriod::LongTerm), dec!(-150));
+    /// ```
+    pub fn tax(&self, gain: Decimal, holding_period: HoldingPeriod) -> Decimal {
+        let rate = match holding_period {
+            HoldingPeriod::ShortTerm => self.short_term,
+            HoldingPeriod::LongTerm => self.long_term,
+        };
+        gain * rate / Decimal::ONE_HUNDRED
+    }
+}
+
+/// TaxLot is a single purchase of a fund within a taxable account along with its cost basis
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaxLot {
+    pub symbol: StockSymbol,
+    pub acquired: NaiveDate,
+    pub shares: Decimal,
+    pub cost_basis: Decimal,
+}
+
+impl TaxLot {
+    /// holding_period returns whether the lot is short or long term on the given date.  The lot
+    /// becomes long term the day after its one year anniversary.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{holdings::StockSymbol, lots::{HoldingPeriod, TaxLot}};
+    ///
+    /// let lot = TaxLot {
+    ///     symbol: StockSymbol::new("VV"),
+    ///     acquired: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+    ///     shares: dec!(10.0),
+    ///     cost_basis: dec!(2000.00),
+    /// };
+    ///
+    /// let anniversary = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+    /// assert_eq!(lot.holding_period(anniversary), HoldingPeriod::ShortTerm);
+    /// assert_eq!(lot.holding_period(anniversary.succ_opt().unwrap()), HoldingPeriod::LongTerm);
+    /// ```
+    pub fn holding_period(&self, as_of: NaiveDate) -> HoldingPeriod {
+        let anniversary = self
+            .acquired
+            .checked_add_months(Months::new(12))
+            .unwrap_or(NaiveDate::MAX);
+        if as_of > anniversary {
+            HoldingPeriod::LongTerm
+        } else {
+            HoldingPeriod::ShortTerm
+        }
+    }
+
+    /// cost_per_share returns the cost basis of a single share within the lot
+    pub fn cost_per_share(&self) -> Decimal {
+        self.cost_basis
+            .checked_div(self.shares)
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// unrealized_gain returns the gain, or loss when negative, if the lot is sold at the price
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{holdings::StockSymbol, lots::TaxLot};
+    ///
+    /// let lot = TaxLot {
+    ///     symbol: StockSymbol::new("VV"),
+    ///     acquired: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+    ///     shares: dec!(10.0),
+    ///     cost_basis: dec!(2000.00),
+    /// };
+    ///
+    /// assert_eq!(lot.unrealized_gain(dec!(250.00)), dec!(500.00));
+    /// assert_eq!(lot.unrealized_gain(dec!(150.00)), dec!(-500.00));
+    /// ```
+    pub fn unrealized_gain(&self, price: Decimal) -> Decimal {
+        self.shares * price - self.cost_basis
+    }
+}
+
+/// LotSale is the sale of shares from a single tax lot
+#[derive(Clone, Debug, PartialEq)]
+pub struct LotSale {
+    pub symbol: StockSymbol,
+    pub acquired: NaiveDate,
+    pub shares: Decimal,
+    pub cost_basis: Decimal,
+    pub proceeds: Decimal,
+    pub holding_period: HoldingPeriod,
+}
+
+impl LotSale {
+    /// gain returns the realized gain of the sale, or loss when negative
+    pub fn gain(&self) -> Decimal {
+        self.proceeds - self.cost_basis
+    }
+}
+
+/// LotSales holds the lot sales of an account's orders along with the tax rates used to estimate
+/// the tax on their gains
+#[derive(Clone, Debug, PartialEq)]
+pub struct LotSales {
+    pub sales: Vec<LotSale>,
+    pub rates: TaxRates,
+}
+
+impl LotSales {
+    /// gain returns the realized gain, or loss when negative, of the sales with the holding period
+    pub fn gain(&self, holding_period: HoldingPeriod) -> Decimal {
+        self.sales
+            .iter()
+            .filter(|sale| sale.holding_period == holding_period)
+            .map(|sale| sale.gain())
+            .sum()
+    }
+
+    /// tax returns the estimated tax on the realized gains of the sales, where losses offset gains
+    /// and a net loss is not taxed
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     holdings::StockSymbol,
+    ///     lots::{HoldingPeriod, LotSale, LotSales, TaxRates},
+    /// };
+    ///
+    /// let sale = |proceeds, holding_period| LotSale {
+    ///     symbol: StockSymbol::new("VV"),
+    ///     acquired: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+    ///     shares: dec!(1.0),
+    ///     cost_basis: dec!(1000.00),
+    ///     proceeds,
+    ///     holding_period,
+    /// };
+    /// let lot_sales = LotSales {
+    ///     sales: vec![
+    ///         sale(dec!(2000.00), HoldingPeriod::LongTerm),
+    ///         sale(dec!(800.00), HoldingPeriod::ShortTerm),
+    ///     ],
+    ///     rates: TaxRates { short_term: dec!(24), long_term: dec!(15) },
+    /// };
+    /// assert_eq!(lot_sales.gain(HoldingPeriod::LongTerm), dec!(1000.00));
+    /// // The short term loss offsets the long term gain
+    /// assert_eq!(lot_sales.tax(), dec!(120.00));
+    /// ```
+    pub fn tax(&self) -> Decimal {
+        let short_term = self.gain(HoldingPeriod::ShortTerm);
+        let long_term = self.gain(HoldingPeriod::LongTerm);
+        // A net loss of one holding period offsets the net gain of the other
+        let tax = if short_term < Decimal::ZERO && long_term > Decimal::ZERO {
+            self.rates.tax(
+                (long_term + short_term).max(Decimal::ZERO),
+                HoldingPeriod::LongTerm,
+            )
+        } else if long_term < Decimal::ZERO && short_term > Decimal::ZERO {
+            self.rates.tax(
+                (short_term + long_term).max(Decimal::ZERO),
+                HoldingPeriod::ShortTerm,
+            )
+        } else {
+            self.rates
+                .tax(short_term.max(Decimal::ZERO), HoldingPeriod::ShortTerm)
+                + self
+                    .rates
+                    .tax(long_term.max(Decimal::ZERO), HoldingPeriod::LongTerm)
+        };
+        round_dollars(tax)
+    }
+}
+
+impl fmt::Display for LotSales {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rows = String::new();
+        for sale in &self.sales {
+            rows.push_str(&format!(
+                "{:<9}{:<12}{:<10.4}${:<11.2}${:<11.2}${:<11.2}{}\n",
+                sale.symbol.to_string(),
+                sale.acquired.format("%Y-%m-%d").to_string(),
+                sale.shares,
+                sale.cost_basis,
+                sale.proceeds,
+                sale.gain(),
+                sale.holding_period
+            ));
+        }
+        write!(
+            f,
+            "Lot sales:\n\
+            Symbol   Acquired    Shares    Cost basis  Proceeds    Gain        Term\n\
+            ------------------------------------------------------------------------------\n\
+            {}\
+            ------------------------------------------------------------------------------\n\
+            Estimated short term gain   ${:.2}\n\
+            Estimated long term gain    ${:.2}\n\
+            Estimated tax               ${:.2}",
+            rows,
+            self.gain(HoldingPeriod::ShortTerm),
+            self.gain(HoldingPeriod::LongTerm),
+            self.tax()
+        )
+    }
+}
+
+/// TaxLots holds the tax lots of each fund within a taxable account
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TaxLots {
+    lots: HashMap<StockSymbol, Vec<TaxLot>>,
+}
+
+impl TaxLots {
+    /// new creates an empty TaxLots struct
+    pub fn new() -> Self {
+        TaxLots {
+            lots: HashMap::new(),
+        }
+    }
+
+    /// add_lot adds a tax lot, where the lots of each fund are kept in order of acquisition
+    pub fn add_lot(&mut self, lot: TaxLot) {
+        let lots = self.lots.entry(lot.symbol.clone()).or_default();
+        lots.push(lot);
+        lots.sort_by_key(|lot| lot.acquired);
+    }
+
+    /// lots returns the tax lots of the fund from the oldest to the newest
+    pub fn lots(&self, symbol: &StockSymbol) -> &[TaxLot] {
+        self.lots.get(symbol).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// symbols returns the symbols of all funds with tax lots
+    pub fn symbols(&self) -> Vec<StockSymbol> {
+        let mut symbols = self.lots.keys().cloned().collect::<Vec<StockSymbol>>();
+        symbols.sort_by_key(|symbol| symbol.to_string());
+        symbols
+    }
+
+    /// shares returns the number of shares across all lots of the fund
+    pub fn shares(&self, symbol: &StockSymbol) -> Decimal {
+        self.lots(symbol).iter().map(|lot| lot.shares).sum()
+    }
+
+    /// cost_basis returns the cost basis across all lots of the fund
+    pub fn cost_basis(&self, symbol: &StockSymbol) -> Decimal {
+        self.lots(symbol).iter().map(|lot| lot.cost_basis).sum()
+    }
+
+    /// unrealized_gain returns the gain, or loss when negative, across all lots of the fund for the
+    /// holding period if they are sold at the price
+    pub fn unrealized_gain(
+        &self,
+        symbol: &StockSymbol,
+        price: Decimal,
+        holding_period: HoldingPeriod,
+        as_of: NaiveDate,
+    ) -> Decimal {
+        self.lots(symbol)
+            .iter()
+            .filter(|lot| lot.holding_period(as_of) == holding_period)
+            .map(|lot| lot.unrealized_gain(price))
+            .sum()
+    }
+
+    /// sell returns the sales of the lots of the fund which sell the number of shares at the
+    /// price, with the lots picked in the order of the lot selection.  Shares beyond the lots of
+    /// the fund are left out of the sales, since their cost basis is not known.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     holdings::StockSymbol,
+    ///     lots::{HoldingPeriod, LotSelection, TaxLot, TaxLots},
+    /// };
+    ///
+    /// let vwo = StockSymbol::new("VWO");
+    /// let mut lots = TaxLots::new();
+    /// lots.add_lot(TaxLot {
+    ///     symbol: vwo.clone(),
+    ///     acquired: NaiveDate::from_ymd_opt(2022, 3, 15).unwrap(),
+    ///     shares: dec!(30.0),
+    ///     cost_basis: dec!(1140.00),
+    /// });
+    /// lots.add_lot(TaxLot {
+    ///     symbol: vwo.clone(),
+    ///     acquired: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+    ///     shares: dec!(20.0),
+    ///     cost_basis: dec!(880.00),
+    /// });
+    /// let as_of = NaiveDate::from_ymd_opt(2024, 9, 1).unwrap();
+    ///
+    /// let hifo = lots.sell(&vwo, dec!(25.0), dec!(41.00), LotSelection::Hifo, as_of);
+    /// assert_eq!(hifo.len(), 2);
+    /// assert_eq!(hifo[0].gain(), dec!(-60.00));
+    /// assert_eq!(hifo[1].shares, dec!(5.0));
+    ///
+    /// let long_term = lots.sell(&vwo, dec!(25.0), dec!(41.00), LotSelection::LongTermFirst, as_of);
+    /// assert_eq!(long_term.len(), 1);
+    /// assert_eq!(long_term[0].holding_period, HoldingPeriod::LongTerm);
+    /// assert_eq!(long_term[0].gain(), dec!(75.00));
+    /// ```
+    pub fn sell(
+        &self,
+        symbol: &StockSymbol,
+        shares: Decimal,
+        price: Decimal,
+        selection: LotSelection,
+        as_of: NaiveDate,
+    ) -> Vec<LotSale> {
+        let mut lots = self.lots(symbol).iter().collect::<Vec<&TaxLot>>();
+        let cost_first =
+            |lot_a: &&TaxLot, lot_b: &&TaxLot| lot_b.cost_per_share().cmp(&lot_a.cost_per_share());
+        match selection {
+            LotSelection::Hifo => lots.sort_by(cost_first),
+            LotSelection::LossFirst => lots.sort_by(|lot_a, lot_b| {
+                let rank = |lot: &TaxLot| {
+                    if lot.cost_per_share() > price {
+                        0
+                    } else if lot.holding_period(as_of) == HoldingPeriod::LongTerm {
+                        1
+                    } else {
+                        2
+                    }
+                };
+                rank(lot_a)
+                    .cmp(&rank(lot_b))
+                    .then_with(|| cost_first(lot_a, lot_b))
+            }),
+            LotSelection::LongTermFirst => lots.sort_by(|lot_a, lot_b| {
+                let short_term =
+                    |lot: &TaxLot| lot.holding_period(as_of) == HoldingPeriod::ShortTerm;
+                short_term(lot_a)
+                    .cmp(&short_term(lot_b))
+                    .then_with(|| cost_first(lot_a, lot_b))
+            }),
+        }
+
+        let mut remaining = shares;
+        let mut sales = Vec::new();
+        for lot in lots {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let sold = remaining.min(lot.shares);
+            remaining -= sold;
+            sales.push(LotSale {
+                symbol: symbol.clone(),
+                acquired: lot.acquired,
+                shares: sold,
+                cost_basis: round_dollars(lot.cost_per_share() * sold),
+                proceeds: round_dollars(price * sold),
+                holding_period: lot.holding_period(as_of),
+            });
+        }
+        sales
+    }
+
+    /// value_without_short_term_gain returns the value of the lots of the fund which can be sold
+    /// at the price without realizing a short term gain, which are the long term lots and the lots
+    /// at a loss
+    pub fn value_without_short_term_gain(
+        &self,
+        symbol: &StockSymbol,
+        price: Decimal,
+        as_of: NaiveDate,
+    ) -> Decimal {
+        self.lots(symbol)
+            .iter()
+            .filter(|lot| {
+                lot.holding_period(as_of) == HoldingPeriod::LongTerm
+                    || lot.unrealized_gain(price) <= Decimal::ZERO
+            })
+            .map(|lot| lot.shares * price)
+            .sum()
+    }
+
+    /// parse_csv parses the tax lots within Vanguard's unrealized gains download and returns them
+    /// keyed by account number.  Columns are found by their header name, where the "Account
+    /// Number", "Symbol", "Date Acquired", "Quantity" and "Total Cost" columns are needed.  Values
+    /// may be quoted, dollar signs and thousands separators are ignored, and dates may be either
+    /// MM/DD/YYYY or YYYY-MM-DD.  Rows without a symbol, such as totals, are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{holdings::StockSymbol, lots::TaxLots};
+    ///
+    /// let accounts = TaxLots::parse_csv(
+    ///     "Account Number,Investment Name,Symbol,Date Acquired,Quantity,Cost Per Share,Total Cost,Market Value\n\
+    ///      33333333,VANGUARD EMERGING,VWO,03/15/2023,30.0,$38.00,$1140.00,$1230.00\n\
+    ///      33333333,VANGUARD EMERGING,VWO,2024-06-01,20.0,$44.00,$880.00,$820.00\n\
+    ///      33333333,Total,,,,,$2020.00,$2050.00\n",
+    /// )
+    /// .unwrap();
+    ///
+    /// let lots = &accounts[&33333333];
+    /// assert_eq!(lots.lots(&StockSymbol::new("VWO")).len(), 2);
+    /// assert_eq!(lots.shares(&StockSymbol::new("VWO")), dec!(50.0));
+    /// assert_eq!(lots.cost_basis(&StockSymbol::new("VWO")), dec!(2020.00));
+    ///
+    /// // Quoted amounts may contain thousands separators
+    /// let accounts = TaxLots::parse_csv(
+    ///     "Account Number,Investment Name,Symbol,Date Acquired,Quantity,Cost Per Share,Total Cost,Market Value\n\
+    ///      33333333,\"VANGUARD EMERGING, ETF\",VWO,03/15/2023,30.0,$38.00,\"$1,140.00\",\"$1,230.00\"\n",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(accounts[&33333333].cost_basis(&StockSymbol::new("VWO")), dec!(1140.00));
+    /// ```
+    pub fn parse_csv(contents: &str) -> Result<HashMap<u32, TaxLots>> {
+        let mut header = Vec::new();
+        let mut accounts: HashMap<u32, TaxLots> = HashMap::new();
+        for (row_index, row) in contents.lines().enumerate() {
+            if !row.contains(',') {
+                continue;
+            }
+            let row_split = split_row(row);
+            if header.is_empty() {
+                if row_split.iter().any(|head| head == "Date Acquired") {
+                    header = row_split;
+                }
+                continue;
+            }
+            let column = |name: &str| -> Result<&str> {
+                let index = header.iter().position(|head| head == name).ok_or_else(|| {
+                    anyhow!("Unrealized gains file is missing the {} column", name)
+                })?;
+                Ok(row_split.get(index).map(String::as_str).unwrap_or_default())
+            };
+            let symbol = column("Symbol")?;
+            if symbol.is_empty() {
+                continue;
+            }
+            let row_context = || format!("Invalid tax lot on line {}: {}", row_index + 1, row);
+            let account_number = column("Account Number")?
+                .parse::<u32>()
+                .with_context(row_context)?;
+            let acquired = parse_date(column("Date Acquired")?).with_context(row_context)?;
+            let shares = column("Quantity")?
+                .parse::<Decimal>()
+                .with_context(row_context)?;
+            let cost_basis = column("Total Cost")?
+                .parse::<Decimal>()
+                .with_context(row_context)?;
+            ensure!(
+                shares > Decimal::ZERO,
+                "Tax lot on line {} does not have a positive quantity",
+                row_index + 1
+            );
+            accounts.entry(account_number).or_default().add_lot(TaxLot {
+                symbol: StockSymbol::new(symbol),
+                acquired,
+                shares,
+                cost_basis,
+            });
+        }
+        ensure!(
+            !header.is_empty(),
+            "Unrealized gains file does not contain a header with a Date Acquired column"
+        );
+        Ok(accounts)
+    }
+
+    /// from_file reads Vanguard's unrealized gains download and returns the tax lots keyed by
+    /// account number
+    pub fn from_file(path: &str) -> Result<HashMap<u32, TaxLots>> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Unrealized gains file not found: {}", path))?;
+        Self::parse_csv(&contents)
+            .with_context(|| format!("Invalid unrealized gains file: {}", path))
+    }
+}
+
+// split_row splits a row of the unrealized gains download on the commas outside of quotes, then
+// removes the quotes, dollar signs and thousands separators from each value
+fn split_row(row: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut value = String::new();
+    let mut quoted = false;
+    for character in row.chars() {
+        match character {
+            '"' => quoted = !quoted,
+            ',' if !quoted => values.push(std::mem::take(&mut value)),
+            _ => value.push(character),
+        }
+    }
+    values.push(value);
+    values
+        .into_iter()
+        .map(|value| value.trim().replace(['$', ','], ""))
+        .collect()
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%m/%d/%Y")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .with_context(|| format!("Unable to parse date acquired: {}", date))
+}
@@ -1,43 +1,147 @@
 use anyhow::{Context, Result};
 use apca::{api::v2::account, ApiInfo, Client};
-use chrono::Local;
+use chrono::{Datelike, Local};
+use rust_decimal::Decimal;
 use std::{fs::File, io::Write};
-use vapore::arguments;
+use vapore::{
+    account::{AccountKind, Owner},
+    arguments, harvest,
+    holdings::StockSymbol,
+    money,
+    outside::{OutsideHolding, OutsideHoldings},
+    strategy, universe,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let mut args = arguments::Args::new();
+    let args = arguments::Args::new();
+    let mut fund_universe = match &args.fund_universe_path_option {
+        Some(fund_universe_path) => universe::FundUniverse::from_file(fund_universe_path)?,
+        None => universe::FundUniverse::default(),
+    };
+    if let Some(money_market) = &args.keep_money_market_option {
+        fund_universe.keep_money_market(money_market)?;
+    }
+    universe::set_fund_universe(fund_universe)?;
+    let mut outside_holdings = match &args.outside_holdings_path_option {
+        Some(outside_holdings_path) => OutsideHoldings::from_file(outside_holdings_path)?,
+        None => OutsideHoldings::new(),
+    };
     let key_id = std::env::var("APCA_API_KEY_ID").unwrap_or_else(|_| String::new());
     let key = std::env::var("APCA_API_SECRET_KEY").unwrap_or_else(|_| String::new());
     if !key_id.is_empty() && !key.is_empty() {
         let api_info = ApiInfo::from_parts("https://api.alpaca.markets/", &key_id, &key)
             .context("Failed to retrieve Alpaca Environment info")?;
         let client = Client::new(api_info);
-        let alpaca_equity = client
-            .issue::<account::Get>(&())
-            .await?
-            .equity
-            .to_f64()
-            .unwrap() as f32;
-        args.brokerage_us_stock_add += alpaca_equity;
+        let alpaca_equity = money::dollars_from_f64(
+            client
+                .issue::<account::Get>(&())
+                .await?
+                .equity
+                .to_f64()
+                .context("Unable to convert Alpaca equity into a dollar value")?,
+        )?;
+        // Alpaca equity is treated as total US stock
+        outside_holdings
+            .add_holding(OutsideHolding {
+                name: "Alpaca".to_string(),
+                symbol: Some(StockSymbol::new("VTI")),
+                sub_class: None,
+                composition: None,
+                value: alpaca_equity,
+                account: AccountKind::Taxable,
+                owner: None,
+            })
+            .context(
+                "Alpaca equity is classified as VTI, which needs to be within the fund universe",
+            )?;
     }
+    let accounts = args.accounts()?;
     let mut vanguard_holdings =
-        vapore::holdings::parse_csv_download(&args.csv_path, args.clone()).await?;
+        vapore::holdings::parse_csv_download(&args.csv_path, &accounts, args.clone()).await?;
 
-    // If an age is given, print the minumum distribution needed for the year
+    // If an age is given, print the minumum distribution needed for the year.  Each owner within
+    // the household with a birth year has their own minimum distribution, while the birth year
+    // argument is used for the accounts without an owner.
     // TODO: need to calculate this from the value on December 31st of the previous year
+    let mut owner_ages = accounts
+        .owners()
+        .iter()
+        .filter_map(|owner| {
+            owner.birth_year.map(|birth_year| {
+                (
+                    Some(owner.name.as_str()),
+                    args.distribution_year - birth_year,
+                )
+            })
+        })
+        .collect::<Vec<(Option<&str>, u32)>>();
     if let Some(age) = args.age_option {
-        if let Some(traditional_value) = vanguard_holdings.eoy_value(args.distribution_year).await? {
+        owner_ages.push((None, age));
+    }
+    for (owner, age) in owner_ages {
+        if let Some(traditional_value) = vanguard_holdings
+            .eoy_value(args.distribution_year, owner)
+            .await?
+        {
             let minimum_distribution = vapore::calc::calculate_minimum_distribution(
                 age,
                 traditional_value,
                 &args.distribution_table_path,
             )?;
-            println!("\n\nEnd of previous year traditional IRA account value: ${:?}\nMinimum distribution for {}: ${:.2}\nDistribution so far: ${:.2}\nDistribution needed: ${:.2}\n\n", traditional_value, args.distribution_year, minimum_distribution, vanguard_holdings.distributions(), (minimum_distribution - vanguard_holdings.distributions()).max(0.0));
+            let owner_title = owner
+                .map(|owner| format!("{}\n", owner))
+                .unwrap_or_default();
+            println!("\n\n{}End of previous year traditional IRA account value: ${:?}\nMinimum distribution for {}: ${:.2}\nDistribution so far: ${:.2}\nDistribution needed: ${:.2}\n\n", owner_title, traditional_value, args.distribution_year, minimum_distribution, vanguard_holdings.distributions(), (minimum_distribution - vanguard_holdings.distributions()).max(Decimal::ZERO));
         }
     }
+    // Compare the allocation every strategy gives each owner within the household, and the
+    // accounts without an owner when there are any
+    if args.compare_strategies {
+        let glide_paths = args.glide_paths()?;
+        let glide_path = glide_paths.glide_path(&args.glide_path)?;
+        let this_year = Local::now().year();
+        let mut investors = accounts
+            .owners()
+            .iter()
+            .map(|owner| (owner.name.clone(), Some(owner)))
+            .collect::<Vec<(String, Option<&Owner>)>>();
+        if accounts
+            .accounts()
+            .iter()
+            .any(|account| account.owner.is_none())
+        {
+            investors.push(("Accounts without an owner".to_string(), None));
+        }
+        for (name, owner) in investors {
+            println!(
+                "\nAllocation strategies for {} in {}:\n{}",
+                name,
+                this_year,
+                strategy::compare_strategies(&args.investor(owner, glide_path), this_year)
+            );
+        }
+    }
+    // Scan the taxable accounts for losses to harvest before the holdings are rebalanced
+    if args.harvest {
+        let harvest_pairs = args.harvest_pairs()?;
+        println!(
+            "\n{}\n",
+            harvest::scan(
+                &vanguard_holdings,
+                &harvest_pairs,
+                args.harvest_threshold,
+                Local::now().date_naive()
+            )
+        );
+    }
     //    .unwrap_or_else(|err| panic!("Holdings error: {}", err));
-    let rebalance = vapore::calc::to_buy(vanguard_holdings, args.clone())?;
+    let rebalance = vapore::calc::to_buy(
+        vanguard_holdings,
+        &accounts,
+        &outside_holdings,
+        args.clone(),
+    )?;
     println!(
         "DESCRIPTIONS:\n{}\n\n{}",
         vapore::holdings::all_stock_descriptions(),
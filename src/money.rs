@@ -0,0 +1,50 @@
+use anyhow::{Context, Result};
+use rust_decimal::{prelude::FromPrimitive, Decimal, RoundingStrategy};
+
+/// Number of decimal places that dollar values are rounded to
+pub const DOLLAR_DECIMALS: u32 = 2;
+
+/// Default number of decimal places that share quantities are rounded to
+pub const DEFAULT_SHARE_DECIMALS: u32 = 4;
+
+/// round_dollars rounds a dollar value to the nearest cent, where half a cent is rounded away from
+/// zero.
+///
+/// # Example
+///
+/// ```
+/// use rust_decimal_macros::dec;
+/// use vapore::money;
+///
+/// assert_eq!(money::round_dollars(dec!(10.005)), dec!(10.01));
+/// assert_eq!(money::round_dollars(dec!(-10.005)), dec!(-10.01));
+/// assert_eq!(money::round_dollars(dec!(10.004)), dec!(10.00));
+/// ```
+pub fn round_dollars(value: Decimal) -> Decimal {
+    value.round_dp_with_strategy(DOLLAR_DECIMALS, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// round_shares rounds a share quantity toward zero to the number of decimals.  Rounding toward
+/// zero makes sure that a purchase never needs more cash than is available and a sale never needs
+/// more shares than are held.
+///
+/// # Example
+///
+/// ```
+/// use rust_decimal_macros::dec;
+/// use vapore::money;
+///
+/// assert_eq!(money::round_shares(dec!(5.55559), 4), dec!(5.5555));
+/// assert_eq!(money::round_shares(dec!(-5.55559), 2), dec!(-5.55));
+/// ```
+pub fn round_shares(value: Decimal, decimals: u32) -> Decimal {
+    value.round_dp_with_strategy(decimals, RoundingStrategy::ToZero)
+}
+
+/// dollars_from_f64 converts a float dollar value, such as a quote from an outside api, into a
+/// Decimal rounded to the nearest cent.
+pub fn dollars_from_f64(value: f64) -> Result<Decimal> {
+    let decimal = Decimal::from_f64(value)
+        .with_context(|| format!("Unable to convert {} into a dollar value", value))?;
+    Ok(round_dollars(decimal))
+}
@@ -0,0 +1,202 @@
+use crate::{
+    account::AccountKind,
+    holdings::{ShareValues, StockSymbol},
+    universe::fund_universe,
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs};
+
+/// OutsideHolding is a position held outside of Vanguard, such as a 401k fund or an account at
+/// another broker.  The position is classified through the same asset model as the Vanguard
+/// holdings by, in order of precedence, a composition of sub class percentages, a single sub
+/// class, or the symbol of a fund within the fund universe.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutsideHolding {
+    pub name: String,
+    #[serde(default)]
+    pub symbol: Option<StockSymbol>,
+    #[serde(default)]
+    pub sub_class: Option<String>,
+    #[serde(default)]
+    pub composition: Option<BTreeMap<String, Decimal>>,
+    pub value: Decimal,
+    pub account: AccountKind,
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+impl OutsideHolding {
+    /// exposure returns the value of the position within the fund of each sub class it holds.
+    ///
+    /// # Errors
+    ///
+    /// Errors when the position names a sub class which no fund fills, has a composition which
+    /// does not add up to 100, or only has a symbol which is not within the fund universe.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{account::AccountKind, holdings::StockSymbol, outside::OutsideHolding};
+    ///
+    /// let holding = OutsideHolding {
+    ///     name: "401k S&P 500".to_string(),
+    ///     symbol: Some(StockSymbol::new("FXAIX")),
+    ///     sub_class: Some("us_stock_large".to_string()),
+    ///     composition: None,
+    ///     value: dec!(2000.0),
+    ///     account: AccountKind::TaxDeferred,
+    ///     owner: None,
+    /// };
+    ///
+    /// let exposure = holding.exposure().unwrap();
+    /// assert_eq!(exposure.stock_value(StockSymbol::new("VV")), dec!(2000.0));
+    /// ```
+    pub fn exposure(&self) -> Result<ShareValues> {
+        let mut exposure = ShareValues::new();
+        if let Some(composition) = &self.composition {
+            let total = composition.values().sum::<Decimal>();
+            ensure!(
+                total == Decimal::ONE_HUNDRED,
+                "Composition of outside holding {} does not add up to 100: {}",
+                self.name,
+                total
+            );
+            for (sub_class, percent) in composition {
+                let symbol = self.sub_class_symbol(sub_class)?;
+                let current_value = exposure.stock_value(symbol.clone());
+                exposure.add_stock_value(
+                    symbol,
+                    current_value + self.value * percent / Decimal::ONE_HUNDRED,
+                );
+            }
+        } else if let Some(sub_class) = &self.sub_class {
+            exposure.add_stock_value(self.sub_class_symbol(sub_class)?, self.value);
+        } else {
+            let symbol = self
+                .symbol
+                .clone()
+                .filter(|symbol| fund_universe().contains(symbol))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Outside holding {} needs a sub class or composition unless its symbol is \
+                        within the fund universe",
+                        self.name
+                    )
+                })?;
+            exposure.add_stock_value(symbol, self.value);
+            exposure = exposure.look_through();
+        }
+        Ok(exposure)
+    }
+
+    fn sub_class_symbol(&self, sub_class: &str) -> Result<StockSymbol> {
+        fund_universe().sub_class_symbol(sub_class).ok_or_else(|| {
+            anyhow!(
+                "Outside holding {} contains {}, which is not the sub class of any fund",
+                self.name,
+                sub_class
+            )
+        })
+    }
+}
+
+/// OutsideHoldings holds all positions held outside of Vanguard
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutsideHoldings {
+    #[serde(default, rename = "holding")]
+    holdings: Vec<OutsideHolding>,
+}
+
+impl OutsideHoldings {
+    /// new creates an empty OutsideHoldings struct
+    pub fn new() -> Self {
+        OutsideHoldings {
+            holdings: Vec::new(),
+        }
+    }
+
+    /// from_toml parses and classifies the outside holdings from the contents of a TOML file
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{account::AccountKind, holdings::StockSymbol, outside::OutsideHoldings};
+    ///
+    /// let outside = OutsideHoldings::from_toml(
+    ///     r#"
+    ///     [[holding]]
+    ///     name = "Schwab total bond"
+    ///     symbol = "SCHZ"
+    ///     sub_class = "us_tot_bond"
+    ///     value = 3000.00
+    ///     account = "tax_free"
+    ///     owner = "Sam"
+    ///
+    ///     [[holding]]
+    ///     name = "401k target date"
+    ///     value = 10000.00
+    ///     account = "traditional_ira"
+    ///
+    ///     [holding.composition]
+    ///     us_stock_large = 50.0
+    ///     int_tot_stock = 30.0
+    ///     us_tot_bond = 20.0
+    ///     "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let roth = outside.exposure(AccountKind::TaxFree).unwrap();
+    /// assert_eq!(roth.stock_value(StockSymbol::new("BND")), dec!(3000.00));
+    ///
+    /// let traditional = outside.exposure(AccountKind::TaxDeferred).unwrap();
+    /// assert_eq!(traditional.stock_value(StockSymbol::new("VXUS")), dec!(3000.00));
+    /// assert_eq!(traditional.total_value(), dec!(10000.00));
+    /// ```
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let outside: OutsideHoldings =
+            toml::from_str(contents).context("Unable to parse outside holdings")?;
+        for holding in &outside.holdings {
+            holding.exposure()?;
+        }
+        Ok(outside)
+    }
+
+    /// from_file reads an outside holdings TOML file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Outside holdings file not found: {}", path))?;
+        Self::from_toml(&contents).with_context(|| format!("Invalid outside holdings: {}", path))
+    }
+
+    /// add_holding adds a position held outside of Vanguard
+    pub fn add_holding(&mut self, holding: OutsideHolding) -> Result<()> {
+        holding.exposure()?;
+        self.holdings.push(holding);
+        Ok(())
+    }
+
+    /// holdings returns all positions held outside of Vanguard
+    pub fn holdings(&self) -> &[OutsideHolding] {
+        &self.holdings
+    }
+
+    /// exposure returns the combined value of the positions of the account kind within the fund
+    /// of each sub class
+    pub fn exposure(&self, account: AccountKind) -> Result<ShareValues> {
+        let mut exposure = ShareValues::new();
+        for holding in self
+            .holdings
+            .iter()
+            .filter(|holding| holding.account == account)
+        {
+            exposure = exposure + holding.exposure()?;
+        }
+        Ok(exposure)
+    }
+}
@@ -0,0 +1,379 @@
+use crate::{
+    holdings::{ShareValues, StockSymbol},
+    money::round_dollars,
+    universe::fund_universe,
+};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// ETF_SETTLEMENT_DAYS is the number of business days after the trade date that the cash of an
+/// ETF sale settles and can be spent
+pub const ETF_SETTLEMENT_DAYS: u32 = 1;
+
+/// MUTUAL_FUND_SETTLEMENT_DAYS is the number of business days after the trade date that the cash
+/// of a mutual fund sale settles and can be spent.  An exchange into another Vanguard mutual fund
+/// is made on the trade date without waiting for the sale to settle.
+pub const MUTUAL_FUND_SETTLEMENT_DAYS: u32 = 1;
+
+/// TradeStep is a single step of an execution plan.  ETF trades are in shares, where the amount is
+/// their estimated value, while mutual fund trades and exchanges are in dollars.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TradeStep {
+    Sell {
+        symbol: StockSymbol,
+        shares: Option<Decimal>,
+        settlement_days: u32,
+    },
+    Exchange {
+        from: StockSymbol,
+        to: StockSymbol,
+    },
+    Sweep {
+        from: StockSymbol,
+        to: StockSymbol,
+    },
+    Settle {
+        symbol: StockSymbol,
+    },
+    Withdraw,
+    Buy {
+        symbol: StockSymbol,
+        shares: Option<Decimal>,
+    },
+}
+
+impl TradeStep {
+    // phase orders the steps made on the same day, where the sales which settle are spent after
+    // they settle, and purchases are made once the day's cash has been swept into the settlement
+    // fund
+    fn phase(&self) -> u32 {
+        match self {
+            TradeStep::Settle { .. } => 0,
+            TradeStep::Sell { .. } => 1,
+            TradeStep::Exchange { .. } => 2,
+            TradeStep::Sweep { .. } => 3,
+            TradeStep::Withdraw => 4,
+            TradeStep::Buy { .. } => 5,
+        }
+    }
+
+    // cash_change returns the change of the settled cash for the step of the amount
+    fn cash_change(&self, amount: Decimal) -> Decimal {
+        match self {
+            TradeStep::Settle { .. } | TradeStep::Sweep { .. } => amount,
+            TradeStep::Withdraw | TradeStep::Buy { .. } => -amount,
+            TradeStep::Sell { .. } | TradeStep::Exchange { .. } => Decimal::ZERO,
+        }
+    }
+}
+
+impl fmt::Display for TradeStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let trade = |action: &str, symbol: &StockSymbol, shares: &Option<Decimal>| match shares {
+            Some(shares) => format!("{} {:.4} shares of {}", action, shares, symbol),
+            None => format!("{} {}", action, symbol),
+        };
+        match self {
+            TradeStep::Sell {
+                symbol,
+                shares,
+                settlement_days,
+            } => write!(
+                f,
+                "{}, settles T+{}",
+                trade("Sell", symbol, shares),
+                settlement_days
+            ),
+            TradeStep::Exchange { from, to } => write!(f, "Exchange {} into {}", from, to),
+            TradeStep::Sweep { from, to } => write!(f, "Sweep {} into {}", from, to),
+            TradeStep::Settle { symbol } => write!(f, "Sale of {} settles", symbol),
+            TradeStep::Withdraw => write!(f, "Withdraw"),
+            TradeStep::Buy { symbol, shares } => write!(f, "{}", trade("Buy", symbol, shares)),
+        }
+    }
+}
+
+/// PlanStep is a step of an execution plan along with the business day after the trade date it is
+/// made on, its dollar amount, and the settled cash projected after it
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanStep {
+    pub day: u32,
+    pub step: TradeStep,
+    pub amount: Decimal,
+    pub cash: Decimal,
+}
+
+/// ExecutionPlan is the order in which the trades of an account are made.  Sales are made first,
+/// then the other money market funds are swept into the settlement fund, and then the withdrawal
+/// and the purchases are made once there is enough settled cash for them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionPlan {
+    pub starting_cash: Decimal,
+    pub steps: Vec<PlanStep>,
+}
+
+impl ExecutionPlan {
+    /// new creates the execution plan of the orders of an account, where ETF orders are in shares
+    /// and mutual fund orders are in dollars.  Mutual fund sales are exchanged into the mutual fund
+    /// purchases on the trade date, the largest sale into the largest purchase first, while the
+    /// rest of the sales settle after their settlement days.  The withdrawal and then each
+    /// purchase is made on the first day that the settled cash covers it without the projected
+    /// cash dropping below zero on any later day.  The cash of the current holdings is after the
+    /// withdrawal, which is taken as its own step.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     holdings::{ShareValues, StockSymbol},
+    ///     plan::{ExecutionPlan, TradeStep},
+    /// };
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("BND"), dec!(1000.0));
+    /// current.add_stock_value(StockSymbol::new("VBTLX"), dec!(1000.0));
+    /// current.add_cash(dec!(100.0));
+    ///
+    /// let mut quotes = ShareValues::new_quote();
+    /// quotes.add_stock_value(StockSymbol::new("BND"), dec!(100.0));
+    /// quotes.add_stock_value(StockSymbol::new("VV"), dec!(250.0));
+    ///
+    /// let mut orders = ShareValues::new();
+    /// orders.add_stock_value(StockSymbol::new("BND"), dec!(-5.0));
+    /// orders.add_stock_value(StockSymbol::new("VV"), dec!(2.0));
+    /// orders.add_stock_value(StockSymbol::new("VBTLX"), dec!(-300.0));
+    /// orders.add_stock_value(StockSymbol::new("VTIAX"), dec!(300.0));
+    ///
+    /// let plan = ExecutionPlan::new(&current, &orders, &quotes, dec!(0.0));
+    /// // The mutual funds are exchanged on the trade date
+    /// assert_eq!(
+    ///     plan.steps[1].step,
+    ///     TradeStep::Exchange { from: StockSymbol::new("VBTLX"), to: StockSymbol::new("VTIAX") }
+    /// );
+    /// // VV is bought once the sale of BND settles
+    /// let buy = plan.steps.last().unwrap();
+    /// assert_eq!(buy.day, 1);
+    /// assert_eq!(buy.cash, dec!(100.0));
+    /// assert!(plan.is_funded());
+    /// ```
+    pub fn new(
+        current: &ShareValues,
+        orders: &ShareValues,
+        quotes: &ShareValues,
+        withdrawal: Decimal,
+    ) -> Self {
+        let universe = fund_universe();
+        let cash_symbol = universe.cash_symbol();
+        let mut steps = Vec::new();
+        let mut mutual_fund_sales = Vec::new();
+        let mut mutual_fund_buys = Vec::new();
+        let mut buys = Vec::new();
+        for symbol in orders.symbols() {
+            let order = orders.stock_value(symbol.clone());
+            if universe.is_pooled_cash(&symbol) || order.is_zero() {
+                continue;
+            }
+            if universe.is_mutual_fund(&symbol) {
+                if order.is_sign_negative() {
+                    mutual_fund_sales.push((symbol, -order));
+                } else {
+                    mutual_fund_buys.push((symbol, order));
+                }
+                continue;
+            }
+            let amount = round_dollars(order.abs() * quotes.stock_value(symbol.clone()));
+            if order.is_sign_negative() {
+                steps.push((
+                    0,
+                    TradeStep::Sell {
+                        symbol: symbol.clone(),
+                        shares: Some(-order),
+                        settlement_days: ETF_SETTLEMENT_DAYS,
+                    },
+                    amount,
+                ));
+                steps.push((ETF_SETTLEMENT_DAYS, TradeStep::Settle { symbol }, amount));
+            } else {
+                buys.push((
+                    TradeStep::Buy {
+                        symbol,
+                        shares: Some(order),
+                    },
+                    amount,
+                ));
+            }
+        }
+
+        // The largest mutual fund sale is exchanged into the largest mutual fund purchase until
+        // either runs out
+        mutual_fund_sales.sort_by(|(_, sale_a), (_, sale_b)| sale_b.cmp(sale_a));
+        mutual_fund_buys.sort_by(|(_, buy_a), (_, buy_b)| buy_b.cmp(buy_a));
+        let mut buy_index = 0;
+        for (symbol, mut sale) in mutual_fund_sales {
+            while sale > Decimal::ZERO && buy_index < mutual_fund_buys.len() {
+                let (buy_symbol, buy) = &mut mutual_fund_buys[buy_index];
+                let exchanged = sale.min(*buy);
+                steps.push((
+                    0,
+                    TradeStep::Exchange {
+                        from: symbol.clone(),
+                        to: buy_symbol.clone(),
+                    },
+                    exchanged,
+                ));
+                sale -= exchanged;
+                *buy -= exchanged;
+                if buy.is_zero() {
+                    buy_index += 1;
+                }
+            }
+            if sale > Decimal::ZERO {
+                steps.push((
+                    0,
+                    TradeStep::Sell {
+                        symbol: symbol.clone(),
+                        shares: None,
+                        settlement_days: MUTUAL_FUND_SETTLEMENT_DAYS,
+                    },
+                    sale,
+                ));
+                steps.push((
+                    MUTUAL_FUND_SETTLEMENT_DAYS,
+                    TradeStep::Settle { symbol },
+                    sale,
+                ));
+            }
+        }
+        for (symbol, buy) in mutual_fund_buys {
+            if buy > Decimal::ZERO {
+                buys.push((
+                    TradeStep::Buy {
+                        symbol,
+                        shares: None,
+                    },
+                    buy,
+                ));
+            }
+        }
+
+        // Cash held within the other money market funds is swept into the settlement fund
+        for symbol in current.symbols() {
+            let value = current.stock_value(symbol.clone());
+            if symbol != cash_symbol && universe.is_pooled_cash(&symbol) && value > Decimal::ZERO {
+                steps.push((
+                    0,
+                    TradeStep::Sweep {
+                        from: symbol,
+                        to: cash_symbol.clone(),
+                    },
+                    value,
+                ));
+            }
+        }
+        let starting_cash = current.cash_value() + withdrawal
+            - steps
+                .iter()
+                .filter(|(_, step, _)| matches!(step, TradeStep::Sweep { .. }))
+                .map(|(_, _, amount)| *amount)
+                .sum::<Decimal>();
+
+        // Each withdrawal and purchase is made on the first day its cash is covered on that day and
+        // every later day
+        let mut outflows = Vec::new();
+        if withdrawal > Decimal::ZERO {
+            outflows.push((TradeStep::Withdraw, withdrawal));
+        }
+        outflows.extend(buys);
+        let mut days = steps.iter().map(|(day, _, _)| *day).collect::<Vec<u32>>();
+        days.push(0);
+        days.sort();
+        days.dedup();
+        for (step, amount) in outflows {
+            let cash_on = |day: u32, steps: &[(u32, TradeStep, Decimal)]| {
+                starting_cash
+                    + steps
+                        .iter()
+                        .filter(|(step_day, _, _)| *step_day <= day)
+                        .map(|(_, step, amount)| step.cash_change(*amount))
+                        .sum::<Decimal>()
+            };
+            let day = days
+                .iter()
+                .copied()
+                .find(|day| {
+                    days.iter()
+                        .filter(|later_day| *later_day >= day)
+                        .all(|later_day| cash_on(*later_day, &steps) >= amount)
+                })
+                .unwrap_or_else(|| *days.last().expect("Days are not empty"));
+            steps.push((day, step, amount));
+        }
+
+        // Steps keep the order they were planned in within each day and phase
+        steps.sort_by_key(|(day, step, _)| (*day, step.phase()));
+        let mut cash = starting_cash;
+        let steps = steps
+            .into_iter()
+            .map(|(day, step, amount)| {
+                cash += step.cash_change(amount);
+                PlanStep {
+                    day,
+                    step,
+                    amount,
+                    cash,
+                }
+            })
+            .collect();
+        ExecutionPlan {
+            starting_cash,
+            steps,
+        }
+    }
+
+    /// is_funded returns whether or not the projected cash stays at or above zero through every
+    /// step of the plan
+    pub fn is_funded(&self) -> bool {
+        !self.starting_cash.is_sign_negative()
+            && self.steps.iter().all(|step| !step.cash.is_sign_negative())
+    }
+}
+
+impl fmt::Display for ExecutionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rows = String::new();
+        for step in &self.steps {
+            rows.push_str(&format!(
+                "T+{:<4}{:<45}${:<15.2}${:.2}\n",
+                step.day,
+                step.step.to_string(),
+                step.amount,
+                step.cash
+            ));
+        }
+        let shortfall = self
+            .steps
+            .iter()
+            .map(|step| step.cash)
+            .chain([self.starting_cash])
+            .min()
+            .unwrap_or_default();
+        let funded_note = if self.is_funded() {
+            String::new()
+        } else {
+            format!(
+                "\nThe projected cash falls ${:.2} short, so the purchases need to be reduced",
+                -shortfall
+            )
+        };
+        write!(
+            f,
+            "Execution plan, starting with ${:.2} of settled cash:\n\
+            Day   Step                                         Amount          Cash\n\
+            ---------------------------------------------------------------------------------\n\
+            {}\
+            ---------------------------------------------------------------------------------{}",
+            self.starting_cash, rows, funded_note
+        )
+    }
+}
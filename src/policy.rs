@@ -0,0 +1,665 @@
+use crate::{
+    holdings::{ShareValues, StockSymbol},
+    lots::{LotSelection, TaxLots, TaxRates},
+    money::round_dollars,
+    universe::{fund_universe, AssetClass},
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::{collections::HashMap, fmt, str::FromStr};
+
+/// Band is a tolerance band around a target, such as the 5/25 rule.  A position is within its band
+/// while it is off its target by no more than the absolute band, in percentage points of the
+/// account, and no more than the relative band, in percent of its own target, whichever is
+/// smaller.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Band {
+    pub absolute: Decimal,
+    pub relative: Decimal,
+}
+
+impl Band {
+    /// width returns the dollars a position with the target may drift within an account of the
+    /// total value before it is rebalanced
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::policy::Band;
+    ///
+    /// let band = "5/25".parse::<Band>().unwrap();
+    /// // 5% of the account is smaller than 25% of a 60% target
+    /// assert_eq!(band.width(dec!(6000), dec!(10000)), dec!(500));
+    /// // 25% of a 10% target is smaller than 5% of the account
+    /// assert_eq!(band.width(dec!(1000), dec!(10000)), dec!(250));
+    /// ```
+    pub fn width(&self, target: Decimal, total: Decimal) -> Decimal {
+        (total * self.absolute / Decimal::ONE_HUNDRED)
+            .min(target.abs() * self.relative / Decimal::ONE_HUNDRED)
+    }
+}
+
+impl FromStr for Band {
+    type Err = anyhow::Error;
+
+    fn from_str(band: &str) -> Result<Self> {
+        let (absolute, relative) = band.split_once('/').ok_or_else(|| {
+            anyhow!(
+                "Band {} needs the format <absolute percent>/<relative percent>, such as 5/25",
+                band
+            )
+        })?;
+        let parse = |percent: &str| {
+            percent
+                .trim()
+                .parse::<Decimal>()
+                .with_context(|| format!("Invalid band: {}", band))
+        };
+        let band = Band {
+            absolute: parse(absolute)?,
+            relative: parse(relative)?,
+        };
+        ensure!(
+            !band.absolute.is_sign_negative() && !band.relative.is_sign_negative(),
+            "Band percentages cannot be negative: {}/{}",
+            band.absolute,
+            band.relative
+        );
+        Ok(band)
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.absolute, self.relative)
+    }
+}
+
+/// BandMode is where a position outside of its band is brought back to, either its target or the
+/// nearest edge of its band
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BandMode {
+    #[default]
+    Target,
+    Edge,
+}
+
+impl FromStr for BandMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode {
+            "target" => Ok(BandMode::Target),
+            "edge" => Ok(BandMode::Edge),
+            _ => Err(anyhow!(
+                "Unknown band mode {}.  Modes are target and edge",
+                mode
+            )),
+        }
+    }
+}
+
+/// RebalancePolicy decides which positions of an account are traded.  Without any bands every
+/// position is traded to its exact target.  With an asset class band, an asset class which drifts
+/// outside of its band has all of its funds traded.  With a fund band, each fund which drifts
+/// outside of its band is traded.  Every other position is left as it is.  Buy only never sells,
+/// and only invests the cash of the account into the funds furthest below their targets.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RebalancePolicy {
+    pub asset_class_band: Option<Band>,
+    pub fund_band: Option<Band>,
+    pub mode: BandMode,
+    pub buy_only: bool,
+}
+
+impl RebalancePolicy {
+    /// apply returns the target of the account after the policy is applied.  Positions within their
+    /// bands keep their current value.  The cash left over from the trades stays as cash while it
+    /// is within the band around the target cash, otherwise it is spread across the traded
+    /// positions which are below their targets, so no position within its band is traded.  Without
+    /// such a position it stays as cash.
+    /// More cash than the account holds is never spent, where the shortfall is taken from the
+    /// positions furthest above their targets when no traded position is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     holdings::{ShareValues, StockSymbol},
+    ///     policy::{BandMode, RebalancePolicy},
+    /// };
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("VV"), dec!(6100.0));
+    /// current.add_stock_value(StockSymbol::new("VXUS"), dec!(2900.0));
+    /// current.add_stock_value(StockSymbol::new("BND"), dec!(1000.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(5000.0));
+    /// target.add_stock_value(StockSymbol::new("VXUS"), dec!(3000.0));
+    /// target.add_stock_value(StockSymbol::new("BND"), dec!(2000.0));
+    ///
+    /// let mut policy = RebalancePolicy {
+    ///     fund_band: Some("5/25".parse().unwrap()),
+    ///     ..Default::default()
+    /// };
+    /// let banded = policy.apply(&current, target.clone());
+    /// assert_eq!(banded.stock_value(StockSymbol::new("VV")), dec!(5000.0));
+    /// assert_eq!(banded.stock_value(StockSymbol::new("VXUS")), dec!(2900.0));
+    /// assert_eq!(banded.stock_value(StockSymbol::new("BND")), dec!(2000.0));
+    /// assert_eq!(banded.cash_value(), dec!(100.0));
+    ///
+    /// // The $100 left over is outside of the band around the $0 target cash, so it goes to BND,
+    /// // which is traded to the edge of its band, rather than VXUS, which is within its band
+    /// policy.mode = BandMode::Edge;
+    /// let banded = policy.apply(&current, target);
+    /// assert_eq!(banded.stock_value(StockSymbol::new("VV")), dec!(5500.0));
+    /// assert_eq!(banded.stock_value(StockSymbol::new("VXUS")), dec!(2900.0));
+    /// assert_eq!(banded.stock_value(StockSymbol::new("BND")), dec!(1600.0));
+    /// assert_eq!(banded.cash_value(), dec!(0.0));
+    /// assert_eq!(banded.total_value(), dec!(10000.0));
+    /// ```
+    pub fn apply(&self, current: &ShareValues, mut target: ShareValues) -> ShareValues {
+        if self.buy_only {
+            return buy_only(current, target);
+        }
+        let cash_band = match self.fund_band.or(self.asset_class_band) {
+            Some(band) => band,
+            None => return target,
+        };
+        let universe = fund_universe();
+        let total = current.total_value();
+        let mut symbols = target.symbols();
+        for symbol in current.symbols() {
+            if !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+        // Kept funds stay at their current value and cash is what is left over
+        let kept = current.kept().symbols();
+        symbols.retain(|symbol| !universe.is_pooled_cash(symbol) && !kept.contains(symbol));
+        let asset_class = |symbol: &StockSymbol| {
+            universe
+                .model_fund(symbol)
+                .map(|fund| fund.asset_class)
+                .filter(|asset_class| {
+                    matches!(
+                        asset_class,
+                        AssetClass::Stock | AssetClass::Bond | AssetClass::InflationProtected
+                    )
+                })
+        };
+
+        // Asset classes outside of their band are scaled to their target or the edge of their band
+        let mut class_values: HashMap<AssetClass, (Decimal, Decimal)> = HashMap::new();
+        for symbol in &symbols {
+            if let Some(asset_class) = asset_class(symbol) {
+                let (class_current, class_target) = class_values.entry(asset_class).or_default();
+                *class_current += current.stock_value(symbol.clone());
+                *class_target += target.stock_value(symbol.clone());
+            }
+        }
+        let mut class_scales = HashMap::new();
+        if let Some(band) = self.asset_class_band {
+            for (asset_class, (class_current, class_target)) in class_values {
+                let width = band.width(class_target, total);
+                if (class_current - class_target).abs() > width {
+                    let edge = self.band_edge(class_current, class_target, width);
+                    let scale = if class_target.is_zero() {
+                        Decimal::ONE
+                    } else {
+                        edge / class_target
+                    };
+                    class_scales.insert(asset_class, scale);
+                }
+            }
+        }
+
+        let mut adjusted = Vec::new();
+        for symbol in symbols {
+            let symbol_current = current.stock_value(symbol.clone());
+            let symbol_target = target.stock_value(symbol.clone());
+            let class = asset_class(&symbol);
+            let (value, traded) = match class.and_then(|class| class_scales.get(&class)) {
+                Some(scale) => (round_dollars(symbol_target * scale), true),
+                None => match (self.fund_band, class) {
+                    (Some(band), _) => {
+                        let width = band.width(symbol_target, total);
+                        if (symbol_current - symbol_target).abs() > width {
+                            let edge = self.band_edge(symbol_current, symbol_target, width);
+                            (round_dollars(edge), true)
+                        } else {
+                            (symbol_current, false)
+                        }
+                    }
+                    // Positions within the asset classes without a fund band are left alone while
+                    // their asset class is within its band
+                    (None, Some(_)) => (symbol_current, false),
+                    // Positions outside of the asset classes, such as funds outside of the fund
+                    // universe, are always traded to their target
+                    (None, None) => (symbol_target, true),
+                },
+            };
+            adjusted.push((symbol, value, symbol_target, traded));
+        }
+
+        // Cash which is left over outside of the cash band is spread across the traded positions
+        // in proportion to how far they are from their targets, otherwise it stays as cash.  More
+        // cash than is held is always spread, falling back on every position when no traded
+        // position is above its target.
+        let leftover = adjusted
+            .iter()
+            .map(|(_, value, symbol_target, _)| symbol_target - value)
+            .sum::<Decimal>();
+        let cash_width = cash_band.width(target.cash_value(), total);
+        if leftover.is_sign_negative() || leftover > cash_width {
+            let gaps = |traded_only: bool| {
+                adjusted
+                    .iter()
+                    .filter(|(_, _, _, traded)| *traded || !traded_only)
+                    .map(|(_, value, symbol_target, _)| symbol_target - value)
+                    .filter(|gap| gap.is_sign_negative() == leftover.is_sign_negative())
+                    .sum::<Decimal>()
+            };
+            let mut traded_only = true;
+            let mut total_gap = gaps(true);
+            if total_gap.is_zero() && leftover.is_sign_negative() {
+                traded_only = false;
+                total_gap = gaps(false);
+            }
+            let receives = |gap: Decimal, traded: bool| {
+                (traded || !traded_only)
+                    && !gap.is_zero()
+                    && gap.is_sign_negative() == leftover.is_sign_negative()
+            };
+            // Rounding to the cent is settled by the position furthest from its target
+            let furthest = adjusted
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, value, symbol_target, traded))| {
+                    receives(symbol_target - value, *traded)
+                })
+                .max_by_key(|(_, (_, value, symbol_target, _))| (symbol_target - value).abs())
+                .map(|(index, _)| index);
+            if let Some(furthest) = furthest {
+                let mut spread = Decimal::ZERO;
+                for (_, value, symbol_target, traded) in adjusted.iter_mut() {
+                    let gap = *symbol_target - *value;
+                    if receives(gap, *traded) {
+                        let share = round_dollars(leftover * gap / total_gap);
+                        *value += share;
+                        spread += share;
+                    }
+                }
+                adjusted[furthest].1 += leftover - spread;
+            }
+        }
+
+        let mut cash_left = Decimal::ZERO;
+        for (symbol, value, symbol_target, _) in adjusted {
+            cash_left += symbol_target - value;
+            target.add_stock_value(symbol, value);
+        }
+        target.add_cash(cash_left);
+        target
+    }
+
+    // band_edge returns where a position outside of its band is brought back to
+    fn band_edge(&self, current: Decimal, target: Decimal, width: Decimal) -> Decimal {
+        match self.mode {
+            BandMode::Target => target,
+            BandMode::Edge if current > target => target + width,
+            BandMode::Edge => target - width,
+        }
+    }
+}
+
+/// buy_only returns the target of the account when nothing is sold.  The cash above the target
+/// cash, such as new contributions, is invested into the funds which are furthest below their
+/// targets, raising each to the same distance below its target, which shrinks the drift of the
+/// account as much as possible.  Every other position keeps its current value.
+///
+/// # Example
+///
+/// ```
+/// use rust_decimal_macros::dec;
+/// use vapore::{
+///     holdings::{ShareValues, StockSymbol},
+///     policy::buy_only,
+/// };
+///
+/// let mut current = ShareValues::new();
+/// current.add_stock_value(StockSymbol::new("VV"), dec!(6000.0));
+/// current.add_stock_value(StockSymbol::new("VXUS"), dec!(2000.0));
+/// current.add_stock_value(StockSymbol::new("BND"), dec!(1000.0));
+/// current.add_cash(dec!(1000.0));
+///
+/// let mut target = ShareValues::new();
+/// target.add_stock_value(StockSymbol::new("VV"), dec!(5000.0));
+/// target.add_stock_value(StockSymbol::new("VXUS"), dec!(3000.0));
+/// target.add_stock_value(StockSymbol::new("BND"), dec!(2000.0));
+///
+/// let bought = buy_only(&current, target);
+/// assert_eq!(bought.stock_value(StockSymbol::new("VV")), dec!(6000.0));
+/// assert_eq!(bought.stock_value(StockSymbol::new("VXUS")), dec!(2500.0));
+/// assert_eq!(bought.stock_value(StockSymbol::new("BND")), dec!(1500.0));
+/// assert_eq!(bought.cash_value(), dec!(0.0));
+/// ```
+pub fn buy_only(current: &ShareValues, mut target: ShareValues) -> ShareValues {
+    let cash = current.cash_value() - target.cash_value();
+    let symbols = traded_symbols(current, &target);
+    // A fund is bought up to its target at most
+    let gaps = symbols
+        .iter()
+        .map(|symbol| {
+            let gap = target.stock_value(symbol.clone()) - current.stock_value(symbol.clone());
+            (gap, gap.max(Decimal::ZERO))
+        })
+        .collect::<Vec<(Decimal, Decimal)>>();
+    let buys = level_trades(&gaps, cash);
+    let mut spent = Decimal::ZERO;
+    for (symbol, buy) in symbols.into_iter().zip(buys) {
+        spent += buy;
+        target.add_stock_value(symbol.clone(), current.stock_value(symbol) + buy);
+    }
+    target.add_cash(current.cash_value() - spent - target.cash_value());
+    target
+}
+
+/// withdraw returns the target of an account which raises the cash it is short of its target cash,
+/// such as a withdrawal, by only selling.  The funds furthest above their targets are sold first,
+/// lowering each to the same distance above its target, and every other position keeps its current
+/// value.  When the most each fund may sell first is given, such as the lots which do not realize a
+/// short term gain, the rest of a fund is only sold once those are not enough.
+///
+/// # Example
+///
+/// ```
+/// use rust_decimal_macros::dec;
+/// use vapore::{
+///     holdings::{ShareValues, StockSymbol},
+///     policy::withdraw,
+/// };
+///
+/// let mut current = ShareValues::new();
+/// current.add_stock_value(StockSymbol::new("VV"), dec!(6000.0));
+/// current.add_stock_value(StockSymbol::new("VXUS"), dec!(3000.0));
+/// current.add_stock_value(StockSymbol::new("BND"), dec!(2000.0));
+/// current.add_cash(dec!(-1000.0));
+///
+/// let mut target = ShareValues::new();
+/// target.add_stock_value(StockSymbol::new("VV"), dec!(5000.0));
+/// target.add_stock_value(StockSymbol::new("VXUS"), dec!(3000.0));
+/// target.add_stock_value(StockSymbol::new("BND"), dec!(2000.0));
+///
+/// let sold = withdraw(&current, target.clone(), None);
+/// assert_eq!(sold.stock_value(StockSymbol::new("VV")), dec!(5000.0));
+/// assert_eq!(sold.stock_value(StockSymbol::new("VXUS")), dec!(3000.0));
+/// assert_eq!(sold.cash_value(), dec!(0.0));
+///
+/// // Only $400 of VV may be sold before the other funds are sold below their targets
+/// let mut first_sales = current.clone();
+/// first_sales.add_stock_value(StockSymbol::new("VV"), dec!(400.0));
+/// let sold = withdraw(&current, target, Some(&first_sales));
+/// assert_eq!(sold.stock_value(StockSymbol::new("VV")), dec!(5600.0));
+/// assert_eq!(sold.stock_value(StockSymbol::new("VXUS")), dec!(2700.0));
+/// assert_eq!(sold.stock_value(StockSymbol::new("BND")), dec!(1700.0));
+/// ```
+pub fn withdraw(
+    current: &ShareValues,
+    mut target: ShareValues,
+    first_sales: Option<&ShareValues>,
+) -> ShareValues {
+    let needed = target.cash_value() - current.cash_value();
+    let symbols = traded_symbols(current, &target);
+    let excess = symbols
+        .iter()
+        .map(|symbol| current.stock_value(symbol.clone()) - target.stock_value(symbol.clone()))
+        .collect::<Vec<Decimal>>();
+    let mut sales = vec![Decimal::ZERO; symbols.len()];
+    if let Some(first_sales) = first_sales {
+        let limited = symbols
+            .iter()
+            .zip(&excess)
+            .map(|(symbol, excess)| {
+                let limit = first_sales
+                    .stock_value(symbol.clone())
+                    .min(current.stock_value(symbol.clone()))
+                    .max(Decimal::ZERO);
+                (*excess, limit)
+            })
+            .collect::<Vec<(Decimal, Decimal)>>();
+        sales = level_trades(&limited, needed);
+    }
+    let sold = sales.iter().sum::<Decimal>();
+    // A fund is sold down to nothing at most
+    let remaining = symbols
+        .iter()
+        .zip(excess.iter().zip(&sales))
+        .map(|(symbol, (excess, sale))| (excess - sale, current.stock_value(symbol.clone()) - sale))
+        .collect::<Vec<(Decimal, Decimal)>>();
+    for (sale, more) in sales
+        .iter_mut()
+        .zip(level_trades(&remaining, needed - sold))
+    {
+        *sale += more;
+    }
+
+    let mut raised = Decimal::ZERO;
+    for (symbol, sale) in symbols.into_iter().zip(sales) {
+        raised += sale;
+        target.add_stock_value(symbol.clone(), current.stock_value(symbol) - sale);
+    }
+    target.add_cash(current.cash_value() + raised - target.cash_value());
+    target
+}
+
+/// TaxPolicy is how the sales of a taxable account with tax lots are taxed.  The lot selection picks
+/// which lots are sold, whose gains are estimated with the tax rates, and the estimated tax of the
+/// sales may be limited to a maximum tax cost.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TaxPolicy {
+    pub lot_selection: LotSelection,
+    pub rates: TaxRates,
+    pub max_tax_cost: Option<Decimal>,
+}
+
+impl TaxPolicy {
+    /// limit_sales returns the target of a taxable account whose sales stay within the maximum tax
+    /// cost.  When the sales to reach the target cost more, the lots which cost the least tax per
+    /// dollar sold are sold first, starting with the lots at a loss, until the maximum tax cost is
+    /// reached.  The drift left over stays within the funds which are not sold, and the cash that
+    /// is raised is invested into the funds furthest below their targets.  Shares without a tax lot
+    /// are treated as sold at their cost basis.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{
+    ///     holdings::{ShareValues, StockSymbol},
+    ///     lots::{LotSelection, TaxLot, TaxLots, TaxRates},
+    ///     policy::TaxPolicy,
+    /// };
+    ///
+    /// let mut current = ShareValues::new();
+    /// current.add_stock_value(StockSymbol::new("VV"), dec!(3000.0));
+    /// current.add_stock_value(StockSymbol::new("BND"), dec!(0.0));
+    ///
+    /// let mut target = ShareValues::new();
+    /// target.add_stock_value(StockSymbol::new("VV"), dec!(1500.0));
+    /// target.add_stock_value(StockSymbol::new("BND"), dec!(1500.0));
+    ///
+    /// let mut quotes = ShareValues::new_quote();
+    /// quotes.add_stock_value(StockSymbol::new("VV"), dec!(300.0));
+    ///
+    /// let mut lots = TaxLots::new();
+    /// lots.add_lot(TaxLot {
+    ///     symbol: StockSymbol::new("VV"),
+    ///     acquired: NaiveDate::from_ymd_opt(2020, 3, 15).unwrap(),
+    ///     shares: dec!(10.0),
+    ///     cost_basis: dec!(1000.00),
+    /// });
+    ///
+    /// let tax_policy = TaxPolicy {
+    ///     lot_selection: LotSelection::Hifo,
+    ///     rates: TaxRates { short_term: dec!(24), long_term: dec!(15) },
+    ///     max_tax_cost: Some(dec!(100.00)),
+    /// };
+    /// let as_of = NaiveDate::from_ymd_opt(2024, 9, 1).unwrap();
+    /// // Selling $1500 of VV gains $1000 and costs $150 of tax, so only $1000 is sold
+    /// let limited = tax_policy.limit_sales(&current, target, &lots, &quotes, as_of);
+    /// assert_eq!(limited.stock_value(StockSymbol::new("VV")), dec!(2000.0));
+    /// assert_eq!(limited.stock_value(StockSymbol::new("BND")), dec!(1000.0));
+    /// ```
+    pub fn limit_sales(
+        &self,
+        current: &ShareValues,
+        target: ShareValues,
+        lots: &TaxLots,
+        quotes: &ShareValues,
+        as_of: NaiveDate,
+    ) -> ShareValues {
+        let max_tax_cost = match self.max_tax_cost {
+            Some(max_tax_cost) => max_tax_cost,
+            None => return target,
+        };
+        // The sale of each fund is split into the value and tax of each of its lots, in the order
+        // the lots are sold
+        let mut fund_sales = Vec::new();
+        for symbol in traded_symbols(current, &target) {
+            let sale = current.stock_value(symbol.clone()) - target.stock_value(symbol.clone());
+            let price = quotes.stock_value(symbol.clone());
+            if sale <= Decimal::ZERO || price <= Decimal::ZERO {
+                continue;
+            }
+            let mut lot_sales = lots
+                .sell(&symbol, sale / price, price, self.lot_selection, as_of)
+                .into_iter()
+                .map(|lot_sale| {
+                    (
+                        lot_sale.proceeds,
+                        self.rates.tax(lot_sale.gain(), lot_sale.holding_period),
+                    )
+                })
+                .collect::<Vec<(Decimal, Decimal)>>();
+            let without_lots = sale - lot_sales.iter().map(|(value, _)| value).sum::<Decimal>();
+            if without_lots > Decimal::ZERO {
+                lot_sales.push((without_lots, Decimal::ZERO));
+            }
+            lot_sales.reverse();
+            fund_sales.push((symbol, Decimal::ZERO, lot_sales));
+        }
+        let tax = fund_sales
+            .iter()
+            .flat_map(|(_, _, lot_sales)| lot_sales.iter().map(|(_, tax)| tax))
+            .sum::<Decimal>();
+        if tax <= max_tax_cost {
+            return target;
+        }
+
+        // Sell the next lot of the fund with the least tax per dollar until the tax cost is reached
+        let mut tax_left = max_tax_cost;
+        loop {
+            let next = fund_sales
+                .iter_mut()
+                .filter(|(_, _, lot_sales)| !lot_sales.is_empty())
+                .min_by_key(|(_, _, lot_sales)| {
+                    let (value, tax) = lot_sales.last().expect("Lot sales are not empty");
+                    tax.checked_div(*value).unwrap_or_default()
+                });
+            let (_, sold, lot_sales) = match next {
+                Some(next) => next,
+                None => break,
+            };
+            let (value, tax) = lot_sales.pop().expect("Lot sales are not empty");
+            if tax <= tax_left {
+                *sold += value;
+                tax_left -= tax;
+            } else {
+                *sold += value * tax_left / tax;
+                break;
+            }
+        }
+
+        let mut sold_values = current.clone();
+        let mut raised = Decimal::ZERO;
+        for (symbol, sold, _) in fund_sales {
+            let sold = round_dollars(sold);
+            raised += sold;
+            sold_values.add_stock_value(symbol.clone(), current.stock_value(symbol) - sold);
+        }
+        sold_values.add_cash(raised);
+        buy_only(&sold_values, target)
+    }
+}
+
+/// traded_symbols returns the funds of the current and target values, other than cash and the kept
+/// funds, which stay at their current value
+pub fn traded_symbols(current: &ShareValues, target: &ShareValues) -> Vec<StockSymbol> {
+    let universe = fund_universe();
+    let kept = current.kept().symbols();
+    let mut symbols = target.symbols();
+    for symbol in current.symbols() {
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    symbols.retain(|symbol| !universe.is_pooled_cash(symbol) && !kept.contains(symbol));
+    symbols
+}
+
+// level_trades splits the amount across the funds so that the funds with the largest gap trade
+// first, each trading down to the same remaining gap, and no fund trades more than its limit.  Each
+// fund is given as its gap and its limit.  The trades are rounded to the cent, where the rounding
+// is settled by the largest trade, and total less than the amount when the limits are reached.
+fn level_trades(gaps: &[(Decimal, Decimal)], amount: Decimal) -> Vec<Decimal> {
+    let trade = |level: Decimal, (gap, limit): &(Decimal, Decimal)| {
+        (*gap - level).max(Decimal::ZERO).min(*limit)
+    };
+    let total = |level: Decimal| gaps.iter().map(|gap| trade(level, gap)).sum::<Decimal>();
+    if amount <= Decimal::ZERO || gaps.is_empty() {
+        return vec![Decimal::ZERO; gaps.len()];
+    }
+
+    // The total traded only changes slope where a fund starts trading or reaches its limit, so the
+    // level is found between the two of these where the total passes the amount
+    let mut levels = gaps
+        .iter()
+        .flat_map(|(gap, limit)| [*gap, *gap - *limit])
+        .collect::<Vec<Decimal>>();
+    levels.sort_by(|level_a, level_b| level_b.cmp(level_a));
+    let mut level = *levels.last().expect("Levels are not empty");
+    for pair in levels.windows(2) {
+        let (above, below) = (pair[0], pair[1]);
+        let total_below = total(below);
+        if total_below >= amount {
+            let total_above = total(above);
+            level = if total_below == total_above {
+                below
+            } else {
+                above - (amount - total_above) * (above - below) / (total_below - total_above)
+            };
+            break;
+        }
+    }
+
+    let mut trades = gaps
+        .iter()
+        .map(|gap| round_dollars(trade(level, gap)))
+        .collect::<Vec<Decimal>>();
+    let residual = round_dollars(amount.min(total(level))) - trades.iter().sum::<Decimal>();
+    if let Some(largest) = trades
+        .iter_mut()
+        .max_by(|trade_a, trade_b| trade_a.cmp(trade_b))
+    {
+        if !largest.is_zero() {
+            *largest += residual;
+        }
+    }
+    trades
+}
@@ -0,0 +1,256 @@
+use crate::{
+    asset::Allocations,
+    glide::{GlidePath, GlidePoint},
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+/// DEFAULT_STRATEGY is the name of the strategy used when none is selected
+pub const DEFAULT_STRATEGY: &str = "glide_path";
+
+// Strategies listed when comparing the strategies of each owner
+const COMPARED_STRATEGIES: [AllocationStrategy; 5] = [
+    AllocationStrategy::GlidePath,
+    AllocationStrategy::AgeInBonds,
+    AllocationStrategy::MinusAge(110),
+    AllocationStrategy::MinusAge(120),
+    AllocationStrategy::BondTent,
+];
+
+/// Investor holds what the allocation strategies need to know about the owner of the retirement
+/// accounts
+pub struct Investor<'a> {
+    pub birth_year: Option<u32>,
+    pub retirement_year: Option<i32>,
+    pub glide_path: &'a GlidePath,
+}
+
+/// AllocationStrategy is a rule which produces the stock, bond and inflation protected allocation
+/// of the retirement accounts.
+/// - GlidePath follows the glide path to the retirement year
+/// - AgeInBonds holds the age as the bond percentage
+/// - MinusAge holds the base minus the age as the stock percentage, such as 110 or 120 minus age
+/// - BondTent raises bonds over the 10 years before retirement and lowers them over the 10 years
+///   after, to protect against a market drop around retirement
+/// - Fixed holds the same allocation at any age
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "String")]
+pub enum AllocationStrategy {
+    GlidePath,
+    AgeInBonds,
+    MinusAge(u32),
+    BondTent,
+    Fixed(Allocations),
+}
+
+impl AllocationStrategy {
+    /// allocations returns the allocation of the investor within this year
+    ///
+    /// # Errors
+    ///
+    /// Errors when the strategy needs a birth year or retirement year which the investor does not
+    /// have
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use vapore::{glide::GlidePath, strategy::{AllocationStrategy, Investor}};
+    ///
+    /// let glide_path = GlidePath::current();
+    /// let investor = Investor {
+    ///     birth_year: Some(1980),
+    ///     retirement_year: Some(2045),
+    ///     glide_path: &glide_path,
+    /// };
+    ///
+    /// let age_in_bonds = "age_in_bonds".parse::<AllocationStrategy>().unwrap();
+    /// assert_eq!(age_in_bonds.allocations(&investor, 2025).unwrap().total_bond(), dec!(45));
+    ///
+    /// let minus_age = "110_minus_age".parse::<AllocationStrategy>().unwrap();
+    /// assert_eq!(minus_age.allocations(&investor, 2025).unwrap().total_stock(), dec!(65));
+    ///
+    /// let bond_tent = "bond_tent".parse::<AllocationStrategy>().unwrap();
+    /// assert_eq!(bond_tent.allocations(&investor, 2045).unwrap().total_stock(), dec!(40));
+    ///
+    /// let fixed = "fixed:70/25/5".parse::<AllocationStrategy>().unwrap();
+    /// assert_eq!(fixed.allocations(&investor, 2025).unwrap().total_inflation_protected(), dec!(5));
+    /// ```
+    pub fn allocations(&self, investor: &Investor, this_year: i32) -> Result<Allocations> {
+        match self {
+            AllocationStrategy::GlidePath => match investor.retirement_year {
+                Some(_) => Ok(investor
+                    .glide_path
+                    .allocations_at(self.years_to_retirement(investor, this_year)?)),
+                None => Ok(Allocations::new()),
+            },
+            AllocationStrategy::AgeInBonds => {
+                let total_bond = Decimal::from(self.age(investor, this_year)?).min(dec!(100));
+                Allocations::custom(Decimal::ONE_HUNDRED - total_bond, total_bond, Decimal::ZERO)
+            }
+            AllocationStrategy::MinusAge(base) => {
+                let age = Decimal::from(self.age(investor, this_year)?);
+                let total_stock = (Decimal::from(*base) - age)
+                    .max(Decimal::ZERO)
+                    .min(Decimal::ONE_HUNDRED);
+                Allocations::custom(
+                    total_stock,
+                    Decimal::ONE_HUNDRED - total_stock,
+                    Decimal::ZERO,
+                )
+            }
+            AllocationStrategy::BondTent => {
+                Ok(bond_tent().allocations_at(self.years_to_retirement(investor, this_year)?))
+            }
+            AllocationStrategy::Fixed(allocations) => Ok(*allocations),
+        }
+    }
+
+    fn years_to_retirement(&self, investor: &Investor, this_year: i32) -> Result<i32> {
+        let retirement_year = investor
+            .retirement_year
+            .ok_or_else(|| anyhow!("The {} strategy needs a retirement year", self))?;
+        ensure!(
+            (2000..3000).contains(&retirement_year),
+            "Year needs to be between 2000 and 3000.  Year input: {}",
+            retirement_year
+        );
+        Ok(retirement_year - this_year)
+    }
+
+    fn age(&self, investor: &Investor, this_year: i32) -> Result<u32> {
+        let birth_year = investor
+            .birth_year
+            .ok_or_else(|| anyhow!("The {} strategy needs a birth year", self))?;
+        u32::try_from(this_year - birth_year as i32)
+            .map_err(|_| anyhow!("Birth year {} is after {}", birth_year, this_year))
+    }
+}
+
+// The bond tent holds 30% bonds until 10 years before retirement, peaks at 55% bonds and 5%
+// inflation protected at retirement, and settles back to 35% bonds 10 years after retirement
+fn bond_tent() -> GlidePath {
+    GlidePath::new(
+        "bond_tent",
+        vec![
+            GlidePoint::new(-10, dec!(60), dec!(35), dec!(5)),
+            GlidePoint::new(0, dec!(40), dec!(55), dec!(5)),
+            GlidePoint::new(10, dec!(70), dec!(30), dec!(0)),
+        ],
+    )
+    .expect("Bond tent percentages add up to 100")
+}
+
+impl FromStr for AllocationStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(strategy: &str) -> Result<Self> {
+        match strategy {
+            "glide_path" => Ok(AllocationStrategy::GlidePath),
+            "age_in_bonds" => Ok(AllocationStrategy::AgeInBonds),
+            "bond_tent" => Ok(AllocationStrategy::BondTent),
+            _ => {
+                if let Some(base) = strategy.strip_suffix("_minus_age") {
+                    let base = base
+                        .parse::<u32>()
+                        .with_context(|| format!("Invalid strategy: {}", strategy))?;
+                    Ok(AllocationStrategy::MinusAge(base))
+                } else if let Some(percents) = strategy.strip_prefix("fixed:") {
+                    let percents = percents
+                        .split('/')
+                        .map(|percent| percent.trim().parse::<Decimal>())
+                        .collect::<Result<Vec<Decimal>, _>>()
+                        .with_context(|| format!("Invalid strategy: {}", strategy))?;
+                    ensure!(
+                        (2..=3).contains(&percents.len()),
+                        "Fixed strategy {} needs stock/bond or stock/bond/inflation protected \
+                        percentages",
+                        strategy
+                    );
+                    Ok(AllocationStrategy::Fixed(Allocations::custom(
+                        percents[0],
+                        percents[1],
+                        percents.get(2).copied().unwrap_or_default(),
+                    )?))
+                } else {
+                    Err(anyhow!(
+                        "Unknown strategy {}.  Strategies are glide_path, age_in_bonds, \
+                        <base>_minus_age, bond_tent and fixed:<stock>/<bond>[/<inflation>]",
+                        strategy
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for AllocationStrategy {
+    type Error = anyhow::Error;
+
+    fn try_from(strategy: String) -> Result<Self> {
+        strategy.parse()
+    }
+}
+
+impl fmt::Display for AllocationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocationStrategy::GlidePath => write!(f, "glide_path"),
+            AllocationStrategy::AgeInBonds => write!(f, "age_in_bonds"),
+            AllocationStrategy::MinusAge(base) => write!(f, "{}_minus_age", base),
+            AllocationStrategy::BondTent => write!(f, "bond_tent"),
+            AllocationStrategy::Fixed(allocations) => write!(
+                f,
+                "fixed:{}/{}/{}",
+                allocations.total_stock(),
+                allocations.total_bond(),
+                allocations.total_inflation_protected()
+            ),
+        }
+    }
+}
+
+/// compare_strategies returns a table of the allocation each strategy produces for the investor
+/// within this year.  Strategies which need a birth year or retirement year the investor does not
+/// have are marked as such.
+///
+/// # Example
+///
+/// ```
+/// use vapore::{glide::GlidePath, strategy::{compare_strategies, Investor}};
+///
+/// let glide_path = GlidePath::current();
+/// let investor = Investor {
+///     birth_year: Some(1980),
+///     retirement_year: None,
+///     glide_path: &glide_path,
+/// };
+///
+/// let comparison = compare_strategies(&investor, 2025);
+/// assert!(comparison.contains("120_minus_age     75.0    25.0    0.0"));
+/// assert!(comparison.contains("bond_tent         The bond_tent strategy needs a retirement year"));
+/// ```
+pub fn compare_strategies(investor: &Investor, this_year: i32) -> String {
+    let mut rows = String::new();
+    for strategy in COMPARED_STRATEGIES {
+        let row = match strategy.allocations(investor, this_year) {
+            Ok(allocations) => format!(
+                "{:<8.1}{:<8.1}{:.1}",
+                allocations.total_stock(),
+                allocations.total_bond(),
+                allocations.total_inflation_protected()
+            ),
+            Err(error) => error.to_string(),
+        };
+        rows.push_str(&format!("{:<18}{}\n", strategy.to_string(), row));
+    }
+    format!(
+        "Strategy          Stock   Bond    Inflation\n\
+        -------------------------------------------\n\
+        {}",
+        rows
+    )
+}
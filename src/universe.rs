@@ -0,0 +1,593 @@
+use crate::holdings::StockSymbol;
+use anyhow::{ensure, Context, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    sync::OnceLock,
+};
+
+// DEFAULT_FUND_UNIVERSE is the built-in fund lineup used when no fund universe file is passed
+const DEFAULT_FUND_UNIVERSE: &str = include_str!("../fund_universe.toml");
+
+// FUND_UNIVERSE holds the fund universe for the run.  It is set once at startup from the fund
+// universe file, otherwise the built-in default is used.
+static FUND_UNIVERSE: OnceLock<FundUniverse> = OnceLock::new();
+
+/// AssetClass is the broad class of an asset.  Blend is used for funds which hold a mix of asset
+/// classes, such as target date retirement funds.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    Stock,
+    Bond,
+    InflationProtected,
+    Cash,
+    Blend,
+}
+
+/// Region is the geographical region that a fund invests within.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    Us,
+    International,
+    Emerging,
+    Global,
+}
+
+/// CapSize is the market capitalization of the companies held within a stock fund.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CapSize {
+    Large,
+    Mid,
+    Small,
+    Total,
+}
+
+/// FundType is how a fund trades.  ETFs trade in shares throughout the day, where mutual funds are
+/// bought and sold in dollar amounts at the next closing net asset value (NAV).
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FundType {
+    #[default]
+    Etf,
+    MutualFund,
+}
+
+/// TaxCharacter is how the distributions of a fund are taxed, which decides the accounts that a
+/// fund is best placed within.
+/// - Qualified dividends, such as those of broad stock funds, are taxed at the lower capital gains
+///   rates and are the most efficient within a taxable account
+/// - Interest from taxable bonds is taxed as ordinary income
+/// - NonQualified dividends, such as those of REITs, are taxed as ordinary income and are placed
+///   within tax-advantaged accounts when possible
+/// - TaxExempt interest, such as that of municipal bonds, is not taxed, which is wasted within a
+///   tax-advantaged account, so these funds are only held within taxable accounts
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxCharacter {
+    #[default]
+    Qualified,
+    Interest,
+    NonQualified,
+    TaxExempt,
+}
+
+/// FundInfo holds the metadata for a single fund within the fund universe.  Blended funds, such as
+/// target date or total market funds, carry a composition of the percentage held within each sub
+/// class, which is used to look through the fund to its underlying exposure.  Mutual fund share
+/// classes, such as admiral shares, name their ETF equivalent and are treated as members of the
+/// same asset class, where the ETF equivalent fills the rebalance targets.  Substitutes, which track
+/// a different index of the same sub class and are bought when harvesting a loss, name the fund
+/// they substitute for and are treated as members of its sub class in the same way.  The tax
+/// character of the distributions is used to place the fund within the accounts.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FundInfo {
+    pub symbol: StockSymbol,
+    pub description: String,
+    pub asset_class: AssetClass,
+    pub region: Region,
+    #[serde(default)]
+    pub cap_size: Option<CapSize>,
+    #[serde(default)]
+    pub sub_class: Option<String>,
+    #[serde(default)]
+    pub risk_rank: Option<u32>,
+    #[serde(default)]
+    quote_symbol: Option<String>,
+    #[serde(default)]
+    pub composition: Option<BTreeMap<String, Decimal>>,
+    #[serde(default)]
+    pub fund_type: FundType,
+    #[serde(default)]
+    pub etf_equivalent: Option<StockSymbol>,
+    #[serde(default)]
+    pub substitute_for: Option<StockSymbol>,
+    #[serde(default)]
+    pub minimum_initial_purchase: Option<Decimal>,
+    #[serde(default)]
+    pub tax_character: TaxCharacter,
+}
+
+impl FundInfo {
+    /// quote_symbol returns the symbol used to retrieve quotes for the fund.  Defaults to the fund
+    /// symbol when one is not set.
+    pub fn quote_symbol(&self) -> String {
+        self.quote_symbol
+            .clone()
+            .unwrap_or_else(|| self.symbol.to_string())
+    }
+}
+
+/// FundUniverse holds all funds known to vapore along with their metadata.  The order of the
+/// funds is the order in which they are displayed.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FundUniverse {
+    #[serde(rename = "fund")]
+    funds: Vec<FundInfo>,
+    #[serde(skip)]
+    kept_money_market: Option<StockSymbol>,
+}
+
+impl FundUniverse {
+    /// from_toml parses and validates a fund universe from the contents of a TOML file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{holdings::StockSymbol, universe::{AssetClass, FundUniverse}};
+    ///
+    /// let universe = FundUniverse::from_toml(
+    ///     r#"
+    ///     [[fund]]
+    ///     symbol = "SCHX"
+    ///     description = "US large cap"
+    ///     asset_class = "stock"
+    ///     region = "us"
+    ///     cap_size = "large"
+    ///     sub_class = "us_stock_large"
+    ///     risk_rank = 1
+    ///
+    ///     [[fund]]
+    ///     symbol = "VMFXX"
+    ///     description = "Federal money market"
+    ///     asset_class = "cash"
+    ///     region = "us"
+    ///     "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let schx = universe.fund(&StockSymbol::new("SCHX")).unwrap();
+    /// assert_eq!(schx.asset_class, AssetClass::Stock);
+    /// assert_eq!(universe.cash_symbol(), StockSymbol::new("VMFXX"));
+    /// ```
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let universe: FundUniverse =
+            toml::from_str(contents).context("Unable to parse fund universe")?;
+        universe.validate()?;
+        Ok(universe)
+    }
+
+    /// from_file reads a fund universe TOML file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Fund universe file not found: {}", path))?;
+        Self::from_toml(&contents).with_context(|| format!("Invalid fund universe: {}", path))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut symbols = HashSet::new();
+        let mut risk_ranks = HashSet::new();
+        for fund in &self.funds {
+            ensure!(
+                !fund.symbol.is_empty(),
+                "Fund universe contains an empty symbol"
+            );
+            ensure!(
+                symbols.insert(fund.symbol.clone()),
+                "{} is listed more than once within the fund universe",
+                fund.symbol
+            );
+            if let Some(risk_rank) = fund.risk_rank {
+                ensure!(
+                    risk_ranks.insert(risk_rank),
+                    "Risk rank {} of {} is used by more than one fund",
+                    risk_rank,
+                    fund.symbol
+                );
+            }
+            ensure!(
+                fund.asset_class != AssetClass::Cash
+                    || (fund.sub_class.is_none() && fund.risk_rank.is_none()),
+                "Cash fund {} cannot have a sub class or risk rank",
+                fund.symbol
+            );
+        }
+        ensure!(
+            self.funds
+                .iter()
+                .any(|fund| fund.asset_class == AssetClass::Cash),
+            "Fund universe needs a cash fund to hold uninvested cash"
+        );
+        for fund in &self.funds {
+            if let Some(composition) = &fund.composition {
+                ensure!(
+                    fund.sub_class.is_none() && fund.risk_rank.is_none(),
+                    "Blended fund {} cannot have a sub class or risk rank",
+                    fund.symbol
+                );
+                for sub_class in composition.keys() {
+                    ensure!(
+                        self.sub_class_symbol(sub_class).is_some(),
+                        "Composition of {} contains {}, which is not the sub class of any fund",
+                        fund.symbol,
+                        sub_class
+                    );
+                }
+                let total = composition.values().sum::<Decimal>();
+                ensure!(
+                    total == Decimal::ONE_HUNDRED,
+                    "Composition of {} does not add up to 100: {}",
+                    fund.symbol,
+                    total
+                );
+            }
+            if let Some(minimum) = fund.minimum_initial_purchase {
+                ensure!(
+                    fund.fund_type == FundType::MutualFund && !minimum.is_sign_negative(),
+                    "Minimum initial purchase of {} needs to be a positive value on a mutual fund",
+                    fund.symbol
+                );
+            }
+            if let Some(etf_symbol) = &fund.etf_equivalent {
+                ensure!(
+                    fund.fund_type == FundType::MutualFund,
+                    "{} has an ETF equivalent but is not a mutual fund",
+                    fund.symbol
+                );
+                ensure!(
+                    fund.sub_class.is_none()
+                        && fund.risk_rank.is_none()
+                        && fund.composition.is_none()
+                        && fund.tax_character == TaxCharacter::default(),
+                    "Mutual fund {} takes its sub class, risk rank, composition and tax character \
+                    from its ETF equivalent {}",
+                    fund.symbol,
+                    etf_symbol
+                );
+                let etf = self.fund(etf_symbol).with_context(|| {
+                    format!(
+                        "ETF equivalent {} of {} is not within the fund universe",
+                        etf_symbol, fund.symbol
+                    )
+                })?;
+                ensure!(
+                    etf.fund_type == FundType::Etf,
+                    "ETF equivalent {} of {} is not an ETF",
+                    etf_symbol,
+                    fund.symbol
+                );
+                ensure!(
+                    etf.asset_class == fund.asset_class && etf.region == fund.region,
+                    "{} does not have the same asset class and region as its ETF equivalent {}",
+                    fund.symbol,
+                    etf_symbol
+                );
+            }
+            if let Some(model_symbol) = &fund.substitute_for {
+                ensure!(
+                    fund.etf_equivalent.is_none(),
+                    "{} cannot have both an ETF equivalent and be a substitute",
+                    fund.symbol
+                );
+                ensure!(
+                    fund.sub_class.is_none()
+                        && fund.risk_rank.is_none()
+                        && fund.composition.is_none()
+                        && fund.tax_character == TaxCharacter::default(),
+                    "Substitute {} takes its sub class, risk rank, composition and tax character \
+                    from {}",
+                    fund.symbol,
+                    model_symbol
+                );
+                let model = self.fund(model_symbol).with_context(|| {
+                    format!(
+                        "{} which {} substitutes for is not within the fund universe",
+                        model_symbol, fund.symbol
+                    )
+                })?;
+                ensure!(
+                    model.etf_equivalent.is_none() && model.substitute_for.is_none(),
+                    "{} substitutes for {}, which is not a model fund",
+                    fund.symbol,
+                    model_symbol
+                );
+                ensure!(
+                    model.asset_class == fund.asset_class && model.region == fund.region,
+                    "{} does not have the same asset class and region as {}, which it substitutes \
+                    for",
+                    fund.symbol,
+                    model_symbol
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// funds returns all funds within the universe in display order
+    pub fn funds(&self) -> &[FundInfo] {
+        &self.funds
+    }
+
+    /// fund returns the fund information for the stock symbol if it is within the universe
+    pub fn fund(&self, symbol: &StockSymbol) -> Option<&FundInfo> {
+        self.funds.iter().find(|fund| &fund.symbol == symbol)
+    }
+
+    /// contains returns whether or not the stock symbol is within the universe
+    pub fn contains(&self, symbol: &StockSymbol) -> bool {
+        self.fund(symbol).is_some()
+    }
+
+    /// asset_class returns the asset class of the stock symbol if it is within the universe
+    pub fn asset_class(&self, symbol: &StockSymbol) -> Option<AssetClass> {
+        self.fund(symbol).map(|fund| fund.asset_class)
+    }
+
+    /// composition returns the percentage held within each sub class for a blended fund.  None is
+    /// returned for funds without a composition.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use vapore::{holdings::StockSymbol, universe::fund_universe};
+    ///
+    /// let vti = fund_universe().composition(&StockSymbol::new("VTI")).unwrap();
+    /// assert_eq!(vti.values().sum::<Decimal>(), Decimal::ONE_HUNDRED);
+    /// assert!(fund_universe().composition(&StockSymbol::new("VV")).is_none());
+    /// ```
+    pub fn composition(&self, symbol: &StockSymbol) -> Option<&BTreeMap<String, Decimal>> {
+        self.model_fund(symbol)
+            .and_then(|fund| fund.composition.as_ref())
+    }
+
+    /// model_fund returns the fund which represents the stock symbol within the rebalance model.
+    /// This is the ETF equivalent for mutual fund share classes, the fund substituted for by a
+    /// substitute, and the fund itself otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{holdings::StockSymbol, universe::fund_universe};
+    ///
+    /// let model_symbol = |symbol| {
+    ///     fund_universe().model_fund(&StockSymbol::new(symbol)).unwrap().symbol.to_string()
+    /// };
+    /// assert_eq!(model_symbol("VLCAX"), "VV");
+    /// assert_eq!(model_symbol("SCHX"), "VV");
+    /// assert_eq!(model_symbol("VV"), "VV");
+    /// ```
+    pub fn model_fund(&self, symbol: &StockSymbol) -> Option<&FundInfo> {
+        let fund = self.fund(symbol)?;
+        let model_symbol = fund
+            .etf_equivalent
+            .as_ref()
+            .or(fund.substitute_for.as_ref());
+        match model_symbol {
+            Some(model_symbol) => self.fund(model_symbol),
+            None => Some(fund),
+        }
+    }
+
+    /// identical_fund returns the fund which the stock symbol is substantially identical to for
+    /// wash sales.  This is the ETF equivalent for mutual fund share classes and the fund itself
+    /// otherwise, where a substitute tracks a different index and is not identical to its fund.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{holdings::StockSymbol, universe::fund_universe};
+    ///
+    /// let identical_symbol = |symbol| {
+    ///     fund_universe().identical_fund(&StockSymbol::new(symbol)).unwrap().symbol.to_string()
+    /// };
+    /// assert_eq!(identical_symbol("VLCAX"), "VV");
+    /// assert_eq!(identical_symbol("SCHX"), "SCHX");
+    /// ```
+    pub fn identical_fund(&self, symbol: &StockSymbol) -> Option<&FundInfo> {
+        let fund = self.fund(symbol)?;
+        match &fund.etf_equivalent {
+            Some(etf_symbol) => self.fund(etf_symbol),
+            None => Some(fund),
+        }
+    }
+
+    /// tax_character returns the tax character of the stock symbol, which is taken from the ETF
+    /// equivalent of mutual fund share classes.  Symbols outside of the universe are treated as
+    /// paying qualified dividends.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{holdings::StockSymbol, universe::{fund_universe, TaxCharacter}};
+    ///
+    /// let universe = fund_universe();
+    /// assert_eq!(universe.tax_character(&StockSymbol::new("VTEB")), TaxCharacter::TaxExempt);
+    /// assert_eq!(universe.tax_character(&StockSymbol::new("VNQ")), TaxCharacter::NonQualified);
+    /// assert_eq!(universe.tax_character(&StockSymbol::new("VBTLX")), TaxCharacter::Interest);
+    /// assert_eq!(universe.tax_character(&StockSymbol::new("VV")), TaxCharacter::Qualified);
+    /// ```
+    pub fn tax_character(&self, symbol: &StockSymbol) -> TaxCharacter {
+        self.model_fund(symbol)
+            .map(|fund| fund.tax_character)
+            .unwrap_or_default()
+    }
+
+    /// is_mutual_fund returns whether or not the stock symbol is a mutual fund within the universe
+    pub fn is_mutual_fund(&self, symbol: &StockSymbol) -> bool {
+        self.fund(symbol)
+            .map(|fund| fund.fund_type == FundType::MutualFund)
+            .unwrap_or(false)
+    }
+
+    /// mutual_fund_share_classes returns the mutual funds which have the stock symbol as their ETF
+    /// equivalent
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{holdings::StockSymbol, universe::fund_universe};
+    ///
+    /// let share_classes = fund_universe().mutual_fund_share_classes(&StockSymbol::new("BND"));
+    /// assert_eq!(share_classes[0].symbol, StockSymbol::new("VBTLX"));
+    /// ```
+    pub fn mutual_fund_share_classes(&self, etf_symbol: &StockSymbol) -> Vec<&FundInfo> {
+        self.funds
+            .iter()
+            .filter(|fund| fund.etf_equivalent.as_ref() == Some(etf_symbol))
+            .collect()
+    }
+
+    /// model_members returns the mutual fund share classes and substitutes which have the stock
+    /// symbol as their model fund
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{holdings::StockSymbol, universe::fund_universe};
+    ///
+    /// let members = fund_universe()
+    ///     .model_members(&StockSymbol::new("BND"))
+    ///     .iter()
+    ///     .map(|fund| fund.symbol.to_string())
+    ///     .collect::<Vec<String>>();
+    /// assert_eq!(members, vec!["VBTLX", "AGG"]);
+    /// ```
+    pub fn model_members(&self, model_symbol: &StockSymbol) -> Vec<&FundInfo> {
+        self.funds
+            .iter()
+            .filter(|fund| {
+                fund.etf_equivalent.as_ref() == Some(model_symbol)
+                    || fund.substitute_for.as_ref() == Some(model_symbol)
+            })
+            .collect()
+    }
+
+    /// keep_money_market keeps the money market fund as a deliberate holding at its current value
+    /// rather than pooling it as investable cash.  The symbol is taken as a string since this is
+    /// set before the fund universe is in use.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{holdings::StockSymbol, universe::FundUniverse};
+    ///
+    /// let mut universe = FundUniverse::default();
+    /// assert!(universe.is_pooled_cash(&StockSymbol::new("VUSXX")));
+    ///
+    /// universe.keep_money_market("VUSXX").unwrap();
+    /// assert!(!universe.is_pooled_cash(&StockSymbol::new("VUSXX")));
+    /// assert!(universe.keep_money_market("VMFXX").is_err());
+    /// ```
+    pub fn keep_money_market(&mut self, symbol: &str) -> Result<()> {
+        let symbol = self
+            .funds
+            .iter()
+            .find(|fund| fund.symbol.to_string() == symbol && fund.asset_class == AssetClass::Cash)
+            .map(|fund| fund.symbol.clone())
+            .with_context(|| {
+                format!(
+                    "{} is not a money market fund within the fund universe",
+                    symbol
+                )
+            })?;
+        ensure!(
+            symbol != self.cash_symbol(),
+            "{} is the settlement fund, which holds the investable cash and cannot be kept",
+            symbol
+        );
+        self.kept_money_market = Some(symbol);
+        Ok(())
+    }
+
+    /// kept_money_market returns the money market fund kept as a holding, if any
+    pub fn kept_money_market(&self) -> Option<&StockSymbol> {
+        self.kept_money_market.as_ref()
+    }
+
+    /// is_pooled_cash returns whether or not the stock symbol is a money market fund which is
+    /// pooled as investable cash
+    pub fn is_pooled_cash(&self, symbol: &StockSymbol) -> bool {
+        self.asset_class(symbol) == Some(AssetClass::Cash)
+            && self.kept_money_market.as_ref() != Some(symbol)
+    }
+
+    /// cash_symbol returns the symbol of the settlement fund, which is the first cash fund within
+    /// the universe.  Added cash is placed within this fund.
+    pub fn cash_symbol(&self) -> StockSymbol {
+        self.funds
+            .iter()
+            .find(|fund| fund.asset_class == AssetClass::Cash)
+            .map(|fund| fund.symbol.clone())
+            .expect("Fund universe validated to contain a cash fund")
+    }
+
+    /// sub_class_symbol returns the symbol of the first fund which fills the sub class
+    pub fn sub_class_symbol(&self, sub_class: &str) -> Option<StockSymbol> {
+        self.funds
+            .iter()
+            .find(|fund| fund.sub_class.as_deref() == Some(sub_class))
+            .map(|fund| fund.symbol.clone())
+    }
+
+    /// high_to_low_risk returns the symbols of all ranked funds ordered from the highest to the
+    /// lowest risk.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vapore::{holdings::StockSymbol, universe::FundUniverse};
+    ///
+    /// let universe = FundUniverse::default();
+    /// let risk_order = universe.high_to_low_risk();
+    /// assert_eq!(risk_order.first(), Some(&StockSymbol::new("VWO")));
+    /// assert_eq!(risk_order.last(), Some(&StockSymbol::new("VTIP")));
+    /// ```
+    pub fn high_to_low_risk(&self) -> Vec<StockSymbol> {
+        let mut ranked = self
+            .funds
+            .iter()
+            .filter_map(|fund| fund.risk_rank.map(|rank| (rank, fund.symbol.clone())))
+            .collect::<Vec<(u32, StockSymbol)>>();
+        ranked.sort_by_key(|(rank, _)| *rank);
+        ranked.into_iter().map(|(_, symbol)| symbol).collect()
+    }
+}
+
+impl Default for FundUniverse {
+    fn default() -> Self {
+        Self::from_toml(DEFAULT_FUND_UNIVERSE).expect("Built-in fund universe is invalid")
+    }
+}
+
+/// fund_universe returns the fund universe for this run.  The built-in default universe is used
+/// if one was not set with set_fund_universe.
+pub fn fund_universe() -> &'static FundUniverse {
+    FUND_UNIVERSE.get_or_init(FundUniverse::default)
+}
+
+/// set_fund_universe sets the fund universe used for the rest of the run.  This needs to be
+/// called before any holdings are parsed.
+pub fn set_fund_universe(universe: FundUniverse) -> Result<()> {
+    ensure!(
+        FUND_UNIVERSE.set(universe).is_ok(),
+        "Fund universe was already set before loading the fund universe file"
+    );
+    Ok(())
+}